@@ -1,9 +1,21 @@
 CREATE DATABASE IF NOT EXISTS bodega;
 USE bodega;
+-- La base de datos es nueva si todavía no tiene categorías; se mira antes de crear nada
+SET @nueva = (SELECT COUNT(*) = 0 FROM information_schema.TABLES
+    WHERE TABLE_SCHEMA = 'bodega' AND TABLE_NAME = 'categorias');
+CREATE TABLE IF NOT EXISTS hogares (
+                                       id BIGINT AUTO_INCREMENT PRIMARY KEY NOT NULL,
+                                       nombre VARCHAR(255) NOT NULL UNIQUE
+);
+
 CREATE TABLE IF NOT EXISTS categorias (
                                           id BIGINT AUTO_INCREMENT PRIMARY KEY NOT NULL ,
                                           nombre VARCHAR(255) NOT NULL,
-                                          descripcion TEXT
+                                          descripcion TEXT,
+                                          hogar BIGINT NOT NULL,
+                                          padre BIGINT NULL,
+                                          FOREIGN KEY (hogar) REFERENCES hogares(id),
+                                          FOREIGN KEY (padre) REFERENCES categorias(id)
 );
 
 CREATE TABLE IF NOT EXISTS objetos (
@@ -11,6 +23,7 @@ CREATE TABLE IF NOT EXISTS objetos (
                                        categoria BIGINT NOT NULL,
                                        nombre VARCHAR(255) NOT NULL,
                                        medida VARCHAR(255) NOT NULL,
+                                       envase DOUBLE NULL,
                                        FOREIGN KEY (categoria) REFERENCES categorias(id)
 );
 
@@ -24,4 +37,153 @@ CREATE TABLE IF NOT EXISTS existencias_tara (
                                                 id_objeto BIGINT PRIMARY KEY NOT NULL,
                                                 cantidad DOUBLE(8,2),
                                                 FOREIGN KEY (id_objeto)  REFERENCES objetos(id)
-);
\ No newline at end of file
+);
+
+CREATE TABLE IF NOT EXISTS usuarios (
+                                        id BIGINT AUTO_INCREMENT PRIMARY KEY NOT NULL,
+                                        nombre VARCHAR(255) NOT NULL UNIQUE,
+                                        hash VARCHAR(255) NOT NULL,
+                                        rol VARCHAR(16) NOT NULL DEFAULT 'lectura'
+);
+
+CREATE TABLE IF NOT EXISTS miembros (
+                                        id_usuario BIGINT NOT NULL,
+                                        id_hogar BIGINT NOT NULL,
+                                        rol VARCHAR(16) NOT NULL DEFAULT 'lectura',
+                                        PRIMARY KEY (id_usuario, id_hogar),
+                                        FOREIGN KEY (id_usuario) REFERENCES usuarios(id) ON DELETE CASCADE,
+                                        FOREIGN KEY (id_hogar) REFERENCES hogares(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS movimientos (
+                                           id BIGINT AUTO_INCREMENT PRIMARY KEY NOT NULL,
+                                           id_objeto BIGINT NOT NULL,
+                                           lugar VARCHAR(16) NOT NULL,
+                                           cantidad DOUBLE(8,2) NOT NULL,
+                                           tipo VARCHAR(32) NOT NULL,
+                                           id_usuario BIGINT NOT NULL,
+                                           fecha DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                                           FOREIGN KEY (id_objeto) REFERENCES objetos(id) ON DELETE CASCADE,
+                                           FOREIGN KEY (id_usuario) REFERENCES usuarios(id)
+);
+
+CREATE TABLE IF NOT EXISTS raciones (
+                                        id_objeto BIGINT PRIMARY KEY NOT NULL,
+                                        por_persona_dia DOUBLE(8,3) NOT NULL,
+                                        FOREIGN KEY (id_objeto) REFERENCES objetos(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS recetas (
+                                       id BIGINT AUTO_INCREMENT PRIMARY KEY NOT NULL,
+                                       nombre VARCHAR(255) NOT NULL,
+                                       raciones INT NOT NULL DEFAULT 1,
+                                       hogar BIGINT NOT NULL,
+                                       FOREIGN KEY (hogar) REFERENCES hogares(id)
+);
+
+CREATE TABLE IF NOT EXISTS ingredientes (
+                                            id_receta BIGINT NOT NULL,
+                                            id_objeto BIGINT NOT NULL,
+                                            cantidad DOUBLE(8,3) NOT NULL,
+                                            PRIMARY KEY (id_receta, id_objeto),
+                                            FOREIGN KEY (id_receta) REFERENCES recetas(id) ON DELETE CASCADE,
+                                            FOREIGN KEY (id_objeto) REFERENCES objetos(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS codigos (
+                                       codigo VARCHAR(14) NOT NULL,
+                                       id_objeto BIGINT NOT NULL,
+                                       PRIMARY KEY (codigo, id_objeto),
+                                       FOREIGN KEY (id_objeto) REFERENCES objetos(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS precios (
+                                       id BIGINT AUTO_INCREMENT PRIMARY KEY NOT NULL,
+                                       id_objeto BIGINT NOT NULL,
+                                       lugar VARCHAR(16) NOT NULL,
+                                       cantidad DOUBLE(8,2) NOT NULL,
+                                       precio DOUBLE(10,2) NOT NULL,
+                                       id_usuario BIGINT NOT NULL,
+                                       fecha DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                                       FOREIGN KEY (id_objeto) REFERENCES objetos(id) ON DELETE CASCADE,
+                                       FOREIGN KEY (id_usuario) REFERENCES usuarios(id)
+);
+
+CREATE TABLE IF NOT EXISTS tiendas (
+                                       id BIGINT AUTO_INCREMENT PRIMARY KEY NOT NULL,
+                                       nombre VARCHAR(255) NOT NULL,
+                                       notas VARCHAR(255) NOT NULL DEFAULT '',
+                                       hogar BIGINT NOT NULL,
+                                       FOREIGN KEY (hogar) REFERENCES hogares(id)
+);
+
+CREATE TABLE IF NOT EXISTS proveedores (
+                                           id_objeto BIGINT NOT NULL,
+                                           id_tienda BIGINT NOT NULL,
+                                           preferida BOOLEAN NOT NULL DEFAULT FALSE,
+                                           PRIMARY KEY (id_objeto, id_tienda),
+                                           FOREIGN KEY (id_objeto) REFERENCES objetos(id) ON DELETE CASCADE,
+                                           FOREIGN KEY (id_tienda) REFERENCES tiendas(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS etiquetas (
+                                          id BIGINT AUTO_INCREMENT PRIMARY KEY NOT NULL,
+                                          nombre VARCHAR(255) NOT NULL,
+                                          hogar BIGINT NOT NULL,
+                                          FOREIGN KEY (hogar) REFERENCES hogares(id)
+);
+
+CREATE TABLE IF NOT EXISTS objetos_etiquetas (
+                                                 id_objeto BIGINT NOT NULL,
+                                                 id_etiqueta BIGINT NOT NULL,
+                                                 PRIMARY KEY (id_objeto, id_etiqueta),
+                                                 FOREIGN KEY (id_objeto) REFERENCES objetos(id) ON DELETE CASCADE,
+                                                 FOREIGN KEY (id_etiqueta) REFERENCES etiquetas(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS ubicaciones (
+                                           id BIGINT AUTO_INCREMENT PRIMARY KEY NOT NULL,
+                                           nombre VARCHAR(255) NOT NULL,
+                                           lugar VARCHAR(16) NOT NULL,
+                                           padre BIGINT NULL,
+                                           hogar BIGINT NOT NULL,
+                                           tipo VARCHAR(16) NOT NULL DEFAULT 'despensa',
+                                           FOREIGN KEY (padre) REFERENCES ubicaciones(id),
+                                           FOREIGN KEY (hogar) REFERENCES hogares(id)
+);
+
+CREATE TABLE IF NOT EXISTS existencias_ubicaciones (
+                                                       id_objeto BIGINT NOT NULL,
+                                                       id_ubicacion BIGINT NOT NULL,
+                                                       cantidad DOUBLE(8,2) NOT NULL,
+                                                       PRIMARY KEY (id_objeto, id_ubicacion),
+                                                       FOREIGN KEY (id_objeto) REFERENCES objetos(id) ON DELETE CASCADE,
+                                                       FOREIGN KEY (id_ubicacion) REFERENCES ubicaciones(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS conservacion (
+                                            id_objeto BIGINT NOT NULL,
+                                            tipo VARCHAR(16) NOT NULL,
+                                            dias INT NOT NULL,
+                                            PRIMARY KEY (id_objeto, tipo),
+                                            FOREIGN KEY (id_objeto) REFERENCES objetos(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS lotes (
+                                     id BIGINT AUTO_INCREMENT PRIMARY KEY NOT NULL,
+                                     id_objeto BIGINT NOT NULL,
+                                     lugar VARCHAR(16) NOT NULL,
+                                     tipo VARCHAR(16) NOT NULL,
+                                     cantidad DOUBLE(8,2) NOT NULL,
+                                     caduca DATE NOT NULL,
+                                     fecha DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                                     FOREIGN KEY (id_objeto) REFERENCES objetos(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS esquema (
+                                       id INT PRIMARY KEY NOT NULL,
+                                       version INT NOT NULL
+);
+-- Solo se guarda la primera vez. Una base de datos nueva ya tiene el esquema actual; una que ya existía de antes de
+-- la tabla esquema empieza en la 0, y la actualizan los scripts de upgrades desde el 01
+INSERT IGNORE INTO esquema (id, version) VALUES (1, IF(@nueva, 14, 0));
@@ -0,0 +1,10 @@
+USE bodega;
+-- Los usuarios que ya existían podían hacerlo todo, así que pasan a ser administradores
+-- Si la tabla usuarios se creó con el init-db.sql de esta versión, ya tiene la columna y no se toca
+SET @falta = (SELECT COUNT(*) = 0 FROM information_schema.COLUMNS
+    WHERE TABLE_SCHEMA = 'bodega' AND TABLE_NAME = 'usuarios' AND COLUMN_NAME = 'rol');
+SET @sentencia = IF(@falta, 'ALTER TABLE usuarios ADD COLUMN rol VARCHAR(16) NOT NULL DEFAULT ''admin''', 'DO 0');
+PREPARE sentencia FROM @sentencia;
+EXECUTE sentencia;
+DEALLOCATE PREPARE sentencia;
+ALTER TABLE usuarios ALTER COLUMN rol SET DEFAULT 'lectura';
@@ -0,0 +1,9 @@
+USE bodega;
+-- Requiere haber vuelto a ejecutar init-db.sql, que crea las tablas hogares y miembros.
+-- Todo lo que ya había pasa a un primer hogar, del que son miembros todos los usuarios
+INSERT INTO hogares (nombre) VALUES ('Principal');
+ALTER TABLE categorias ADD COLUMN hogar BIGINT NULL;
+UPDATE categorias SET hogar = (SELECT id FROM hogares WHERE nombre = 'Principal');
+ALTER TABLE categorias MODIFY hogar BIGINT NOT NULL, ADD FOREIGN KEY (hogar) REFERENCES hogares(id);
+INSERT INTO miembros (id_usuario, id_hogar)
+    SELECT u.id, h.id FROM usuarios u, hogares h WHERE h.nombre = 'Principal';
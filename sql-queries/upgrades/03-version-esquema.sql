@@ -0,0 +1,4 @@
+USE bodega;
+-- Requiere haber vuelto a ejecutar init-db.sql, que crea la tabla esquema.
+-- A partir de aquí, cada script que cambia el esquema actualiza la versión, que comprueba bodega-manager doctor
+INSERT INTO esquema (id, version) VALUES (1, 3) ON DUPLICATE KEY UPDATE version = 3;
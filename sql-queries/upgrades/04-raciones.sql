@@ -0,0 +1,3 @@
+USE bodega;
+-- Requiere haber vuelto a ejecutar init-db.sql, que crea la tabla raciones.
+UPDATE esquema SET version = 4 WHERE id = 1;
@@ -0,0 +1,3 @@
+USE bodega;
+-- Requiere haber vuelto a ejecutar init-db.sql, que crea las tablas recetas e ingredientes.
+UPDATE esquema SET version = 5 WHERE id = 1;
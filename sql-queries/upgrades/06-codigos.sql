@@ -0,0 +1,3 @@
+USE bodega;
+-- Requiere haber vuelto a ejecutar init-db.sql, que crea la tabla codigos.
+UPDATE esquema SET version = 6 WHERE id = 1;
@@ -0,0 +1,4 @@
+USE bodega;
+-- Cantidad que trae cada envase de un objeto, en su unidad de medida. Los objetos que ya había no la tienen
+ALTER TABLE objetos ADD COLUMN envase DOUBLE NULL;
+UPDATE esquema SET version = 7 WHERE id = 1;
@@ -0,0 +1,3 @@
+USE bodega;
+-- Requiere haber vuelto a ejecutar init-db.sql, que crea la tabla precios.
+UPDATE esquema SET version = 8 WHERE id = 1;
@@ -0,0 +1,3 @@
+USE bodega;
+-- Requiere haber vuelto a ejecutar init-db.sql, que crea las tablas tiendas y proveedores.
+UPDATE esquema SET version = 9 WHERE id = 1;
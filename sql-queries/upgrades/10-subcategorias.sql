@@ -0,0 +1,4 @@
+USE bodega;
+-- Categoría de la que cuelga cada categoría. Las que ya había quedan en la raíz
+ALTER TABLE categorias ADD COLUMN padre BIGINT NULL, ADD FOREIGN KEY (padre) REFERENCES categorias(id);
+UPDATE esquema SET version = 10 WHERE id = 1;
@@ -0,0 +1,3 @@
+USE bodega;
+-- Requiere haber vuelto a ejecutar init-db.sql, que crea las tablas etiquetas y objetos_etiquetas.
+UPDATE esquema SET version = 11 WHERE id = 1;
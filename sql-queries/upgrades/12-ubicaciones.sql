@@ -0,0 +1,3 @@
+USE bodega;
+-- Requiere haber vuelto a ejecutar init-db.sql, que crea las tablas ubicaciones y existencias_ubicaciones.
+UPDATE esquema SET version = 12 WHERE id = 1;
@@ -0,0 +1,11 @@
+USE bodega;
+-- Requiere haber vuelto a ejecutar init-db.sql, que crea las tablas conservacion y lotes.
+-- Tipo de conservación de cada ubicación: despensa, nevera o congelador. Las que ya había son despensas.
+-- Si la tabla ubicaciones se creó con el init-db.sql de esta versión, ya tiene la columna y no se toca
+SET @falta = (SELECT COUNT(*) = 0 FROM information_schema.COLUMNS
+    WHERE TABLE_SCHEMA = 'bodega' AND TABLE_NAME = 'ubicaciones' AND COLUMN_NAME = 'tipo');
+SET @sentencia = IF(@falta, 'ALTER TABLE ubicaciones ADD COLUMN tipo VARCHAR(16) NOT NULL DEFAULT ''despensa''', 'DO 0');
+PREPARE sentencia FROM @sentencia;
+EXECUTE sentencia;
+DEALLOCATE PREPARE sentencia;
+UPDATE esquema SET version = 13 WHERE id = 1;
@@ -0,0 +1,11 @@
+USE bodega;
+-- El rol pasa a ser de cada miembro en cada hogar. Cada usuario conserva en todos sus hogares el rol que tenía
+-- Si la tabla miembros se creó con el init-db.sql de esta versión, ya tiene la columna y no se añade
+SET @falta = (SELECT COUNT(*) = 0 FROM information_schema.COLUMNS
+    WHERE TABLE_SCHEMA = 'bodega' AND TABLE_NAME = 'miembros' AND COLUMN_NAME = 'rol');
+SET @sentencia = IF(@falta, 'ALTER TABLE miembros ADD COLUMN rol VARCHAR(16) NOT NULL DEFAULT ''lectura''', 'DO 0');
+PREPARE sentencia FROM @sentencia;
+EXECUTE sentencia;
+DEALLOCATE PREPARE sentencia;
+UPDATE miembros m JOIN usuarios u ON u.id = m.id_usuario SET m.rol = u.rol;
+UPDATE esquema SET version = 14 WHERE id = 1;
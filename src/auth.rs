@@ -0,0 +1,384 @@
+///Módulo que gestiona los usuarios de la aplicación: sus contraseñas cifradas, sus roles y permisos, el inicio de sesión en el menú y la autenticación de las peticiones de red.
+pub mod auth {
+    use crate::error::error::BodegaError;
+    use crate::hogar::hogar::{Hogar, Sesion};
+    use crate::selector::selector::read_input;
+    use argon2::password_hash::rand_core::OsRng;
+    use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+    use argon2::Argon2;
+    use base64::Engine;
+    use mysql::prelude::Queryable;
+    use mysql::{params, PooledConn, Transaction, TxOpts};
+    use serde::Serialize;
+    use std::sync::OnceLock;
+
+    ///Longitud mínima de las contraseñas
+    const MIN_PASSWORD: usize = 4;
+    ///Intentos que se permiten para iniciar sesión en el menú
+    const LOGIN_ATTEMPTS: u8 = 3;
+
+    ///Rol de un usuario, que determina qué operaciones puede hacer.
+    #[derive(PartialEq, Clone, Debug, Serialize)]
+    pub enum Rol {
+        ///Puede hacer cualquier operación, incluida la gestión de usuarios y hogares
+        Admin,
+        ///Puede gestionar existencias, categorías y objetos
+        Editor,
+        ///Solo puede retirar existencias que se han consumido
+        Consumidor,
+        ///Solo puede consultar
+        Lectura,
+    }
+
+    ///Operación que requiere un permiso concreto.
+    #[derive(PartialEq, Clone, Debug)]
+    pub enum Permiso {
+        ///Retirar existencias
+        Consumir,
+        ///Añadir, fijar y transladar existencias
+        GestionarExistencias,
+        ///Crear y eliminar categorías y objetos
+        GestionarCatalogo,
+        ///Crear usuarios y cambiar sus roles
+        GestionarUsuarios,
+        ///Crear hogares y gestionar sus miembros
+        GestionarHogares,
+    }
+
+    ///Estructura basada en la tabla usuarios de la base de datos bodega-db. El hash de la contraseña nunca sale de este módulo.
+    #[derive(Clone, Serialize)]
+    pub struct Usuario {
+        pub id: i32,
+        pub nombre: String,
+        ///Rol en el hogar activo, guardado en la tabla miembros. Antes de elegir hogar, el de la tabla usuarios, que solo sirve para crear el primer hogar
+        pub rol: Rol,
+    }
+
+    ///Hash con el que se comprueba la contraseña cuando el usuario no existe, para que el inicio de sesión tarde lo mismo y no delate qué usuarios hay
+    static DUMMY_HASH: OnceLock<String> = OnceLock::new();
+
+    pub fn get_role_name(rol: &Rol) -> &'static str {
+        //!Devuelve el nombre con el que se guarda el rol en la tabla usuarios.
+        match rol {
+            Rol::Admin => "admin",
+            Rol::Editor => "editor",
+            Rol::Consumidor => "consumidor",
+            Rol::Lectura => "lectura",
+        }
+    }
+
+    pub fn parse_role(name: &str) -> Option<Rol> {
+        //!Obtiene el rol a partir de su nombre, sin importar mayúsculas ni minúsculas.
+        match name.trim().to_lowercase().as_str() {
+            "admin" => Some(Rol::Admin),
+            "editor" => Some(Rol::Editor),
+            "consumidor" => Some(Rol::Consumidor),
+            "lectura" => Some(Rol::Lectura),
+            _ => None,
+        }
+    }
+
+    pub fn has_permission(rol: &Rol, permiso: &Permiso) -> bool {
+        //!Indica si un rol concede el permiso dado.
+        match rol {
+            Rol::Admin => true,
+            Rol::Editor => {
+                *permiso != Permiso::GestionarUsuarios && *permiso != Permiso::GestionarHogares
+            }
+            Rol::Consumidor => *permiso == Permiso::Consumir,
+            Rol::Lectura => false,
+        }
+    }
+
+    pub fn check_permission(usuario: &Usuario, permiso: &Permiso) -> Result<(), BodegaError> {
+        //!Comprueba que el usuario tiene el permiso dado. Todas las operaciones que modifican la base de datos lo llaman antes de hacer nada, así que se aplica igual desde el menú, la línea de comandos o el servidor.
+        if has_permission(&usuario.rol, permiso) {
+            return Ok(());
+        }
+        return Err(BodegaError::SinPermiso(format!(
+            "El usuario {} tiene el rol {}, que no permite esta operación",
+            usuario.nombre,
+            get_role_name(&usuario.rol)
+        )));
+    }
+
+    fn user_from_row(id: i32, nombre: String, rol: String) -> Usuario {
+        //!Construye un usuario a partir de una fila de la tabla usuarios. Un rol desconocido se trata como de solo lectura.
+        return Usuario {
+            id,
+            nombre,
+            rol: parse_role(&rol).unwrap_or(Rol::Lectura),
+        };
+    }
+
+    pub fn hash_password(password: &str) -> Result<String, BodegaError> {
+        //!Cifra una contraseña con argon2 y una sal aleatoria, en el formato PHC que se guarda en la tabla usuarios.
+        let salt = SaltString::generate(&mut OsRng);
+        return Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|h| h.to_string())
+            .map_err(|e| BodegaError::Invalido(format!("No se pudo cifrar la contraseña: {}", e)));
+    }
+
+    pub fn verify_password(hash: &str, password: &str) -> bool {
+        //!Comprueba si la contraseña corresponde al hash guardado.
+        return match PasswordHash::new(hash) {
+            Ok(parsed) => Argon2::default()
+                .verify_password(password.as_bytes(), &parsed)
+                .is_ok(),
+            Err(_) => false,
+        };
+    }
+
+    pub fn read_users(conn: &mut PooledConn, hogar: &Hogar) -> Result<Vec<Usuario>, BodegaError> {
+        //!Devuelve los usuarios que son miembros del hogar dado, cada uno con su rol en él. Los usuarios de otros hogares no se ven.
+        return Ok(conn.exec_map(
+            "SELECT u.id, u.nombre, m.rol FROM usuarios u
+                JOIN miembros m ON m.id_usuario = u.id
+                WHERE m.id_hogar = :hogar ORDER BY u.nombre;",
+            params! {"hogar" => hogar.id},
+            |(id, nombre, rol)| user_from_row(id, nombre, rol),
+        )?);
+    }
+
+    pub fn insert_user(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        name: String,
+        password: &str,
+        rol: &Rol,
+    ) -> Result<(), BodegaError> {
+        //!Crea un usuario con el nombre y la contraseña dados y lo hace miembro del hogar activo con el rol dado, en una única transacción, si el usuario que lo crea puede gestionar usuarios.
+        check_permission(&sesion.usuario, &Permiso::GestionarUsuarios)?;
+        let mut tx = conn.start_transaction(TxOpts::default())?;
+        let id = create_user(&mut tx, name, password, rol)?;
+        tx.exec_drop(
+            "INSERT INTO miembros (id_usuario, id_hogar, rol) VALUES (:usuario, :hogar, :rol);",
+            params! {"usuario" => id, "hogar" => sesion.hogar.id, "rol" => get_role_name(rol)},
+        )?;
+        tx.commit()?;
+        return Ok(());
+    }
+
+    fn create_user(
+        tx: &mut Transaction,
+        name: String,
+        password: &str,
+        rol: &Rol,
+    ) -> Result<i32, BodegaError> {
+        //!Crea un usuario con el nombre, la contraseña y el rol dados, comprobando que el nombre no está vacío ni repetido y que la contraseña tiene la longitud mínima. Devuelve el id del usuario creado.
+        let name = String::from(name.trim());
+        if name.is_empty() {
+            return Err(BodegaError::Invalido(String::from(
+                "El nombre no puede estar vacío",
+            )));
+        }
+        if password.chars().count() < MIN_PASSWORD {
+            return Err(BodegaError::Invalido(format!(
+                "La contraseña debe tener al menos {} caracteres",
+                MIN_PASSWORD
+            )));
+        }
+        let existing: Option<i32> = tx.exec_first(
+            "SELECT id FROM usuarios WHERE nombre = :nombre;",
+            params! {"nombre" => &name},
+        )?;
+        if existing.is_some() {
+            return Err(BodegaError::Invalido(format!(
+                "Ya existe un usuario llamado {}",
+                name
+            )));
+        }
+        tx.exec_drop(
+            "INSERT INTO usuarios (nombre, hash, rol) VALUES (:nombre, :hash, :rol);",
+            params! {
+                "nombre" => name,
+                "hash" => hash_password(password)?,
+                "rol" => get_role_name(rol),
+            },
+        )?;
+        return Ok(tx.last_insert_id().unwrap_or(0) as i32);
+    }
+
+    pub fn set_role(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        target: &Usuario,
+        rol: &Rol,
+    ) -> Result<(), BodegaError> {
+        //!Cambia el rol de un miembro del hogar activo en ese hogar, si el usuario que lo cambia puede gestionar usuarios. No permite dejar el hogar sin ningún administrador.
+        check_permission(&sesion.usuario, &Permiso::GestionarUsuarios)?;
+        let members = read_users(conn, &sesion.hogar)?;
+        let current = match members.iter().find(|u| u.id == target.id) {
+            Some(member) => member.rol.clone(),
+            None => {
+                return Err(BodegaError::NoEncontrado(format!(
+                    "{} no es miembro de {}",
+                    target.nombre, sesion.hogar.nombre
+                )));
+            }
+        };
+        let admins = members.iter().filter(|u| u.rol == Rol::Admin).count();
+        if current == Rol::Admin && *rol != Rol::Admin && admins <= 1 {
+            return Err(BodegaError::Invalido(format!(
+                "{} es el único administrador de {}. Nombra antes a otro",
+                target.nombre, sesion.hogar.nombre
+            )));
+        }
+        conn.exec_drop(
+            "UPDATE miembros SET rol=:rol WHERE id_usuario=:id AND id_hogar=:hogar;",
+            params! {
+                "rol" => get_role_name(rol),
+                "id" => target.id,
+                "hogar" => sesion.hogar.id,
+            },
+        )?;
+        return Ok(());
+    }
+
+    pub fn change_password(
+        conn: &mut PooledConn,
+        usuario: &Usuario,
+        password: &str,
+    ) -> Result<(), BodegaError> {
+        //!Cambia la contraseña de un usuario.
+        if password.chars().count() < MIN_PASSWORD {
+            return Err(BodegaError::Invalido(format!(
+                "La contraseña debe tener al menos {} caracteres",
+                MIN_PASSWORD
+            )));
+        }
+        conn.exec_drop(
+            "UPDATE usuarios SET hash=:hash WHERE id=:id;",
+            params! {
+                "hash" => hash_password(password)?,
+                "id" => usuario.id,
+            },
+        )?;
+        return Ok(());
+    }
+
+    pub fn login(
+        conn: &mut PooledConn,
+        name: &str,
+        password: &str,
+    ) -> Result<Usuario, BodegaError> {
+        //!Comprueba el nombre y la contraseña de un usuario y, si son correctos, lo devuelve. No se distingue si falla el nombre o la contraseña, ni por el mensaje ni por el tiempo: si el usuario no existe, la contraseña se comprueba igualmente contra un hash de relleno.
+        let found: Option<(i32, String, String, String)> = conn.exec_first(
+            "SELECT id, nombre, hash, rol FROM usuarios WHERE nombre=:nombre;",
+            params! {"nombre" => name.trim()},
+        )?;
+        match found {
+            Some((id, nombre, hash, rol)) if verify_password(&hash, password) => {
+                return Ok(user_from_row(id, nombre, rol));
+            }
+            found => {
+                if found.is_none() {
+                    let dummy =
+                        DUMMY_HASH.get_or_init(|| hash_password("bodega").unwrap_or_default());
+                    verify_password(dummy, password);
+                }
+                return Err(BodegaError::NoAutenticado(String::from(
+                    "Usuario o contraseña incorrectos",
+                )));
+            }
+        }
+    }
+
+    pub fn read_password(prompt: &str) -> String {
+        //!Pide una contraseña por la terminal sin mostrarla mientras se escribe.
+        return match rpassword::prompt_password(prompt) {
+            Ok(password) => password,
+            Err(e) => {
+                println!("No se pudo leer la contraseña: {}", e);
+                String::new()
+            }
+        };
+    }
+
+    pub fn ask_credentials() -> Result<(String, String), BodegaError> {
+        //!Pide por la terminal el nombre y la contraseña (dos veces) de un usuario nuevo.
+        println!("Nombre del nuevo usuario: ");
+        let name = read_input();
+        let password = read_password("Contraseña: ");
+        if read_password("Repite la contraseña: ") != password {
+            return Err(BodegaError::Invalido(String::from(
+                "Las contraseñas no coinciden",
+            )));
+        }
+        return Ok((name, password));
+    }
+
+    pub fn ask_role() -> Option<Rol> {
+        //!Pide por la terminal que se elija un rol.
+        println!("1. Administrador: puede hacerlo todo, también gestionar usuarios y hogares");
+        println!("2. Editor: gestiona existencias, categorías y objetos");
+        println!("3. Consumidor: solo retira lo que se consume");
+        println!("4. Lectura: solo consulta");
+        println!("\nElige el rol:");
+        match read_input().as_str() {
+            "1" => Some(Rol::Admin),
+            "2" => Some(Rol::Editor),
+            "3" => Some(Rol::Consumidor),
+            "4" => Some(Rol::Lectura),
+            _ => None,
+        }
+    }
+
+    pub fn login_interactive(conn: &mut PooledConn, name: Option<String>) -> Option<Usuario> {
+        //!Inicia sesión pidiendo el usuario (si no se ha dado) y la contraseña por la terminal. Si todavía no hay ningún usuario, pide crear el primero como administrador.
+        let users: Option<i64> = match conn.query_first("SELECT COUNT(*) FROM usuarios;") {
+            Ok(users) => users,
+            Err(e) => {
+                println!("No se pudieron leer los usuarios: {}", e);
+                return None;
+            }
+        };
+        if users.unwrap_or(0) == 0 {
+            println!("Todavía no hay usuarios. Crea el primero, que será administrador:");
+            if let Err(e) = ask_credentials().and_then(|(name, password)| {
+                let mut tx = conn.start_transaction(TxOpts::default())?;
+                create_user(&mut tx, name, &password, &Rol::Admin)?;
+                tx.commit()?;
+                return Ok(());
+            }) {
+                println!("No se pudo crear el usuario: {}", e);
+                return None;
+            }
+        }
+        for _ in 0..LOGIN_ATTEMPTS {
+            let name = match &name {
+                Some(name) => name.clone(),
+                None => {
+                    println!("Usuario: ");
+                    read_input()
+                }
+            };
+            let password = read_password("Contraseña: ");
+            match login(conn, &name, &password) {
+                Ok(usuario) => {
+                    return Some(usuario);
+                }
+                Err(e) => {
+                    println!("{}", e);
+                }
+            }
+        }
+        return None;
+    }
+
+    pub fn basic_auth(conn: &mut PooledConn, header: Option<&str>) -> Result<Usuario, BodegaError> {
+        //!Autentica una petición HTTP a partir de su cabecera Authorization con el esquema Basic.
+        let missing = || BodegaError::NoAutenticado(String::from("Hace falta iniciar sesión"));
+        let encoded = header
+            .and_then(|h| h.strip_prefix("Basic "))
+            .ok_or_else(missing)?;
+        let decoded = base64::engine::general_purpose::STANDARD
+            .decode(encoded.trim())
+            .ok()
+            .and_then(|d| String::from_utf8(d).ok())
+            .ok_or_else(missing)?;
+        let (name, password) = decoded.split_once(':').ok_or_else(missing)?;
+        return login(conn, name, password);
+    }
+}
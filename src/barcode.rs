@@ -0,0 +1,245 @@
+///Módulo que gestiona los códigos de barras (EAN-8, UPC-A, EAN-13 y GTIN-14) de los objetos, para poder buscarlos con un lector, que escribe los dígitos y pulsa Enter como si fuera un teclado.
+pub mod barcode {
+    use crate::auth::auth::{check_permission, Permiso};
+    use crate::db_manager::db_manager::{
+        adjust_stock, get_quantity, get_string_name, read_objects, read_stock, Objeto, Procedencia,
+    };
+    use crate::error::error::BodegaError;
+    use crate::hogar::hogar::{Hogar, Sesion};
+    use crate::labels::labels::parse_label;
+    use crate::selector::selector::{choose, read_input};
+    use mysql::prelude::Queryable;
+    use mysql::{params, PooledConn};
+
+    ///Longitudes de los códigos admitidos: EAN-8, UPC-A, EAN-13 y GTIN-14
+    const LENGTHS: [usize; 4] = [8, 12, 13, 14];
+
+    pub fn is_code(text: &str) -> bool {
+        //!Indica si un texto es un código de barras válido: solo dígitos, con una de las longitudes admitidas y con el dígito de control correcto.
+        let text = text.trim();
+        if !LENGTHS.contains(&text.len()) || !text.chars().all(|c| c.is_ascii_digit()) {
+            return false;
+        }
+        let digits: Vec<u32> = text.chars().filter_map(|c| c.to_digit(10)).collect();
+        let (check, body) = match digits.split_last() {
+            Some(split) => split,
+            None => return false,
+        };
+        let sum: u32 = body
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(i, d)| if i % 2 == 0 { d * 3 } else { *d })
+            .sum();
+        return (10 - sum % 10) % 10 == *check;
+    }
+
+    pub fn read_codes(conn: &mut PooledConn, objeto: &Objeto) -> Result<Vec<String>, BodegaError> {
+        //!Devuelve los códigos de barras de un objeto.
+        return Ok(conn.exec(
+            "SELECT codigo FROM codigos WHERE id_objeto = :id ORDER BY codigo;",
+            params! {"id" => objeto.id},
+        )?);
+    }
+
+    pub fn find_by_code(
+        conn: &mut PooledConn,
+        hogar: &Hogar,
+        code: &str,
+    ) -> Result<Option<Objeto>, BodegaError> {
+        //!Busca el objeto del hogar dado que tiene el código de barras dado.
+        let ids: Vec<i32> = conn.exec(
+            "SELECT id_objeto FROM codigos WHERE codigo = :codigo;",
+            params! {"codigo" => code.trim()},
+        )?;
+        return Ok(read_objects(conn, hogar)?
+            .into_iter()
+            .find(|o| ids.contains(&o.id)));
+    }
+
+    pub fn add_code(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        objeto: &Objeto,
+        code: &str,
+    ) -> Result<(), BodegaError> {
+        //!Asocia un código de barras a un objeto del hogar activo, si el usuario puede gestionar el catálogo. Un objeto puede tener varios códigos, pero un código solo puede ser de un objeto de cada hogar.
+        check_permission(&sesion.usuario, &Permiso::GestionarCatalogo)?;
+        let code = code.trim();
+        if !is_code(code) {
+            return Err(BodegaError::Invalido(format!(
+                "\"{}\" no es un código EAN-8, UPC-A, EAN-13 ni GTIN-14 válido",
+                code
+            )));
+        }
+        if !read_objects(conn, &sesion.hogar)?
+            .iter()
+            .any(|o| o.id == objeto.id)
+        {
+            return Err(BodegaError::NoEncontrado(format!(
+                "No existe ningún objeto con id {} en {}",
+                objeto.id, sesion.hogar.nombre
+            )));
+        }
+        if let Some(other) = find_by_code(conn, &sesion.hogar, code)? {
+            if other.id != objeto.id {
+                return Err(BodegaError::Conflicto(format!(
+                    "El código {} ya es de {}",
+                    code, other.nombre
+                )));
+            }
+        }
+        conn.exec_drop(
+            "INSERT IGNORE INTO codigos (codigo, id_objeto) VALUES (:codigo, :id);",
+            params! {"codigo" => code, "id" => objeto.id},
+        )?;
+        return Ok(());
+    }
+
+    pub fn remove_code(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        code: &str,
+    ) -> Result<Objeto, BodegaError> {
+        //!Quita un código de barras del objeto del hogar activo que lo tiene, si el usuario puede gestionar el catálogo. Devuelve el objeto.
+        check_permission(&sesion.usuario, &Permiso::GestionarCatalogo)?;
+        let objeto =
+            find_by_code(conn, &sesion.hogar, code)?.ok_or(BodegaError::NoEncontrado(format!(
+                "Ningún objeto de {} tiene el código {}",
+                sesion.hogar.nombre,
+                code.trim()
+            )))?;
+        conn.exec_drop(
+            "DELETE FROM codigos WHERE codigo = :codigo AND id_objeto = :id;",
+            params! {"codigo" => code.trim(), "id" => objeto.id},
+        )?;
+        return Ok(objeto);
+    }
+
+    pub fn choose_object(
+        conn: &mut PooledConn,
+        hogar: &Hogar,
+        input: &str,
+        objects: &[Objeto],
+    ) -> Result<Option<Objeto>, BodegaError> {
+        //!Como choose(), pero si lo escrito es el código QR de una etiqueta, devuelve su objeto, y si es un código de barras, busca primero el objeto que lo tiene. Así se puede usar un lector en cualquier sitio en el que se pide un objeto.
+        if let Some((id, _)) = parse_label(input) {
+            let found = match objects.iter().find(|o| o.id == id) {
+                Some(objeto) => Some(objeto.clone()),
+                None => read_objects(conn, hogar)?.into_iter().find(|o| o.id == id),
+            };
+            if found.is_none() {
+                println!(
+                    "La etiqueta es de un objeto que no existe en {}.",
+                    hogar.nombre
+                );
+            }
+            return Ok(found);
+        }
+        if is_code(input) {
+            if let Some(objeto) = find_by_code(conn, hogar, input)? {
+                return Ok(Some(objeto));
+            }
+        }
+        return Ok(choose(input, objects));
+    }
+
+    pub fn scan_interactive(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        lugar: &Procedencia,
+        remove: bool,
+        pack: bool,
+    ) -> Result<(), BodegaError> {
+        //!Modo de escaneo rápido: cada código leído añade (o retira, si remove es verdadero) una unidad del objeto en lugar, hasta que se lea una línea vacía. Si pack es verdadero, cada lectura cuenta un envase completo, o una unidad si no se sabe cuánto trae el del objeto. Si un código no es de ningún objeto, se puede asociar a uno en ese momento.
+        let (sign, verb) = match remove {
+            true => (-1.0, "retira"),
+            false => (1.0, "añade"),
+        };
+        println!(
+            "Escaneo rápido en {}: cada código {} {}. Línea vacía para terminar.",
+            get_string_name(lugar),
+            verb,
+            match pack {
+                true => "un envase",
+                false => "una unidad",
+            }
+        );
+        let mut count = 0;
+        loop {
+            let input = read_input();
+            if input.is_empty() {
+                break;
+            }
+            let objeto = match choose_object(conn, &sesion.hogar, &input, &[])? {
+                Some(objeto) => objeto,
+                None if is_code(&input) => {
+                    println!(
+                        "El código {} no es de ningún objeto. Escribe el ID o el nombre del objeto al que asociarlo, o nada para saltarlo:",
+                        input
+                    );
+                    let objects = read_objects(conn, &sesion.hogar)?;
+                    match choose(&read_input(), &objects) {
+                        Some(objeto) => match add_code(conn, sesion, &objeto, &input) {
+                            Ok(()) => objeto,
+                            Err(e) => {
+                                println!("No se pudo asociar el código: {}", e);
+                                continue;
+                            }
+                        },
+                        None => continue,
+                    }
+                }
+                None => {
+                    println!("\"{}\" no es un código de barras válido", input);
+                    continue;
+                }
+            };
+            let amount = match pack {
+                true => sign * objeto.envase.unwrap_or(1.0),
+                false => sign,
+            };
+            match adjust_stock(conn, sesion, objeto.id, false, amount as f32, lugar, None) {
+                Ok(()) => {
+                    count += 1;
+                    let stock = read_stock(conn, std::slice::from_ref(&objeto))?;
+                    println!(
+                        "{:+} {} de {}: hay x{} {}",
+                        amount,
+                        objeto.medida,
+                        objeto.nombre,
+                        get_quantity(&stock, objeto.id, lugar),
+                        objeto.medida
+                    );
+                }
+                Err(e) => {
+                    println!("{}: {}", objeto.nombre, e);
+                }
+            }
+        }
+        println!("Se registraron {} lecturas", count);
+        return Ok(());
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn valid_codes_of_every_length() {
+            assert!(is_code("96385074"));
+            assert!(is_code("036000291452"));
+            assert!(is_code("4006381333931"));
+            assert!(is_code("10012345678902"));
+            assert!(is_code(" 4006381333931\n"));
+        }
+
+        #[test]
+        fn wrong_check_digit_length_or_characters() {
+            assert!(!is_code("4006381333932"));
+            assert!(!is_code("400638133393"));
+            assert!(!is_code("40063813339a1"));
+            assert!(!is_code(""));
+        }
+    }
+}
@@ -0,0 +1,546 @@
+///Módulo que importa objetos de un volcado de Open Food Facts descargado en el equipo, sin conexión a internet. Admite el volcado en CSV (separado por tabuladores, como el oficial, o por comas) y en JSONL, comprimidos con gzip o no. El volcado se lee línea a línea, porque el completo ocupa varios gigas.
+pub mod catalog {
+    use crate::auth::auth::{check_permission, Permiso};
+    use crate::barcode::barcode::{find_by_code, is_code};
+    use crate::db_manager::db_manager::{
+        check_pack_size, get_category_by_id, insert_category, read_categories, read_objects,
+        Categoria, Objeto,
+    };
+    use crate::error::error::BodegaError;
+    use crate::hogar::hogar::Sesion;
+    use crate::integrity::integrity::UNITS;
+    use crate::selector::selector::{choose, normalize, read_input};
+    use flate2::read::MultiGzDecoder;
+    use mysql::prelude::Queryable;
+    use mysql::{params, PooledConn, TxOpts};
+    use serde::Serialize;
+    use serde_json::Value;
+    use std::fs::File;
+    use std::io::{BufRead, BufReader, Read};
+    use std::path::Path;
+
+    ///Número máximo de productos que devuelve una búsqueda por nombre
+    const MAX_RESULTS: usize = 20;
+    ///Unidad de medida de los productos cuya cantidad no trae ninguna unidad conocida
+    const DEFAULT_UNIT: &str = "unidades";
+    ///Descripción de las categorías que se crean al importar
+    const CATEGORY_DESCRIPTION: &str = "Importada de Open Food Facts";
+
+    ///Producto del volcado de Open Food Facts.
+    #[derive(Clone, Serialize)]
+    pub struct Producto {
+        pub codigo: String,
+        pub nombre: String,
+        pub marcas: String,
+        ///Cantidad tal y como aparece en el volcado, por ejemplo "6 x 330 ml"
+        pub cantidad: String,
+        ///Cantidad que trae cada envase, en la unidad de medida, si se ha podido entender
+        pub envase: Option<f64>,
+        pub medida: String,
+        ///Categorías del producto, de la más general a la más concreta
+        pub categorias: Vec<String>,
+    }
+
+    ///Formato de un volcado.
+    enum Volcado {
+        ///Una fila por producto, con cabecera
+        Csv,
+        ///Un objeto JSON por línea
+        Jsonl,
+    }
+
+    pub fn parse_pack(text: &str) -> (Option<f64>, String) {
+        //!Entiende la cantidad de un producto como la que trae cada envase y su unidad de medida: "500 g" es (500, "g"), "1,5L" es (1.5, "l") y "6 x 330 ml" es (1980, "ml"). Si no hay ninguna unidad, el número se toma como unidades; si la unidad no se conoce, no se usa el número.
+        let text = normalize(text).replace(',', ".");
+        let number = |rest: &str| -> Option<(f64, usize)> {
+            let end = rest
+                .find(|c: char| !c.is_ascii_digit() && c != '.')
+                .unwrap_or(rest.len());
+            return rest[..end].parse::<f64>().ok().map(|n| (n, end));
+        };
+        let start = match text.find(|c: char| c.is_ascii_digit()) {
+            Some(start) => start,
+            None => return (None, String::from(DEFAULT_UNIT)),
+        };
+        let (mut quant, end) = match number(&text[start..]) {
+            Some(found) => found,
+            None => return (None, String::from(DEFAULT_UNIT)),
+        };
+        let mut rest = text[start + end..].trim_start();
+        if let Some(after) = rest.strip_prefix('x').or(rest.strip_prefix('×')) {
+            let after = after.trim_start();
+            if let Some((each, end)) = number(after) {
+                quant *= each;
+                rest = after[end..].trim_start();
+            }
+        }
+        let unit: String = rest.chars().take_while(|c| c.is_alphabetic()).collect();
+        let unit = match unit.as_str() {
+            "" => DEFAULT_UNIT,
+            "gr" | "grs" | "gramo" | "gramos" => "g",
+            "lt" | "ltr" | "litro" | "litros" => "l",
+            u => u,
+        };
+        if !quant.is_finite() || quant <= 0.0 || !UNITS.contains(&unit) {
+            return (None, String::from(DEFAULT_UNIT));
+        }
+        return (Some(quant), String::from(unit));
+    }
+
+    fn clean_category(text: &str) -> String {
+        //!Quita a una categoría el prefijo del idioma ("en:") y los guiones de las etiquetas, y pone en mayúscula la primera letra.
+        let text = match text.trim().split_once(':') {
+            Some((lang, rest)) if lang.len() == 2 => rest,
+            _ => text.trim(),
+        };
+        let text = text.replace('-', " ");
+        let mut chars = text.chars();
+        return match chars.next() {
+            Some(first) => first.to_uppercase().chain(chars).collect(),
+            None => String::new(),
+        };
+    }
+
+    fn build(
+        codigo: &str,
+        nombre: &str,
+        marcas: &str,
+        cantidad: &str,
+        categorias: &[&str],
+    ) -> Option<Producto> {
+        //!Construye un producto a partir de los campos del volcado. Los productos sin nombre no se pueden importar, así que no se devuelven.
+        if nombre.trim().is_empty() {
+            return None;
+        }
+        let (envase, medida) = parse_pack(cantidad);
+        return Some(Producto {
+            codigo: String::from(codigo.trim()),
+            nombre: String::from(nombre.trim()),
+            marcas: String::from(marcas.trim()),
+            cantidad: String::from(cantidad.trim()),
+            envase,
+            medida,
+            categorias: categorias
+                .iter()
+                .map(|c| clean_category(c))
+                .filter(|c| !c.is_empty())
+                .collect(),
+        });
+    }
+
+    fn by_code(query: &str) -> bool {
+        //!Indica si lo buscado es un código: solo dígitos. El volcado también tiene códigos internos de las tiendas, que no son códigos de barras válidos.
+        let query = query.trim();
+        return !query.is_empty() && query.chars().all(|c| c.is_ascii_digit());
+    }
+
+    fn matches(query: &str, codigo: &str, nombre: &str, marcas: &str) -> bool {
+        //!Indica si un producto coincide con lo buscado: si es un código, el mismo código sin contar los ceros a la izquierda; si no, que el nombre o las marcas contengan todas las palabras buscadas.
+        if by_code(query) {
+            return codigo.trim().trim_start_matches('0') == query.trim().trim_start_matches('0');
+        }
+        let text = normalize(&format!("{} {}", nombre, marcas));
+        return normalize(query).split(' ').all(|word| text.contains(word));
+    }
+
+    fn open(path: &Path) -> Result<(Box<dyn BufRead>, Volcado), BodegaError> {
+        //!Abre un volcado, descomprimiéndolo si su nombre termina en .gz, y deduce su formato por la extensión.
+        let file = File::open(path).map_err(|e| {
+            BodegaError::Invalido(format!(
+                "No se pudo abrir el catálogo {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let (reader, name): (Box<dyn Read>, &str) = match name.strip_suffix(".gz") {
+            Some(name) => (Box::new(MultiGzDecoder::new(file)), name),
+            None => (Box::new(file), name.as_str()),
+        };
+        let format = match name.ends_with(".jsonl") || name.ends_with(".json") {
+            true => Volcado::Jsonl,
+            false => Volcado::Csv,
+        };
+        return Ok((Box::new(BufReader::new(reader)), format));
+    }
+
+    fn read_error(path: &Path, e: impl std::fmt::Display) -> BodegaError {
+        //!Error de lectura de un volcado.
+        return BodegaError::Invalido(format!(
+            "No se pudo leer el catálogo {}: {}",
+            path.display(),
+            e
+        ));
+    }
+
+    fn search_csv(
+        path: &Path,
+        mut reader: Box<dyn BufRead>,
+        query: &str,
+        found: &mut Vec<Producto>,
+    ) -> Result<(), BodegaError> {
+        //!Busca en un volcado CSV. El separador es el tabulador si la cabecera tiene alguno, y si no la coma; con tabuladores no se interpretan las comillas, como en el volcado oficial.
+        let mut header = String::new();
+        reader
+            .read_line(&mut header)
+            .map_err(|e| read_error(path, e))?;
+        let delimiter = match header.contains('\t') {
+            true => b'\t',
+            false => b',',
+        };
+        let columns: Vec<String> = header
+            .trim_end_matches(['\r', '\n'])
+            .split(delimiter as char)
+            .map(|c| String::from(c.trim_matches('"')))
+            .collect();
+        let column = |name: &str| columns.iter().position(|c| c == name);
+        let code = column("code").ok_or(BodegaError::Invalido(format!(
+            "{} no parece un volcado de Open Food Facts: no tiene la columna code",
+            path.display()
+        )))?;
+        let names = [column("product_name_es"), column("product_name")];
+        let brands = column("brands");
+        let quantity = column("quantity");
+        let categories = column("categories").or(column("categories_tags"));
+        let mut csv = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .delimiter(delimiter)
+            .quoting(delimiter == b',')
+            .from_reader(reader);
+        for record in csv.records() {
+            let record = record.map_err(|e| read_error(path, e))?;
+            let field = |i: Option<usize>| i.and_then(|i| record.get(i)).unwrap_or("");
+            let codigo = field(Some(code));
+            let nombre = names
+                .iter()
+                .map(|i| field(*i))
+                .find(|n| !n.trim().is_empty())
+                .unwrap_or("");
+            if !matches(query, codigo, nombre, field(brands)) {
+                continue;
+            }
+            let categorias: Vec<&str> = field(categories).split(',').collect();
+            if let Some(p) = build(codigo, nombre, field(brands), field(quantity), &categorias) {
+                found.push(p);
+                if by_code(query) || found.len() >= MAX_RESULTS {
+                    break;
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    fn search_jsonl(
+        path: &Path,
+        reader: Box<dyn BufRead>,
+        query: &str,
+        found: &mut Vec<Producto>,
+    ) -> Result<(), BodegaError> {
+        //!Busca en un volcado JSONL. Las líneas que no son JSON válido se saltan. Al buscar un código, solo se analizan las líneas que lo contienen.
+        let digits = query.trim().trim_start_matches('0');
+        for line in reader.lines() {
+            let line = line.map_err(|e| read_error(path, e))?;
+            if by_code(query) && !line.contains(digits) {
+                continue;
+            }
+            let value: Value = match serde_json::from_str(&line) {
+                Ok(value) => value,
+                Err(_) => continue,
+            };
+            let text = |name: &str| match &value[name] {
+                Value::String(s) => s.clone(),
+                Value::Number(n) => n.to_string(),
+                _ => String::new(),
+            };
+            let codigo = text("code");
+            let nombre = match text("product_name_es") {
+                n if !n.trim().is_empty() => n,
+                _ => text("product_name"),
+            };
+            if !matches(query, &codigo, &nombre, &text("brands")) {
+                continue;
+            }
+            let categorias: Vec<String> = match text("categories") {
+                c if !c.trim().is_empty() => c.split(',').map(String::from).collect(),
+                _ => value["categories_tags"]
+                    .as_array()
+                    .map(|tags| {
+                        tags.iter()
+                            .filter_map(|t| t.as_str().map(String::from))
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            };
+            let categorias: Vec<&str> = categorias.iter().map(|c| c.as_str()).collect();
+            if let Some(p) = build(
+                &codigo,
+                &nombre,
+                &text("brands"),
+                &text("quantity"),
+                &categorias,
+            ) {
+                found.push(p);
+                if by_code(query) || found.len() >= MAX_RESULTS {
+                    break;
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    pub fn search(path: &Path, query: &str) -> Result<Vec<Producto>, BodegaError> {
+        //!Busca productos en un volcado de Open Food Facts por código de barras o por palabras del nombre y las marcas. Devuelve como mucho MAX_RESULTS productos, o el primero con el código buscado.
+        if query.trim().is_empty() {
+            return Err(BodegaError::Invalido(String::from(
+                "Escribe un código de barras o parte del nombre del producto",
+            )));
+        }
+        let (reader, format) = open(path)?;
+        let mut found = Vec::new();
+        match format {
+            Volcado::Csv => search_csv(path, reader, query, &mut found)?,
+            Volcado::Jsonl => search_jsonl(path, reader, query, &mut found)?,
+        }
+        return Ok(found);
+    }
+
+    pub fn suggest_category(producto: &Producto, categories: &[Categoria]) -> Option<Categoria> {
+        //!Sugiere la categoría del hogar que más se parece a las del producto, empezando por la más concreta. Se parecen si tienen el mismo nombre, sin contar tildes, mayúsculas ni la ese final, o si el nombre de la categoría del hogar es una de las palabras de la del producto.
+        let stem = |text: &str| String::from(text.trim_end_matches('s'));
+        for off in producto.categorias.iter().rev() {
+            let off = normalize(off);
+            let words: Vec<String> = off.split(' ').map(stem).collect();
+            if let Some(c) = categories.iter().find(|c| {
+                let name = stem(&normalize(&c.nombre));
+                name == stem(&off) || words.contains(&name)
+            }) {
+                return Some(c.clone());
+            }
+        }
+        return None;
+    }
+
+    pub fn import_product(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        producto: &Producto,
+        categoria: &Categoria,
+        nombre: &str,
+    ) -> Result<Objeto, BodegaError> {
+        //!Crea un objeto en la categoría dada a partir de un producto, con el nombre dado, la unidad de medida y el tamaño del envase del producto, y le asocia su código de barras si es válido, todo en una única transacción. Requiere poder gestionar el catálogo.
+        check_permission(&sesion.usuario, &Permiso::GestionarCatalogo)?;
+        let nombre = nombre.trim();
+        if nombre.is_empty() {
+            return Err(BodegaError::Invalido(String::from(
+                "El nombre del objeto no puede estar vacío",
+            )));
+        }
+        if read_objects(conn, &sesion.hogar)?
+            .iter()
+            .any(|o| normalize(&o.nombre) == normalize(nombre))
+        {
+            return Err(BodegaError::Conflicto(format!(
+                "Ya hay un objeto llamado {} en {}",
+                nombre, sesion.hogar.nombre
+            )));
+        }
+        let code = is_code(&producto.codigo);
+        if code {
+            if let Some(other) = find_by_code(conn, &sesion.hogar, &producto.codigo)? {
+                return Err(BodegaError::Conflicto(format!(
+                    "El código {} ya es de {}",
+                    producto.codigo, other.nombre
+                )));
+            }
+        }
+        if let Some(envase) = producto.envase {
+            check_pack_size(envase)?;
+        }
+        let mut tx = conn.start_transaction(TxOpts::default())?;
+        tx.exec_drop(
+            "INSERT INTO objetos (categoria, nombre, medida, envase)
+                SELECT id, :name, :measure, :envase FROM categorias WHERE id=:cat AND hogar=:hogar;",
+            params! {
+                "cat" => categoria.id,
+                "name" => nombre,
+                "measure" => &producto.medida,
+                "envase" => producto.envase,
+                "hogar" => sesion.hogar.id,
+            },
+        )?;
+        if tx.affected_rows() == 0 {
+            return Err(BodegaError::NoEncontrado(format!(
+                "No existe ninguna categoría con id {} en {}",
+                categoria.id, sesion.hogar.nombre
+            )));
+        }
+        let id = tx.last_insert_id().unwrap_or_default() as i32;
+        if code {
+            tx.exec_drop(
+                "INSERT INTO codigos (codigo, id_objeto) VALUES (:codigo, :id);",
+                params! {"codigo" => &producto.codigo, "id" => id},
+            )?;
+        }
+        tx.commit()?;
+        let objeto = read_objects(conn, &sesion.hogar)?
+            .into_iter()
+            .find(|o| o.id == id)
+            .ok_or(BodegaError::NoEncontrado(format!(
+                "No se encontró el objeto {} recién creado",
+                nombre
+            )))?;
+        return Ok(objeto);
+    }
+
+    pub fn print_products(productos: &[Producto]) {
+        //!Muestra una lista numerada de productos.
+        if productos.is_empty() {
+            println!("No se encontró ningún producto");
+            return;
+        }
+        for (i, p) in productos.iter().enumerate() {
+            println!(
+                "{}. {}{}{} [{}]",
+                i + 1,
+                p.nombre,
+                match p.marcas.is_empty() {
+                    true => String::new(),
+                    false => format!(" ({})", p.marcas),
+                },
+                match p.cantidad.is_empty() {
+                    true => String::new(),
+                    false => format!(", {}", p.cantidad),
+                },
+                p.codigo
+            );
+        }
+    }
+
+    fn ask_category(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        producto: &Producto,
+    ) -> Result<Option<Categoria>, BodegaError> {
+        //!Pide la categoría del objeto, proponiendo la sugerida. Si no se sugiere ninguna, propone crear una con el nombre de la categoría más concreta del producto.
+        let categories = read_categories(conn, &sesion.hogar)?;
+        let suggested = suggest_category(producto, &categories);
+        let new = producto.categorias.last().cloned();
+        match (&suggested, &new) {
+            (Some(c), _) => println!(
+                "Categoría (nada para usar la sugerida, {}):",
+                c.nombre
+            ),
+            (None, Some(n)) => println!(
+                "Ninguna categoría se parece a las del producto. Escribe una (nada para crear \"{}\"):",
+                n
+            ),
+            (None, None) => println!("Escribe el ID o el nombre de la categoría:"),
+        }
+        let input = read_input();
+        if !input.is_empty() {
+            return Ok(choose(&input, &categories));
+        }
+        if suggested.is_some() {
+            return Ok(suggested);
+        }
+        let new = match new {
+            Some(new) => new,
+            None => return Ok(None),
+        };
+        let id = insert_category(conn, sesion, new, String::from(CATEGORY_DESCRIPTION), None)?;
+        return Ok(get_category_by_id(
+            id,
+            read_categories(conn, &sesion.hogar)?,
+        ));
+    }
+
+    pub fn import_interactive(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        path: &Path,
+        query: &str,
+    ) -> Result<(), BodegaError> {
+        //!Busca productos en el volcado, deja elegir uno y lo importa como objeto, preguntando el nombre y la categoría con los del producto como sugerencia.
+        println!("Buscando \"{}\" en {}...", query.trim(), path.display());
+        let productos = search(path, query)?;
+        print_products(&productos);
+        let producto = match productos.len() {
+            0 => return Ok(()),
+            1 => productos[0].clone(),
+            n => {
+                println!("\nElige el número del producto (cualquier otra cosa para cancelar):");
+                match read_input().parse::<usize>() {
+                    Ok(i) if i >= 1 && i <= n => productos[i - 1].clone(),
+                    _ => {
+                        println!("No se importó ningún producto");
+                        return Ok(());
+                    }
+                }
+            }
+        };
+        println!(
+            "\nUnidad de medida: {}, envase: {}",
+            producto.medida,
+            producto
+                .envase
+                .map(|e| format!("{} {}", e, producto.medida))
+                .unwrap_or(String::from("desconocido"))
+        );
+        println!(
+            "Nombre del objeto (nada para usar \"{}\"):",
+            producto.nombre
+        );
+        let nombre = match read_input() {
+            n if n.is_empty() => producto.nombre.clone(),
+            n => n,
+        };
+        let categoria = match ask_category(conn, sesion, &producto)? {
+            Some(categoria) => categoria,
+            None => {
+                println!("No se importó ningún producto");
+                return Ok(());
+            }
+        };
+        let objeto = import_product(conn, sesion, &producto, &categoria, &nombre)?;
+        println!(
+            "Se creó el objeto {} en {} (ID:{})",
+            objeto.nombre, categoria.nombre, objeto.id
+        );
+        return Ok(());
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn pack_with_unit() {
+            assert_eq!(parse_pack("500 g"), (Some(500.0), String::from("g")));
+            assert_eq!(parse_pack("1,5L"), (Some(1.5), String::from("l")));
+            assert_eq!(parse_pack("750 gramos"), (Some(750.0), String::from("g")));
+        }
+
+        #[test]
+        fn pack_with_several_units() {
+            assert_eq!(parse_pack("6 x 330 ml"), (Some(1980.0), String::from("ml")));
+            assert_eq!(parse_pack("4×125g"), (Some(500.0), String::from("g")));
+        }
+
+        #[test]
+        fn pack_without_unit_or_number() {
+            assert_eq!(parse_pack("12"), (Some(12.0), String::from(DEFAULT_UNIT)));
+            assert_eq!(parse_pack("grande"), (None, String::from(DEFAULT_UNIT)));
+            assert_eq!(parse_pack(""), (None, String::from(DEFAULT_UNIT)));
+        }
+
+        #[test]
+        fn pack_with_unknown_unit_or_zero() {
+            assert_eq!(parse_pack("3 pintas"), (None, String::from(DEFAULT_UNIT)));
+            assert_eq!(parse_pack("0 g"), (None, String::from(DEFAULT_UNIT)));
+        }
+    }
+}
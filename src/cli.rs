@@ -0,0 +1,856 @@
+///Módulo que define los argumentos de la línea de comandos y ejecuta las órdenes que no necesitan el menú interactivo.
+pub mod cli {
+    use crate::barcode::barcode::{add_code, choose_object, remove_code, scan_interactive};
+    use crate::catalog::catalog::{import_interactive, print_products, search};
+    use crate::config::config::{Configuracion, Formato, Perfil};
+    use crate::connection_manager::connection_manager::{get_conn, Conexiones};
+    use crate::consumption::consumption::{
+        days_until, print_consumption, read_consumption, running_out, DEFAULT_WINDOW,
+    };
+    use crate::db_manager::db_manager::*;
+    use crate::error::error::BodegaError;
+    use crate::expiry::expiry::{
+        parse_storage, print_shelf_life, print_use_first, read_shelf_life, set_shelf_life,
+        use_first, Conservacion,
+    };
+    use crate::hogar::hogar::Sesion;
+    use crate::integrity::integrity::{describe_problem, repair_interactive, scan};
+    use crate::labels::labels::{labels_for, write_labels};
+    use crate::places::places::{
+        delete_place, insert_place, move_between, object_places, parse_sitio, place_stock,
+        print_object_places, print_places, read_places, sitio_storage,
+    };
+    use crate::planner::planner::{plan_stay, print_plan, set_ration, stay_days};
+    use crate::prices::prices::{
+        monthly_spend, print_purchases, print_spend, print_valuation, read_purchases, valuation,
+        DEFAULT_MONTHS,
+    };
+    use crate::recipes::recipes::{availability, cook, print_availability, read_recipes};
+    use crate::selector::selector::choose;
+    use crate::server::server::{self, category_summary, stock_rows};
+    use crate::shops::shops::{
+        export_shopping_list, link_supplier, print_shopping_list, read_shops, read_suppliers,
+        shopping_list, unlink_supplier,
+    };
+    use crate::stocktake::stocktake::count_interactive;
+    use crate::tags::tags::{tag_object, untag_object};
+    use crate::tui::tui;
+    use clap::{Parser, Subcommand};
+    use mysql::PooledConn;
+    use serde::Serialize;
+    use std::path::PathBuf;
+
+    #[derive(Parser)]
+    #[command(
+        name = "bodega-manager",
+        version,
+        about = "Gestor de existencias de la bodega"
+    )]
+    ///Argumentos de la línea de comandos. Sin ninguna orden, se abre el menú interactivo.
+    pub struct Cli {
+        ///Usuario con el que iniciar sesión. Si no se indica, se pregunta
+        #[arg(long, global = true)]
+        pub usuario: Option<String>,
+        ///Hogar con el que trabajar, por id o por nombre. Si no se indica y el usuario es miembro de varios, se pregunta
+        #[arg(long, global = true)]
+        pub hogar: Option<String>,
+        ///Fichero de configuración. Por defecto, $XDG_CONFIG_HOME/bodega-manager/config.toml
+        #[arg(long, global = true)]
+        pub config: Option<PathBuf>,
+        ///Perfil del fichero de configuración a usar
+        #[arg(long, global = true)]
+        pub perfil: Option<String>,
+        ///Servidor de mysql
+        #[arg(long, global = true)]
+        pub host: Option<String>,
+        ///Puerto del servidor de mysql
+        #[arg(long, global = true)]
+        pub puerto: Option<u16>,
+        ///Nombre de la base de datos
+        #[arg(long, global = true)]
+        pub base_datos: Option<String>,
+        ///Usuario de mysql, distinto del usuario de la aplicación
+        #[arg(long, global = true)]
+        pub db_usuario: Option<String>,
+        ///Fichero del que leer la contraseña de mysql
+        #[arg(long, global = true)]
+        pub db_contrasena_fichero: Option<String>,
+        ///Cifra la conexión con mysql con TLS
+        #[arg(long, global = true)]
+        pub tls: bool,
+        ///Certificado de la autoridad que firma el del servidor de mysql; implica --tls
+        #[arg(long, global = true)]
+        pub tls_ca: Option<String>,
+        ///Formato de salida de las órdenes: texto o json
+        #[arg(long, global = true)]
+        pub formato: Option<String>,
+        #[command(subcommand)]
+        pub comando: Option<Comando>,
+    }
+
+    #[derive(Subcommand)]
+    ///Órdenes que se pueden ejecutar directamente desde la línea de comandos. Los objetos se indican por id o por nombre.
+    pub enum Comando {
+        ///Muestra las existencias de un objeto, o de todos si no se indica ninguno
+        Existencias {
+            objeto: Option<String>,
+            ///Muestra solo los objetos de esta categoría y de sus subcategorías
+            #[arg(long, conflicts_with = "objeto")]
+            categoria: Option<String>,
+            ///Muestra solo los objetos con esta etiqueta
+            #[arg(long, conflicts_with = "objeto")]
+            etiqueta: Option<String>,
+        },
+        ///Muestra el árbol de categorías con cuántos objetos hay en cada una, contando sus subcategorías, y cuántos tienen existencias en cada lugar
+        Categorias,
+        ///Añade o retira (con una cantidad negativa) existencias de un objeto
+        Ajustar {
+            objeto: String,
+            #[arg(allow_negative_numbers = true)]
+            cantidad: f32,
+            ///Lugar en el que hacer la operación: casa o tara. Por defecto, el de la configuración
+            #[arg(long, value_parser = location_arg)]
+            lugar: Option<Procedencia>,
+            ///Sobrescribe la cantidad en lugar de sumarla
+            #[arg(long)]
+            fijar: bool,
+            ///Lo que se pagó en total por lo añadido, para registrar la compra
+            #[arg(long, value_parser = price_arg, conflicts_with = "fijar")]
+            precio: Option<f64>,
+            ///Cómo se conserva lo añadido, para calcular cuándo caduca: despensa, nevera o congelador. Por defecto, la habitual del objeto
+            #[arg(long, value_parser = storage_arg)]
+            tipo: Option<Conservacion>,
+        },
+        ///Translada existencias de un objeto de un lugar al otro
+        Transladar {
+            objeto: String,
+            cantidad: f32,
+            ///Lugar del que se sacan las existencias: casa o tara. Por defecto, el de la configuración
+            #[arg(long, value_parser = location_arg)]
+            desde: Option<Procedencia>,
+        },
+        ///Muestra las ubicaciones de cada lugar, unas dentro de otras, con cuántos objetos hay en cada una
+        Ubicaciones,
+        ///Crea una ubicación, como una habitación, una estantería o un congelador, o la elimina con --eliminar
+        Ubicacion {
+            nombre: String,
+            ///Dónde está: casa, tara o la ubicación que la contiene
+            #[arg(long, required_unless_present = "eliminar")]
+            en: Option<String>,
+            ///Cómo se conserva lo que se guarda en ella: despensa, nevera o congelador. Por defecto, como donde está
+            #[arg(long, value_parser = storage_arg, conflicts_with = "eliminar")]
+            tipo: Option<Conservacion>,
+            ///Elimina la ubicación en vez de crearla. Lo que había en ella queda sin ubicar
+            #[arg(long, conflicts_with = "en")]
+            eliminar: bool,
+        },
+        ///Muestra dónde está un objeto: cuánto hay en cada ubicación y sin ubicar en cada lugar
+        Donde { objeto: String },
+        ///Añade existencias de un objeto directamente en una ubicación, o las retira de ella con una cantidad negativa
+        Guardar {
+            objeto: String,
+            #[arg(allow_negative_numbers = true)]
+            cantidad: f32,
+            ubicacion: String,
+        },
+        ///Mueve existencias de un objeto de un sitio a otro. Cada sitio es una ubicación, o casa o tara para lo que está sin ubicar
+        Mover {
+            objeto: String,
+            cantidad: f32,
+            desde: String,
+            hasta: String,
+        },
+        ///Muestra el consumo medio diario de cada objeto en cada lugar y cuántos días durarán sus existencias
+        Consumo {
+            ///Número de días hacia atrás con los que calcular el consumo
+            #[arg(long, default_value_t = DEFAULT_WINDOW)]
+            dias: u32,
+            ///Muestra solo lo que se agotará antes de esta fecha (AAAA-MM-DD)
+            #[arg(long)]
+            hasta: Option<String>,
+        },
+        ///Calcula qué llevar y qué comprar para una estancia, según lo que consume cada persona al día
+        Estancia {
+            ///Fecha de llegada (AAAA-MM-DD)
+            desde: String,
+            ///Fecha de salida (AAAA-MM-DD)
+            hasta: String,
+            ///Número de personas
+            personas: u32,
+            ///Lugar de la estancia: casa o tara. Por defecto, el contrario al de la configuración
+            #[arg(long, value_parser = location_arg)]
+            destino: Option<Procedencia>,
+        },
+        ///Fija lo que consume una persona al día de un objeto, para planificar estancias. Con 0 se quita
+        Racion { objeto: String, cantidad: f32 },
+        ///Fija cuántos días dura un objeto en despensa, nevera o congelador, para calcular cuándo caduca lo que se añada. Con 0 se quita
+        Conservar {
+            objeto: String,
+            #[arg(value_parser = storage_arg)]
+            tipo: Conservacion,
+            dias: u32,
+        },
+        ///Muestra qué gastar primero: las existencias con fecha de caducidad, de la más cercana a la más lejana
+        Caducidades {
+            ///Muestra solo lo que caduca en este número de días o menos, incluido lo ya caducado
+            #[arg(long)]
+            dias: Option<i64>,
+        },
+        ///Muestra qué recetas se pueden cocinar con lo que hay en un lugar y qué falta para las demás
+        Recetas {
+            ///Lugar en el que cocinar: casa o tara. Por defecto, el de la configuración
+            #[arg(long, value_parser = location_arg)]
+            lugar: Option<Procedencia>,
+        },
+        ///Cocina una receta, retirando todos sus ingredientes a la vez
+        Cocinar {
+            receta: String,
+            ///Número de raciones
+            #[arg(long, default_value_t = 1)]
+            raciones: u32,
+            ///Lugar en el que cocinar: casa o tara. Por defecto, el de la configuración
+            #[arg(long, value_parser = location_arg)]
+            lugar: Option<Procedencia>,
+        },
+        ///Lee códigos de barras uno tras otro y añade (o retira) una unidad del objeto de cada uno, hasta una línea vacía
+        Escanear {
+            ///Lugar en el que hacer la operación: casa o tara. Por defecto, el de la configuración
+            #[arg(long, value_parser = location_arg)]
+            lugar: Option<Procedencia>,
+            ///Retira una unidad por lectura en vez de añadirla
+            #[arg(long)]
+            retirar: bool,
+            ///Cuenta un envase completo por lectura en vez de una unidad, si se sabe cuánto trae el del objeto
+            #[arg(long)]
+            envase: bool,
+        },
+        ///Pone una etiqueta libre a un objeto, como "sin gluten", o se la quita con --quitar
+        Etiquetar {
+            objeto: String,
+            etiqueta: String,
+            ///Quita la etiqueta en vez de ponerla
+            #[arg(long)]
+            quitar: bool,
+        },
+        ///Asocia un código de barras a un objeto, o se lo quita con --quitar
+        Codigo {
+            objeto: String,
+            codigo: String,
+            ///Quita el código en vez de asociarlo
+            #[arg(long)]
+            quitar: bool,
+        },
+        ///Busca un producto por código de barras o por nombre en un volcado de Open Food Facts y lo importa como objeto
+        Importar {
+            busqueda: String,
+            ///Volcado en el que buscar. Por defecto, el de la configuración
+            #[arg(long)]
+            catalogo: Option<PathBuf>,
+            ///Solo muestra los productos encontrados, sin importar ninguno
+            #[arg(long)]
+            listar: bool,
+        },
+        ///Guarda en SVG etiquetas con un código QR para imprimir, de los objetos dados o de todos si no se indica ninguno
+        Etiquetas {
+            objetos: Vec<String>,
+            ///Hace las etiquetas de este lugar: casa o tara. Se puede repetir; sin él, no llevan lugar
+            #[arg(long, value_parser = location_arg)]
+            lugar: Vec<Procedencia>,
+            ///Fichero en el que guardarlas. Si ocupan varias hojas, se guarda una por fichero, numeradas
+            #[arg(long, default_value = "etiquetas.svg")]
+            salida: PathBuf,
+        },
+        ///Muestra las compras registradas de un objeto
+        Precios { objeto: String },
+        ///Muestra el valor de las existencias de cada lugar por categoría y lo gastado cada mes
+        Valor {
+            ///Número de meses hacia atrás de los que mostrar el gasto
+            #[arg(long, default_value_t = DEFAULT_MONTHS)]
+            meses: u32,
+        },
+        ///Muestra las tiendas del hogar y lo que se compra en cada una
+        Tiendas,
+        ///Indica en qué tienda se compra un objeto, o deja de hacerlo con --quitar
+        Proveedor {
+            objeto: String,
+            tienda: String,
+            ///Es la tienda preferida para el objeto
+            #[arg(long)]
+            preferida: bool,
+            ///Quita la tienda del objeto en vez de añadirla
+            #[arg(long, conflicts_with = "preferida")]
+            quitar: bool,
+        },
+        ///Muestra la lista de la compra: lo que no tiene existencias en ningún lugar, agrupado por tienda
+        Comprar {
+            ///Guarda además la lista en este fichero CSV
+            #[arg(long)]
+            salida: Option<PathBuf>,
+        },
+        ///Hace inventario de un lugar: pide la cantidad contada de cada objeto y corrige las diferencias
+        Inventario {
+            ///Lugar del que hacer inventario: casa o tara. Por defecto, el de la configuración
+            #[arg(long, value_parser = location_arg)]
+            lugar: Option<Procedencia>,
+        },
+        ///Busca problemas de integridad en los datos del hogar activo: nombres repetidos, cantidades nulas o negativas y unidades desconocidas
+        Integridad {
+            ///Pregunta cómo reparar cada problema encontrado
+            #[arg(long)]
+            reparar: bool,
+        },
+        ///Comprueba la configuración, la conexión y la base de datos, y explica cómo arreglar lo que falle
+        Doctor,
+        ///Abre la interfaz a pantalla completa
+        Tui,
+        ///Sirve la API JSON y la interfaz web en la red local. --hogar indica el hogar por defecto de las peticiones
+        Serve {
+            ///Dirección y puerto en los que escuchar. Por defecto solo este equipo; 0.0.0.0:8080 lo abre a toda la red, con las contraseñas viajando sin cifrar
+            #[arg(long, default_value = "127.0.0.1:8080")]
+            direccion: String,
+        },
+    }
+
+    fn location_arg(name: &str) -> Result<Procedencia, String> {
+        //!Convierte el nombre de un lugar dado como argumento en su procedencia.
+        return parse_location(name).ok_or(format!(
+            "\"{}\" no es un lugar válido. Usa casa o tara",
+            name
+        ));
+    }
+
+    fn storage_arg(name: &str) -> Result<Conservacion, String> {
+        //!Convierte el nombre de un tipo de conservación dado como argumento.
+        return parse_storage(name).ok_or(format!(
+            "\"{}\" no es un tipo de conservación válido. Usa despensa, nevera o congelador",
+            name
+        ));
+    }
+
+    fn price_arg(text: &str) -> Result<f64, String> {
+        //!Convierte el precio dado como argumento, que puede llevar coma decimal.
+        return match parse_price(text) {
+            Ok(Some(price)) => Ok(price),
+            Ok(None) => Err(String::from("El precio no puede estar vacío")),
+            Err(e) => Err(e.to_string()),
+        };
+    }
+
+    pub fn config_flags(cli: &Cli) -> Perfil {
+        //!Ajustes de configuración dados como argumentos, que tienen prioridad sobre las variables de entorno y el fichero de configuración.
+        return Perfil {
+            host: cli.host.clone(),
+            puerto: cli.puerto,
+            usuario: cli.db_usuario.clone(),
+            contrasena_fichero: cli.db_contrasena_fichero.clone(),
+            base_datos: cli.base_datos.clone(),
+            tls: cli.tls.then_some(true),
+            tls_ca: cli.tls_ca.clone(),
+            formato: cli.formato.clone(),
+            ..Perfil::default()
+        };
+    }
+
+    fn print_json<T: Serialize>(value: &T) {
+        //!Muestra un valor en JSON, con sangría.
+        println!(
+            "{}",
+            serde_json::to_string_pretty(value).unwrap_or_default()
+        );
+    }
+
+    fn print_formatted<T: Serialize>(formato: &Formato, value: &T, text: impl FnOnce()) {
+        //!Muestra un resultado en el formato dado: en texto con la función text, o el valor en JSON.
+        match formato {
+            Formato::Texto => text(),
+            Formato::Json => print_json(value),
+        }
+    }
+
+    fn print_stock(
+        connection: &mut PooledConn,
+        sesion: &Sesion,
+        objects: Vec<Objeto>,
+        formato: &Formato,
+    ) -> Result<(), BodegaError> {
+        //!Muestra las existencias de los objetos dados en el formato configurado.
+        match formato {
+            Formato::Texto => {
+                print_all_stock(connection, &sesion.hogar, objects, false)?;
+            }
+            Formato::Json => {
+                print_json(&stock_rows(connection, objects)?);
+            }
+        }
+        return Ok(());
+    }
+
+    pub fn run(
+        conexiones: &Conexiones,
+        sesion: &Sesion,
+        config: &Configuracion,
+        comando: Comando,
+    ) -> Result<(), BodegaError> {
+        //!Ejecuta la orden dada en la línea de comandos, en nombre del usuario que ha iniciado sesión y en su hogar activo. La interfaz a pantalla completa y el servidor usan el conjunto de conexiones; el resto de órdenes, una sola conexión.
+        match comando {
+            Comando::Tui => {
+                tui::run(conexiones, sesion).map_err(|e| {
+                    BodegaError::Terminal(format!(
+                        "Ocurrió un error en la interfaz a pantalla completa: {}",
+                        e
+                    ))
+                })?;
+            }
+            Comando::Serve { direccion } => {
+                server::serve(conexiones, &direccion, Some(sesion.hogar.nombre.as_str()));
+            }
+            comando => {
+                let mut connection = get_conn(conexiones)?;
+                return query(&mut connection, sesion, config, comando);
+            }
+        }
+        return Ok(());
+    }
+
+    fn show_stock(
+        connection: &mut PooledConn,
+        sesion: &Sesion,
+        config: &Configuracion,
+        objeto: Option<String>,
+        categoria: Option<String>,
+        etiqueta: Option<String>,
+    ) -> Result<(), BodegaError> {
+        //!Muestra las existencias de un objeto o, sin él, las de todos los de la categoría y con la etiqueta dadas, en el formato de la configuración.
+        match objeto {
+            Some(text) => {
+                let objects = read_objects(connection, &sesion.hogar)?;
+                let found = choose_object(connection, &sesion.hogar, &text, &objects)?;
+                if let (Some(obj), Formato::Json) = (&found, &config.formato) {
+                    print_stock(connection, sesion, vec![obj.clone()], &config.formato)?;
+                } else if let Some(obj) = found {
+                    match get_stock_by_id(connection, &sesion.hogar, obj.id)? {
+                        (None, None) => {
+                            println!("{}: Sin existencias", obj.nombre);
+                        }
+                        (home, tara) => {
+                            for e in [home, tara].into_iter().flatten() {
+                                println!(
+                                    "{}: x{} {} en {}",
+                                    e.objeto.nombre,
+                                    e.cantidad,
+                                    e.objeto.medida,
+                                    get_string_name(&e.procedencia)
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+            None => {
+                let mut list = read_objects(connection, &sesion.hogar)?;
+                if let Some(text) = categoria {
+                    let categories = read_categories(connection, &sesion.hogar)?;
+                    match choose(&text, &categories) {
+                        Some(cat) => {
+                            let ids = subtree(&categories, cat.id);
+                            list.retain(|o| ids.contains(&o.categoria.id));
+                        }
+                        None => return Ok(()),
+                    }
+                }
+                if let Some(tag) = etiqueta {
+                    list = with_tag(list, &tag);
+                }
+                match config.formato {
+                    Formato::Texto => print_all_stock(connection, &sesion.hogar, list, true)?,
+                    Formato::Json => print_stock(connection, sesion, list, &config.formato)?,
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    fn query(
+        connection: &mut PooledConn,
+        sesion: &Sesion,
+        config: &Configuracion,
+        comando: Comando,
+    ) -> Result<(), BodegaError> {
+        //!Ejecuta una orden que consulta o modifica las existencias sobre la conexión dada. Los resultados se muestran en el formato de la configuración.
+        match comando {
+            Comando::Existencias {
+                objeto,
+                categoria,
+                etiqueta,
+            } => show_stock(connection, sesion, config, objeto, categoria, etiqueta)?,
+            Comando::Categorias => {
+                let summary = category_summary(connection, &sesion.hogar)?;
+                print_formatted(&config.formato, &summary, || {
+                    for r in &summary {
+                        println!(
+                            "{}({}) {}: {} objetos, {} con existencias en casa, {} en tara",
+                            "    ".repeat(r.nivel),
+                            r.categoria.id,
+                            r.categoria.nombre,
+                            r.objetos,
+                            r.con_existencias_casa,
+                            r.con_existencias_tara
+                        );
+                    }
+                });
+            }
+            Comando::Ajustar {
+                objeto,
+                cantidad,
+                lugar,
+                fijar,
+                precio,
+                tipo,
+            } => {
+                let lugar = lugar.unwrap_or(config.lugar.clone());
+                let objects = read_objects(connection, &sesion.hogar)?;
+                if let Some(obj) = choose_object(connection, &sesion.hogar, &objeto, &objects)? {
+                    match precio {
+                        Some(precio) => purchase_stock(
+                            connection,
+                            sesion,
+                            obj.id,
+                            cantidad,
+                            precio,
+                            &lugar,
+                            tipo.as_ref(),
+                        )?,
+                        None => adjust_stock(
+                            connection,
+                            sesion,
+                            obj.id,
+                            fijar,
+                            cantidad,
+                            &lugar,
+                            tipo.as_ref(),
+                        )?,
+                    };
+                    print_stock(connection, sesion, vec![obj], &config.formato)?;
+                }
+            }
+            Comando::Transladar {
+                objeto,
+                cantidad,
+                desde,
+            } => {
+                let desde = desde.unwrap_or(config.lugar.clone());
+                let objects = read_objects(connection, &sesion.hogar)?;
+                if let Some(obj) = choose_object(connection, &sesion.hogar, &objeto, &objects)? {
+                    move_stock(connection, sesion, obj.id, cantidad, &desde)?;
+                    print_stock(connection, sesion, vec![obj], &config.formato)?;
+                }
+            }
+            Comando::Ubicaciones => {
+                let places = read_places(connection, &sesion.hogar)?;
+                match config.formato {
+                    Formato::Texto => print_places(connection, &sesion.hogar, &places)?,
+                    Formato::Json => print_json(&places),
+                }
+            }
+            Comando::Ubicacion {
+                nombre,
+                en,
+                eliminar,
+                tipo,
+            } => {
+                let places = read_places(connection, &sesion.hogar)?;
+                match (eliminar, en) {
+                    (true, _) => {
+                        if let Some(u) = choose(&nombre, &places) {
+                            delete_place(connection, sesion, u.id)?;
+                            println!(
+                                "Se eliminó {}. Lo que había en ella queda sin ubicar",
+                                u.ruta
+                            );
+                        }
+                    }
+                    (false, Some(en)) => {
+                        if let Some(dentro) = parse_sitio(&en, &places) {
+                            let tipo = tipo.unwrap_or(sitio_storage(&dentro));
+                            let u = insert_place(connection, sesion, &nombre, &dentro, &tipo)?;
+                            println!("Se creó la ubicación {} (ID:{})", u.ruta, u.id);
+                        }
+                    }
+                    (false, None) => {}
+                }
+            }
+            Comando::Donde { objeto } => {
+                let objects = read_objects(connection, &sesion.hogar)?;
+                if let Some(obj) = choose_object(connection, &sesion.hogar, &objeto, &objects)? {
+                    let repartos = object_places(connection, &sesion.hogar, &obj)?;
+                    print_formatted(&config.formato, &repartos, || {
+                        print_object_places(&obj, &repartos)
+                    });
+                }
+            }
+            Comando::Guardar {
+                objeto,
+                cantidad,
+                ubicacion,
+            } => {
+                let objects = read_objects(connection, &sesion.hogar)?;
+                let places = read_places(connection, &sesion.hogar)?;
+                if let Some(obj) = choose_object(connection, &sesion.hogar, &objeto, &objects)? {
+                    if let Some(u) = choose(&ubicacion, &places) {
+                        place_stock(connection, sesion, &obj, &u, cantidad)?;
+                        print_object_places(&obj, &object_places(connection, &sesion.hogar, &obj)?);
+                    }
+                }
+            }
+            Comando::Mover {
+                objeto,
+                cantidad,
+                desde,
+                hasta,
+            } => {
+                let objects = read_objects(connection, &sesion.hogar)?;
+                let places = read_places(connection, &sesion.hogar)?;
+                if let Some(obj) = choose_object(connection, &sesion.hogar, &objeto, &objects)? {
+                    if let (Some(desde), Some(hasta)) =
+                        (parse_sitio(&desde, &places), parse_sitio(&hasta, &places))
+                    {
+                        move_between(connection, sesion, &obj, cantidad, &desde, &hasta)?;
+                        print_object_places(&obj, &object_places(connection, &sesion.hogar, &obj)?);
+                    }
+                }
+            }
+            Comando::Consumo { dias, hasta } => {
+                let mut consumos = read_consumption(connection, &sesion.hogar, dias)?;
+                if let Some(date) = hasta {
+                    consumos = running_out(&consumos, days_until(connection, &date)?);
+                }
+                print_formatted(&config.formato, &consumos, || print_consumption(&consumos));
+            }
+            Comando::Estancia {
+                desde,
+                hasta,
+                personas,
+                destino,
+            } => {
+                let destino = destino.unwrap_or(contrary(&config.lugar));
+                let dias = stay_days(connection, &desde, &hasta)?;
+                let plan = plan_stay(connection, &sesion.hogar, &destino, dias, personas)?;
+                print_formatted(&config.formato, &plan, || print_plan(&plan));
+            }
+            Comando::Importar {
+                busqueda,
+                catalogo,
+                listar,
+            } => {
+                let path = catalogo.or(config.catalogo.clone()).ok_or(BodegaError::Configuracion(String::from(
+                    "No se ha indicado ningún catálogo. Usa --catalogo, BODEGA_CATALOGO o la clave catalogo del perfil",
+                )))?;
+                match listar {
+                    true => {
+                        let productos = search(&path, &busqueda)?;
+                        print_formatted(&config.formato, &productos, || print_products(&productos));
+                    }
+                    false => import_interactive(connection, sesion, &path, &busqueda)?,
+                }
+            }
+            Comando::Etiquetas {
+                objetos,
+                lugar,
+                salida,
+            } => {
+                let all = read_objects(connection, &sesion.hogar)?;
+                let mut selected = Vec::new();
+                for text in &objetos {
+                    match choose_object(connection, &sesion.hogar, text, &all)? {
+                        Some(obj) => selected.push(obj),
+                        None => return Ok(()),
+                    }
+                }
+                if objetos.is_empty() {
+                    selected = all;
+                }
+                for f in write_labels(&salida, &labels_for(&selected, &lugar))? {
+                    println!("Se guardaron las etiquetas en {}", f.display());
+                }
+            }
+            Comando::Precios { objeto } => {
+                let objects = read_objects(connection, &sesion.hogar)?;
+                if let Some(obj) = choose_object(connection, &sesion.hogar, &objeto, &objects)? {
+                    let compras = read_purchases(connection, &obj)?;
+                    print_formatted(&config.formato, &compras, || {
+                        print_purchases(&obj, &compras)
+                    });
+                }
+            }
+            Comando::Valor { meses } => {
+                let valores = valuation(connection, &sesion.hogar)?;
+                let gastos = monthly_spend(connection, &sesion.hogar, meses)?;
+                let json = serde_json::json!({
+                    "valor": valores,
+                    "gasto": gastos,
+                });
+                print_formatted(&config.formato, &json, || {
+                    print_valuation(&valores);
+                    println!("\nGasto de los últimos {} meses:", meses);
+                    print_spend(&gastos);
+                });
+            }
+            Comando::Tiendas => {
+                let shops = read_shops(connection, &sesion.hogar)?;
+                let suppliers = read_suppliers(connection, &sesion.hogar)?;
+                let objects = read_objects(connection, &sesion.hogar)?;
+                print_formatted(&config.formato, &shops, || {
+                    for t in &shops {
+                        println!("{} (ID:{}) {}", t.nombre, t.id, t.notas);
+                        for (id, p) in suppliers.iter().filter(|(_, p)| p.tienda.id == t.id) {
+                            if let Some(o) = objects.iter().find(|o| o.id == *id) {
+                                println!(
+                                    "  {}{}",
+                                    o.nombre,
+                                    if p.preferida { " (preferida)" } else { "" }
+                                );
+                            }
+                        }
+                    }
+                });
+            }
+            Comando::Proveedor {
+                objeto,
+                tienda,
+                preferida,
+                quitar,
+            } => {
+                let objects = read_objects(connection, &sesion.hogar)?;
+                let shops = read_shops(connection, &sesion.hogar)?;
+                if let (Some(obj), Some(t)) = (
+                    choose_object(connection, &sesion.hogar, &objeto, &objects)?,
+                    choose(&tienda, &shops),
+                ) {
+                    match quitar {
+                        true => {
+                            unlink_supplier(connection, sesion, &obj, &t)?;
+                            println!("{} ya no se compra en {}", obj.nombre, t.nombre);
+                        }
+                        false => {
+                            link_supplier(connection, sesion, &obj, &t, preferida)?;
+                            println!("{} se compra en {}", obj.nombre, t.nombre);
+                        }
+                    }
+                }
+            }
+            Comando::Comprar { salida } => {
+                let groups = shopping_list(connection, &sesion.hogar)?;
+                print_formatted(&config.formato, &groups, || print_shopping_list(&groups));
+                if let Some(path) = salida {
+                    export_shopping_list(&path, &groups)?;
+                }
+            }
+            Comando::Escanear {
+                lugar,
+                retirar,
+                envase,
+            } => {
+                let lugar = lugar.unwrap_or(config.lugar.clone());
+                scan_interactive(connection, sesion, &lugar, retirar, envase)?;
+            }
+            Comando::Codigo {
+                objeto,
+                codigo,
+                quitar,
+            } => match quitar {
+                true => {
+                    let obj = remove_code(connection, sesion, &codigo)?;
+                    println!("El código {} se quitó de {}", codigo.trim(), obj.nombre);
+                }
+                false => {
+                    let objects = read_objects(connection, &sesion.hogar)?;
+                    if let Some(obj) = choose_object(connection, &sesion.hogar, &objeto, &objects)?
+                    {
+                        add_code(connection, sesion, &obj, &codigo)?;
+                        println!("El código {} se asoció a {}", codigo.trim(), obj.nombre);
+                    }
+                }
+            },
+            Comando::Etiquetar {
+                objeto,
+                etiqueta,
+                quitar,
+            } => {
+                let objects = read_objects(connection, &sesion.hogar)?;
+                if let Some(obj) = choose_object(connection, &sesion.hogar, &objeto, &objects)? {
+                    match quitar {
+                        true => {
+                            untag_object(connection, sesion, &obj, &etiqueta)?;
+                            println!("Se quitó la etiqueta {} de {}", etiqueta.trim(), obj.nombre);
+                        }
+                        false => {
+                            tag_object(connection, sesion, &obj, &etiqueta)?;
+                            println!("{} tiene ahora la etiqueta {}", obj.nombre, etiqueta.trim());
+                        }
+                    }
+                }
+            }
+            Comando::Racion { objeto, cantidad } => {
+                let objects = read_objects(connection, &sesion.hogar)?;
+                if let Some(obj) = choose_object(connection, &sesion.hogar, &objeto, &objects)? {
+                    set_ration(connection, sesion, &obj, cantidad)?;
+                    println!(
+                        "{}: x{} {} por persona y día",
+                        obj.nombre, cantidad, obj.medida
+                    );
+                }
+            }
+            Comando::Conservar { objeto, tipo, dias } => {
+                let objects = read_objects(connection, &sesion.hogar)?;
+                if let Some(obj) = choose_object(connection, &sesion.hogar, &objeto, &objects)? {
+                    set_shelf_life(connection, sesion, &obj, &tipo, dias)?;
+                    print_shelf_life(&obj, &read_shelf_life(connection, &obj)?);
+                }
+            }
+            Comando::Caducidades { dias } => {
+                let lotes = use_first(connection, &sesion.hogar, dias)?;
+                print_formatted(&config.formato, &lotes, || print_use_first(&lotes));
+            }
+            Comando::Recetas { lugar } => {
+                let lugar = lugar.unwrap_or(config.lugar.clone());
+                let list = availability(connection, &sesion.hogar, &lugar)?;
+                print_formatted(&config.formato, &list, || print_availability(&list, &lugar));
+            }
+            Comando::Cocinar {
+                receta,
+                raciones,
+                lugar,
+            } => {
+                let lugar = lugar.unwrap_or(config.lugar.clone());
+                if let Some(receta) = choose(&receta, &read_recipes(connection, &sesion.hogar)?) {
+                    cook(connection, sesion, &receta, raciones, &lugar)?;
+                    let ids: Vec<i32> = receta.ingredientes.iter().map(|i| i.objeto.id).collect();
+                    let objects = read_objects(connection, &sesion.hogar)?
+                        .into_iter()
+                        .filter(|o| ids.contains(&o.id))
+                        .collect();
+                    print_stock(connection, sesion, objects, &config.formato)?;
+                }
+            }
+            Comando::Inventario { lugar } => {
+                count_interactive(connection, sesion, &lugar.unwrap_or(config.lugar.clone()))?;
+            }
+            Comando::Integridad { reparar } => {
+                let problems = scan(connection, &sesion.hogar)?;
+                print_formatted(&config.formato, &problems, || {
+                    if problems.is_empty() {
+                        println!("No se encontró ningún problema");
+                    }
+                    for p in &problems {
+                        println!("{}", describe_problem(p));
+                    }
+                });
+                if reparar && !problems.is_empty() {
+                    repair_interactive(connection, sesion, &problems);
+                }
+            }
+            Comando::Tui | Comando::Serve { .. } | Comando::Doctor => {}
+        }
+        return Ok(());
+    }
+}
@@ -0,0 +1,512 @@
+///Módulo que obtiene la configuración del programa a partir de los argumentos de la línea de comandos, las variables de entorno (y el archivo .env) y el fichero de configuración con perfiles, por ese orden de prioridad.
+pub mod config {
+    use crate::db_manager::db_manager::{parse_location, Procedencia};
+    use crate::error::error::BodegaError;
+    use serde::Deserialize;
+    use std::collections::HashMap;
+    use std::env;
+    use std::io::IsTerminal;
+    use std::path::PathBuf;
+
+    ///Especifica el nombre de la variable de entorno del usuario de la base de datos
+    const VAR_USER: &str = "DB_USER";
+    ///Especifica el nombre de la variable de entorno de la contraseña de la base de datos
+    const VAR_PASSWORD: &str = "DB_PASSWORD";
+    ///Especifica el nombre de la variable de entorno con la ruta de un fichero que contiene la contraseña de la base de datos
+    const VAR_PASSWORD_FILE: &str = "DB_PASSWORD_FILE";
+    ///Especifica el nombre de la variable de entorno que activa TLS en la conexión con la base de datos
+    const VAR_TLS: &str = "DB_TLS";
+    ///Especifica el nombre de la variable de entorno con el certificado de la autoridad que firma el del servidor
+    const VAR_TLS_CA: &str = "DB_TLS_CA";
+    ///Especifica el nombre de la variable de entorno que indica si se comprueba el certificado del servidor
+    const VAR_TLS_VERIFY: &str = "DB_TLS_VERIFY";
+    ///Especifica el nombre de la variable de entorno del host de la base de datos
+    const VAR_HOST: &str = "DB_HOST";
+    ///Especifica el nombre de la variable de entorno del puerto de la base de datos
+    const VAR_PORT: &str = "DB_PORT";
+    ///Especifica el nombre de la variable de entorno con el nombre de la base de datos
+    const VAR_DATABASE: &str = "DB_NAME";
+    ///Especifica el nombre de la variable de entorno con la ruta del fichero de configuración
+    const VAR_CONFIG: &str = "BODEGA_CONFIG";
+    ///Especifica el nombre de la variable de entorno con el perfil a usar
+    const VAR_PROFILE: &str = "BODEGA_PERFIL";
+    ///Especifica el nombre de la variable de entorno con el lugar por defecto
+    const VAR_LOCATION: &str = "BODEGA_LUGAR";
+    ///Especifica el nombre de la variable de entorno con el idioma
+    const VAR_LANGUAGE: &str = "BODEGA_IDIOMA";
+    ///Especifica el nombre de la variable de entorno con el formato de salida
+    const VAR_FORMAT: &str = "BODEGA_FORMATO";
+    ///Especifica el nombre de la variable de entorno con el volcado de Open Food Facts del que importar objetos
+    const VAR_CATALOG: &str = "BODEGA_CATALOGO";
+
+    ///Directorio del programa dentro del directorio de configuración
+    const APP_DIR: &str = "bodega-manager";
+    ///Nombre del fichero de configuración
+    const CONFIG_FILE: &str = "config.toml";
+    ///Host que se usa si no se indica ninguno
+    const DEFAULT_HOST: &str = "localhost";
+    ///Puerto que se usa si no se indica ninguno
+    const DEFAULT_PORT: u16 = 3306;
+    ///Base de datos que se usa si no se indica ninguna, la que crea init-db.sql
+    const DEFAULT_DATABASE: &str = "bodega";
+    ///Idiomas en los que está disponible el programa
+    const LANGUAGES: [&str; 1] = ["es"];
+
+    ///Formato en el que las órdenes de la línea de comandos muestran los resultados.
+    #[derive(PartialEq, Clone, Debug)]
+    pub enum Formato {
+        ///Texto para leer en la terminal
+        Texto,
+        ///JSON, para usar desde otros programas
+        Json,
+    }
+
+    pub fn parse_format(name: &str) -> Option<Formato> {
+        //!Obtiene el formato a partir de su nombre, sin importar mayúsculas ni minúsculas.
+        match name.trim().to_lowercase().as_str() {
+            "texto" => Some(Formato::Texto),
+            "json" => Some(Formato::Json),
+            _ => None,
+        }
+    }
+
+    pub fn parse_bool(value: &str) -> Option<bool> {
+        //!Obtiene un valor verdadero o falso escrito como si/no, true/false o 1/0, sin importar mayúsculas ni minúsculas.
+        match value.trim().to_lowercase().as_str() {
+            "si" | "sí" | "true" | "1" => Some(true),
+            "no" | "false" | "0" => Some(false),
+            _ => None,
+        }
+    }
+
+    ///Ajustes de un perfil del fichero de configuración. También representa los ajustes dados por la línea de comandos o por las variables de entorno: los que no se indican quedan en None.
+    #[derive(Deserialize, Default, Clone)]
+    #[serde(deny_unknown_fields)]
+    pub struct Perfil {
+        pub host: Option<String>,
+        pub puerto: Option<u16>,
+        pub usuario: Option<String>,
+        pub contrasena: Option<String>,
+        ///Fichero del que leer la contraseña, en lugar de escribirla en la configuración
+        pub contrasena_fichero: Option<String>,
+        pub base_datos: Option<String>,
+        ///Si se cifra la conexión con TLS
+        pub tls: Option<bool>,
+        ///Certificado de la autoridad que firma el del servidor, si no es una de las del sistema
+        pub tls_ca: Option<String>,
+        ///Si se comprueba el certificado del servidor
+        pub tls_verificar: Option<bool>,
+        pub lugar: Option<String>,
+        pub idioma: Option<String>,
+        pub formato: Option<String>,
+        ///Volcado de Open Food Facts descargado del que importar objetos
+        pub catalogo: Option<String>,
+    }
+
+    ///Contenido del fichero de configuración: el perfil por defecto y los perfiles por nombre.
+    #[derive(Deserialize, Default)]
+    #[serde(deny_unknown_fields)]
+    struct Fichero {
+        perfil: Option<String>,
+        #[serde(default)]
+        perfiles: HashMap<String, Perfil>,
+    }
+
+    ///Ajustes de TLS de la conexión con la base de datos.
+    #[derive(Clone)]
+    pub struct Tls {
+        ///Certificado de la autoridad que firma el del servidor; si no hay, se usan las del sistema
+        pub ca: Option<PathBuf>,
+        ///Si se comprueba que el certificado del servidor es válido y corresponde al host
+        pub verificar: bool,
+    }
+
+    ///Configuración con la que se ejecuta el programa, ya resuelta.
+    pub struct Configuracion {
+        ///Fichero de configuración del que se leyó, si había alguno
+        pub fichero: Option<PathBuf>,
+        ///Perfil del fichero que se usó, si se usó alguno
+        pub perfil: Option<String>,
+        pub host: String,
+        pub puerto: u16,
+        pub usuario: String,
+        pub contrasena: String,
+        pub base_datos: String,
+        ///Ajustes de TLS, si la conexión se cifra
+        pub tls: Option<Tls>,
+        ///Lugar que usan las órdenes cuando no se indica ninguno
+        pub lugar: Procedencia,
+        pub formato: Formato,
+        ///Volcado de Open Food Facts del que importar objetos, si se ha configurado
+        pub catalogo: Option<PathBuf>,
+    }
+
+    pub fn default_config_path() -> Option<PathBuf> {
+        //!Ruta del fichero de configuración según la especificación XDG: $XDG_CONFIG_HOME/bodega-manager/config.toml, o ~/.config/bodega-manager/config.toml si la variable no está definida.
+        let base = match env::var_os("XDG_CONFIG_HOME").filter(|d| !d.is_empty()) {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+        };
+        return Some(base.join(APP_DIR).join(CONFIG_FILE));
+    }
+
+    fn var(name: &str) -> Option<String> {
+        //!Obtiene una variable de entorno, tratando una variable vacía como si no estuviera.
+        return env::var(name).ok().filter(|v| !v.trim().is_empty());
+    }
+
+    fn split_host(host: String) -> (String, Option<u16>, Option<String>) {
+        //!Separa un host escrito como host:puerto/base_de_datos, la forma en que se daba antes DB_HOST, en sus tres partes.
+        let (address, database) = match host.split_once('/') {
+            Some((address, database)) => (String::from(address), Some(String::from(database))),
+            None => (host, None),
+        };
+        return match address.rsplit_once(':') {
+            Some((name, port)) => match port.parse::<u16>() {
+                Ok(port) => (String::from(name), Some(port), database),
+                Err(_) => (address, None, database),
+            },
+            None => (address, None, database),
+        };
+    }
+
+    fn env_bool(name: &str) -> Result<Option<bool>, BodegaError> {
+        //!Obtiene una variable de entorno de tipo si/no, o un error si tiene otro valor.
+        return match var(name) {
+            Some(value) => match parse_bool(&value) {
+                Some(b) => Ok(Some(b)),
+                None => Err(BodegaError::Configuracion(format!(
+                    "La variable {} debe ser si o no, no \"{}\"",
+                    name, value
+                ))),
+            },
+            None => Ok(None),
+        };
+    }
+
+    fn from_env() -> Result<Perfil, BodegaError> {
+        //!Lee los ajustes de las variables de entorno, incluidas las del archivo .env del directorio actual.
+        let _ = dotenv::from_path(".env");
+        let (host, port, database) = match var(VAR_HOST) {
+            Some(host) => {
+                let (host, port, database) = split_host(host);
+                (Some(host), port, database)
+            }
+            None => (None, None, None),
+        };
+        let puerto = match var(VAR_PORT) {
+            Some(p) => Some(p.trim().parse::<u16>().map_err(|_| {
+                BodegaError::Configuracion(format!(
+                    "La variable {} no es un puerto válido: {}",
+                    VAR_PORT, p
+                ))
+            })?),
+            None => port,
+        };
+        return Ok(Perfil {
+            host,
+            puerto,
+            usuario: var(VAR_USER),
+            contrasena: var(VAR_PASSWORD),
+            contrasena_fichero: var(VAR_PASSWORD_FILE),
+            base_datos: var(VAR_DATABASE).or(database),
+            tls: env_bool(VAR_TLS)?,
+            tls_ca: var(VAR_TLS_CA),
+            tls_verificar: env_bool(VAR_TLS_VERIFY)?,
+            lugar: var(VAR_LOCATION),
+            idioma: var(VAR_LANGUAGE),
+            formato: var(VAR_FORMAT),
+            catalogo: var(VAR_CATALOG),
+        });
+    }
+
+    fn read_file(path: &PathBuf) -> Result<Fichero, BodegaError> {
+        //!Lee y analiza el fichero de configuración.
+        let text = std::fs::read_to_string(path).map_err(|e| {
+            BodegaError::Configuracion(format!("No se pudo leer {}: {}", path.display(), e))
+        })?;
+        return toml::from_str(&text).map_err(|e| {
+            BodegaError::Configuracion(format!(
+                "El fichero de configuración {} no es válido: {}",
+                path.display(),
+                e
+            ))
+        });
+    }
+
+    fn overlay(top: Perfil, bottom: Perfil) -> Perfil {
+        //!Combina dos conjuntos de ajustes: los de top tienen prioridad, y los que falten se toman de bottom. La contraseña y el fichero de la contraseña van juntos, para que una contraseña de un nivel inferior no tape el fichero dado en uno superior.
+        let (contrasena, contrasena_fichero) =
+            if top.contrasena.is_some() || top.contrasena_fichero.is_some() {
+                (top.contrasena, top.contrasena_fichero)
+            } else {
+                (bottom.contrasena, bottom.contrasena_fichero)
+            };
+        return Perfil {
+            host: top.host.or(bottom.host),
+            puerto: top.puerto.or(bottom.puerto),
+            usuario: top.usuario.or(bottom.usuario),
+            contrasena,
+            contrasena_fichero,
+            base_datos: top.base_datos.or(bottom.base_datos),
+            tls: top.tls.or(bottom.tls),
+            tls_ca: top.tls_ca.or(bottom.tls_ca),
+            tls_verificar: top.tls_verificar.or(bottom.tls_verificar),
+            lugar: top.lugar.or(bottom.lugar),
+            idioma: top.idioma.or(bottom.idioma),
+            formato: top.formato.or(bottom.formato),
+            catalogo: top.catalogo.or(bottom.catalogo),
+        };
+    }
+
+    fn read_password_file(path: &str) -> Result<String, BodegaError> {
+        //!Lee la contraseña de un fichero, sin el salto de línea final.
+        let text = std::fs::read_to_string(path).map_err(|e| {
+            BodegaError::Configuracion(format!(
+                "No se pudo leer la contraseña de la base de datos de {}: {}",
+                path, e
+            ))
+        })?;
+        return Ok(String::from(text.trim_end_matches(['\n', '\r'])));
+    }
+
+    fn resolve_password(
+        contrasena: Option<String>,
+        fichero: Option<String>,
+        usuario: &str,
+        host: &str,
+    ) -> Result<String, BodegaError> {
+        //!Obtiene la contraseña de la base de datos: la configurada, la del fichero configurado o, si no hay ninguna y se está usando una terminal, la que se escriba en ella.
+        if let Some(contrasena) = contrasena {
+            return Ok(contrasena);
+        }
+        if let Some(fichero) = fichero {
+            return read_password_file(&fichero);
+        }
+        if !std::io::stdin().is_terminal() {
+            return Err(BodegaError::Configuracion(format!(
+                "No se ha configurado la contraseña de la base de datos. Indícala con {} o {}, o con las claves contrasena o contrasena_fichero del perfil",
+                VAR_PASSWORD, VAR_PASSWORD_FILE
+            )));
+        }
+        return rpassword::prompt_password(format!(
+            "Contraseña de mysql para {}@{}: ",
+            usuario, host
+        ))
+        .map_err(|e| {
+            BodegaError::Configuracion(format!(
+                "No se pudo leer la contraseña de la base de datos: {}",
+                e
+            ))
+        });
+    }
+
+    pub fn describe(config: &Configuracion) -> String {
+        //!Describe a qué base de datos se conecta la configuración y de dónde sale, sin mostrar la contraseña.
+        let mut text = format!(
+            "{}@{}:{}/{}",
+            config.usuario, config.host, config.puerto, config.base_datos
+        );
+        if config.tls.is_some() {
+            text.push_str(" con TLS");
+        }
+        if let Some(fichero) = &config.fichero {
+            text.push_str(&format!(" ({}", fichero.display()));
+            if let Some(perfil) = &config.perfil {
+                text.push_str(&format!(", perfil {}", perfil));
+            }
+            text.push(')');
+        }
+        return text;
+    }
+
+    pub fn load(
+        path: Option<PathBuf>,
+        profile: Option<String>,
+        flags: Perfil,
+    ) -> Result<Configuracion, BodegaError> {
+        //!Obtiene la configuración. Cada ajuste se toma de los argumentos de la línea de comandos (flags), si no de las variables de entorno, si no del perfil del fichero de configuración, y si no de su valor por defecto. El perfil es el indicado con --perfil o BODEGA_PERFIL, el de la clave perfil del fichero, o el único que haya.
+        let environment = from_env()?;
+        let (path, explicit) = match path.or(var(VAR_CONFIG).map(PathBuf::from)) {
+            Some(path) => (Some(path), true),
+            None => (default_config_path(), false),
+        };
+        let (fichero, file) = match path {
+            Some(path) if explicit || path.exists() => (Some(path.clone()), read_file(&path)?),
+            _ => (None, Fichero::default()),
+        };
+        let perfil = profile.or(var(VAR_PROFILE)).or(file.perfil.clone());
+        let from_file = match &perfil {
+            Some(name) => match file.perfiles.get(name) {
+                Some(p) => p.clone(),
+                None => {
+                    return Err(BodegaError::Configuracion(format!(
+                        "El perfil {} no existe en {}",
+                        name,
+                        fichero
+                            .as_ref()
+                            .map(|f| f.display().to_string())
+                            .unwrap_or(String::from("la configuración, que no tiene fichero"))
+                    )));
+                }
+            },
+            None => match file.perfiles.len() {
+                0 => Perfil::default(),
+                1 => file.perfiles.values().next().cloned().unwrap_or_default(),
+                _ => {
+                    return Err(BodegaError::Configuracion(String::from(
+                        "Hay varios perfiles configurados. Indica uno con --perfil, con BODEGA_PERFIL o con la clave perfil del fichero",
+                    )));
+                }
+            },
+        };
+        let perfil = perfil.or(file.perfiles.keys().next().cloned());
+        let merged = overlay(flags, overlay(environment, from_file));
+
+        let lugar = match merged.lugar {
+            Some(name) => parse_location(&name).ok_or(BodegaError::Configuracion(format!(
+                "\"{}\" no es un lugar válido. Usa casa o tara",
+                name
+            )))?,
+            None => Procedencia::Casa,
+        };
+        let formato = match merged.formato {
+            Some(name) => parse_format(&name).ok_or(BodegaError::Configuracion(format!(
+                "\"{}\" no es un formato válido. Usa texto o json",
+                name
+            )))?,
+            None => Formato::Texto,
+        };
+        let idioma = merged.idioma.unwrap_or(String::from(LANGUAGES[0]));
+        if !LANGUAGES.contains(&idioma.trim()) {
+            return Err(BodegaError::Configuracion(format!(
+                "El idioma \"{}\" no está disponible. Por ahora solo hay: {}",
+                idioma,
+                LANGUAGES.join(", ")
+            )));
+        }
+        let host = merged.host.unwrap_or(String::from(DEFAULT_HOST));
+        let usuario = merged.usuario.ok_or(BodegaError::Configuracion(format!(
+            "No se ha configurado el usuario de la base de datos. Indícalo con --db-usuario, {} o la clave usuario del perfil",
+            VAR_USER
+        )))?;
+        let tls = match merged.tls.unwrap_or(merged.tls_ca.is_some()) {
+            true => Some(Tls {
+                ca: merged.tls_ca.map(PathBuf::from),
+                verificar: merged.tls_verificar.unwrap_or(true),
+            }),
+            false => None,
+        };
+        let contrasena = resolve_password(
+            merged.contrasena,
+            merged.contrasena_fichero,
+            &usuario,
+            &host,
+        )?;
+        return Ok(Configuracion {
+            fichero,
+            perfil,
+            host,
+            puerto: merged.puerto.unwrap_or(DEFAULT_PORT),
+            usuario,
+            contrasena,
+            base_datos: merged.base_datos.unwrap_or(String::from(DEFAULT_DATABASE)),
+            tls,
+            lugar,
+            formato,
+            catalogo: merged.catalogo.map(PathBuf::from),
+        });
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn overlay_prefers_top_and_fills_from_bottom() {
+            let top = Perfil {
+                host: Some(String::from("arriba")),
+                ..Perfil::default()
+            };
+            let bottom = Perfil {
+                host: Some(String::from("abajo")),
+                puerto: Some(3307),
+                ..Perfil::default()
+            };
+            let merged = overlay(top, bottom);
+            assert_eq!(merged.host.as_deref(), Some("arriba"));
+            assert_eq!(merged.puerto, Some(3307));
+            assert_eq!(merged.usuario, None);
+        }
+
+        #[test]
+        fn overlay_keeps_password_and_password_file_together() {
+            let top = Perfil {
+                contrasena_fichero: Some(String::from("/run/secrets/db")),
+                ..Perfil::default()
+            };
+            let bottom = Perfil {
+                contrasena: Some(String::from("secreta")),
+                ..Perfil::default()
+            };
+            let merged = overlay(top, bottom);
+            assert_eq!(merged.contrasena, None);
+            assert_eq!(
+                merged.contrasena_fichero.as_deref(),
+                Some("/run/secrets/db")
+            );
+        }
+
+        #[test]
+        fn flags_then_environment_then_file() {
+            let flags = Perfil {
+                formato: Some(String::from("json")),
+                ..Perfil::default()
+            };
+            let environment = Perfil {
+                formato: Some(String::from("texto")),
+                lugar: Some(String::from("tara")),
+                ..Perfil::default()
+            };
+            let file: Fichero = toml::from_str(
+                "perfil = \"casa\"\n[perfiles.casa]\nlugar = \"casa\"\nusuario = \"bodega\"\n",
+            )
+            .unwrap();
+            let from_file = file.perfiles.get("casa").cloned().unwrap();
+            let merged = overlay(flags, overlay(environment, from_file));
+            assert_eq!(merged.formato.as_deref(), Some("json"));
+            assert_eq!(merged.lugar.as_deref(), Some("tara"));
+            assert_eq!(merged.usuario.as_deref(), Some("bodega"));
+        }
+
+        #[test]
+        fn unknown_keys_in_file_are_rejected() {
+            assert!(toml::from_str::<Fichero>("[perfiles.casa]\nservidor = \"x\"\n").is_err());
+        }
+
+        #[test]
+        fn split_host_with_port_and_database() {
+            assert_eq!(
+                split_host(String::from("db.local:3307/bodega")),
+                (
+                    String::from("db.local"),
+                    Some(3307),
+                    Some(String::from("bodega"))
+                )
+            );
+            assert_eq!(
+                split_host(String::from("db.local")),
+                (String::from("db.local"), None, None)
+            );
+        }
+
+        #[test]
+        fn parse_bool_and_format() {
+            assert_eq!(parse_bool(" Sí "), Some(true));
+            assert_eq!(parse_bool("0"), Some(false));
+            assert_eq!(parse_bool("quizá"), None);
+            assert_eq!(parse_format("JSON"), Some(Formato::Json));
+            assert_eq!(parse_format("xml"), None);
+        }
+    }
+}
@@ -1,63 +1,118 @@
-///Módulo que gestiona la conexión a la base de datos de mysql con el uso del archivo .env
+///Módulo que gestiona la conexión a la base de datos de mysql a partir de la configuración del programa: un conjunto de conexiones que dura todo el programa, del que cada operación toma una, reconectando si el servidor se ha reiniciado.
 pub mod connection_manager {
-    use std::env;
-    use std::env::VarError;
-    use dotenv::{from_path};
-    use mysql::{Pool, PooledConn};
-
-    ///Especifica el nombre de la variable de entorno del usuario de la base de datos
-    const VAR_USER:&str = "DB_USER";
-    ///Especifica el nombre de la variable de entorno de la contraseña de la base de datos
-    const VAR_PASSWORD:&str = "DB_PASSWORD";
-    ///Especifica el nombre de la variable de entorno del host de la base de datos
-    const VAR_HOST:&str = "DB_HOST";
-
-    pub fn get_envs() -> (Result<String, VarError>, Result<String, VarError>, Result<String, VarError>) {
-        //!Obtiene las variables de entorno especificadas en las constantes VAR_USER, VAR_PASSWORD y VAR_HOST del fichero .env en forma de resultados (pueden ser correctos o erróneos).
-        match from_path(".env") {
-            Ok(()) => {
-                return (env::var(VAR_USER), env::var(VAR_PASSWORD), env::var(VAR_HOST));
-            }
-            Err(e) => {
-                println!("No se pudieron leer las variables de entorno desde el directorio .env: {}", e);
-                return (env::var(VAR_USER), env::var(VAR_PASSWORD), env::var("VAR_HOST"));
-                }
-        }
+    use crate::config::config::{Configuracion, Tls};
+    use crate::error::error::BodegaError;
+    use mysql::{
+        DriverError, Opts, OptsBuilder, Pool, PoolConstraints, PoolOpts, PooledConn, SslOpts,
+    };
+    use std::thread::sleep;
+    use std::time::Duration;
+
+    ///Tiempo máximo para establecer la conexión TCP con el servidor
+    const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+    ///Tiempo máximo de espera de una lectura o una escritura en una conexión ya establecida
+    const IO_TIMEOUT: Duration = Duration::from_secs(30);
+    ///Tiempo máximo de espera a que quede libre una conexión del conjunto
+    const POOL_TIMEOUT: Duration = Duration::from_secs(10);
+    ///Número máximo de conexiones abiertas a la vez
+    const MAX_CONNECTIONS: usize = 4;
+    ///Intentos que se hacen para obtener una conexión cuando el error es pasajero
+    const ATTEMPTS: u32 = 4;
+    ///Espera antes del primer reintento, que se duplica en cada uno de los siguientes
+    const BACKOFF: Duration = Duration::from_millis(250);
+
+    ///Código de error de mysql cuando hay demasiadas conexiones abiertas
+    const ER_CON_COUNT: u16 = 1040;
+    ///Código de error de mysql cuando el servidor se está apagando
+    const ER_SERVER_SHUTDOWN: u16 = 1053;
+    ///Código de error de mysql cuando el servidor ha cerrado la conexión
+    const CR_SERVER_GONE: u16 = 2006;
+    ///Código de error de mysql cuando se pierde la conexión durante una consulta
+    const CR_SERVER_LOST: u16 = 2013;
+
+    ///Conjunto de conexiones con el servidor de mysql que se crea al arrancar y dura todo el programa.
+    pub struct Conexiones {
+        pool: Pool,
+        ///Servidor al que se conecta, para los mensajes de error
+        destino: String,
+    }
+
+    fn get_ssl_opts(tls: &Tls) -> SslOpts {
+        //!Devuelve las opciones de TLS de la conexión a partir de la configuración.
+        return SslOpts::default()
+            .with_root_cert_path(tls.ca.clone())
+            .with_danger_accept_invalid_certs(!tls.verificar)
+            .with_danger_skip_domain_validation(!tls.verificar);
     }
 
+    pub fn get_opts(config: &Configuracion) -> Opts {
+        //!Devuelve las opciones de conexión al servidor mysql de la configuración, con los tiempos de espera y el tamaño del conjunto de conexiones. Cada ajuste se da por separado, así que el usuario y la contraseña pueden contener cualquier carácter.
+        let builder = OptsBuilder::new()
+            .ip_or_hostname(Some(config.host.as_str()))
+            .tcp_port(config.puerto)
+            .user(Some(config.usuario.as_str()))
+            .pass(Some(config.contrasena.as_str()))
+            .db_name(Some(config.base_datos.as_str()))
+            .ssl_opts(config.tls.as_ref().map(get_ssl_opts))
+            .tcp_connect_timeout(Some(CONNECT_TIMEOUT))
+            .read_timeout(Some(IO_TIMEOUT))
+            .write_timeout(Some(IO_TIMEOUT))
+            .pool_opts(
+                PoolOpts::default()
+                    .with_constraints(PoolConstraints::new(0, MAX_CONNECTIONS).unwrap_or_default()),
+            );
+        return Opts::from(builder);
+    }
 
-    fn get_connection(user:String, password:String, host:String) -> Result<PooledConn, mysql::Error>{
-        //!Devuelve una conexión en forma de resultado a un servidor mysql a partir del usuario, contraseña y host.
-        let url = format!("mysql://{}:{}@{}", user, password, host);
-        return Pool::new(url.as_str())?.get_conn();
+    fn is_transient(e: &mysql::Error) -> bool {
+        //!Indica si un error de conexión puede desaparecer al reintentar, como cuando el servidor se está reiniciando.
+        match e {
+            mysql::Error::IoError(_) | mysql::Error::CodecError(_) => true,
+            mysql::Error::DriverError(DriverError::CouldNotConnect(_))
+            | mysql::Error::DriverError(DriverError::ConnectTimeout)
+            | mysql::Error::DriverError(DriverError::Timeout) => true,
+            mysql::Error::MySqlError(err) => matches!(
+                err.code,
+                ER_CON_COUNT | ER_SERVER_SHUTDOWN | CR_SERVER_GONE | CR_SERVER_LOST
+            ),
+            _ => false,
+        }
     }
 
-    pub fn connect(data:(Result<String, VarError>, Result<String, VarError>, Result<String, VarError>)) -> Result<PooledConn, mysql::Error>{
-        //!Comprueba que las variables obtenidas en get_envs() no son erróneas, y devuelve la conexión usando el método get_connection()
-        match data.0 {
-            Ok(user) => {
-                match data.1 {
-                    Ok(password) => {
-                        match data.2 {
-                            Ok(host) => {
-                                return get_connection(user, password, host);
-                            }
-                            Err(e) => {
-                                println!("Error al obtener la variable {}: {}",VAR_HOST, e);
-                                return get_connection(user, password, format!("NULL"));
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        println!("Error al obtener la variable {}: {}",VAR_PASSWORD, e);
-                        return get_connection(user, format!("NULL"), format!("NULL"));
-                    }
+    pub fn get_conn(conexiones: &Conexiones) -> Result<PooledConn, BodegaError> {
+        //!Toma una conexión del conjunto. El conjunto comprueba que la conexión sigue viva y abre otra si no. Si el error es pasajero, se reintenta esperando cada vez el doble; si no se consigue, se explica que el servidor no está disponible.
+        let mut wait = BACKOFF;
+        let mut attempt = 1;
+        loop {
+            match conexiones.pool.try_get_conn(POOL_TIMEOUT) {
+                Ok(conn) => {
+                    return Ok(conn);
+                }
+                Err(e) if is_transient(&e) && attempt < ATTEMPTS => {
+                    sleep(wait);
+                    wait *= 2;
+                    attempt += 1;
+                }
+                Err(e) if is_transient(&e) => {
+                    return Err(BodegaError::SinConexion(format!(
+                        "No se pudo conectar con el servidor de mysql en {} tras {} intentos: {}",
+                        conexiones.destino, ATTEMPTS, e
+                    )));
+                }
+                Err(e) => {
+                    return Err(BodegaError::from(e));
                 }
-            }
-            Err(e) => {
-                println!("Error al obtener la variable {}: {}",VAR_USER, e);
-                return get_connection(format!("NULL"), format!("NULL"), format!("NULL"));
             }
         }
     }
-}
\ No newline at end of file
+
+    pub fn connect(config: &Configuracion) -> Result<Conexiones, BodegaError> {
+        //!Crea el conjunto de conexiones a partir de la configuración, que ya tiene el usuario y la contraseña, y comprueba que se puede conectar, tomando una conexión con get_conn().
+        let conexiones = Conexiones {
+            pool: Pool::new(get_opts(config))?,
+            destino: format!("{}:{}", config.host, config.puerto),
+        };
+        get_conn(&conexiones)?;
+        return Ok(conexiones);
+    }
+}
@@ -0,0 +1,135 @@
+///Módulo que calcula el consumo de cada objeto en cada lugar a partir de las retiradas registradas en la tabla movimientos, y prevé cuándo se agotará.
+pub mod consumption {
+    use crate::db_manager::db_manager::{
+        get_movement_name, get_quantity, get_string_name, parse_location, read_objects, read_stock,
+        Objeto, Procedencia, TipoMovimiento, EPSILON,
+    };
+    use crate::error::error::BodegaError;
+    use crate::hogar::hogar::Hogar;
+    use mysql::prelude::Queryable;
+    use mysql::{params, PooledConn};
+    use serde::Serialize;
+
+    ///Número de días hacia atrás que se usan por defecto para calcular el consumo
+    pub const DEFAULT_WINDOW: u32 = 90;
+
+    ///Consumo de un objeto en un lugar y previsión de cuándo se agotará.
+    #[derive(Clone, Serialize)]
+    pub struct Consumo {
+        pub objeto: Objeto,
+        pub lugar: Procedencia,
+        ///Existencias actuales
+        pub existencias: f64,
+        ///Cantidad retirada de media cada día
+        pub diario: f64,
+        ///Días que durarán las existencias actuales al ritmo de consumo
+        pub dias_restantes: f64,
+    }
+
+    pub fn read_consumption(
+        conn: &mut PooledConn,
+        hogar: &Hogar,
+        window: u32,
+    ) -> Result<Vec<Consumo>, BodegaError> {
+        //!Calcula el consumo medio diario de cada objeto del hogar en cada lugar durante los últimos window días, contando solo las retiradas (los ajustes negativos y lo gastado al cocinar recetas; no los translados ni las correcciones de inventario). Si el primer movimiento del objeto en ese lugar es más reciente, se cuenta desde él, para no subestimar el consumo de los objetos nuevos. Solo se devuelven los objetos y lugares con algún consumo.
+        if window == 0 {
+            return Err(BodegaError::Invalido(String::from(
+                "El número de días debe ser mayor que 0",
+            )));
+        }
+        let objects = read_objects(conn, hogar)?;
+        let stock = read_stock(conn, &objects)?;
+        let rows: Vec<(i32, String, f64, i64)> = conn.exec(
+            "SELECT m.id_objeto, m.lugar,
+                    -SUM(CASE WHEN m.tipo IN (:ajuste, :receta) AND m.cantidad < 0
+                        AND m.fecha >= NOW() - INTERVAL :dias DAY THEN m.cantidad ELSE 0 END),
+                    GREATEST(1, LEAST(:dias, DATEDIFF(NOW(), MIN(m.fecha))))
+                FROM movimientos m
+                JOIN objetos o ON o.id = m.id_objeto
+                JOIN categorias c ON c.id = o.categoria
+                WHERE c.hogar = :hogar
+                GROUP BY m.id_objeto, m.lugar;",
+            params! {
+                "ajuste" => get_movement_name(&TipoMovimiento::Ajuste),
+                "receta" => get_movement_name(&TipoMovimiento::Receta),
+                "hogar" => hogar.id,
+                "dias" => window,
+            },
+        )?;
+        let mut result = Vec::new();
+        for (id, lugar, consumido, dias) in rows {
+            let (objeto, lugar) =
+                match (objects.iter().find(|o| o.id == id), parse_location(&lugar)) {
+                    (Some(objeto), Some(lugar)) => (objeto.clone(), lugar),
+                    _ => continue,
+                };
+            if consumido <= EPSILON {
+                continue;
+            }
+            let diario = consumido / dias as f64;
+            let existencias = get_quantity(&stock, id, &lugar);
+            result.push(Consumo {
+                dias_restantes: existencias.max(0.0) / diario,
+                objeto,
+                lugar,
+                existencias,
+                diario,
+            });
+        }
+        result.sort_by(|a, b| a.dias_restantes.total_cmp(&b.dias_restantes));
+        return Ok(result);
+    }
+
+    pub fn days_until(conn: &mut PooledConn, date: &str) -> Result<i64, BodegaError> {
+        //!Devuelve cuántos días faltan hasta la fecha dada, escrita como AAAA-MM-DD. La fecha no puede haber pasado.
+        let days: Option<Option<i64>> = conn.exec_first(
+            "SELECT DATEDIFF(STR_TO_DATE(:fecha, '%Y-%m-%d'), CURDATE());",
+            params! {"fecha" => date.trim()},
+        )?;
+        return match days.flatten() {
+            Some(days) if days >= 0 => Ok(days),
+            Some(_) => Err(BodegaError::Invalido(format!(
+                "La fecha {} ya ha pasado",
+                date.trim()
+            ))),
+            None => Err(BodegaError::Invalido(format!(
+                "\"{}\" no es una fecha válida. Escríbela como AAAA-MM-DD",
+                date.trim()
+            ))),
+        };
+    }
+
+    pub fn running_out(consumos: &[Consumo], days: i64) -> Vec<Consumo> {
+        //!Devuelve los consumos cuyas existencias se agotarán antes de que pasen los días dados.
+        return consumos
+            .iter()
+            .filter(|c| c.dias_restantes < days as f64)
+            .cloned()
+            .collect();
+    }
+
+    pub fn print_consumption(consumos: &[Consumo]) {
+        //!Muestra el consumo y la previsión de cada objeto y lugar dados.
+        if consumos.is_empty() {
+            println!("No hay retiradas registradas en ese periodo");
+            return;
+        }
+        for c in consumos {
+            let remaining = match c.dias_restantes {
+                _ if c.existencias <= EPSILON => String::from("ya no queda"),
+                d if d < 1.0 => String::from("se agotará hoy"),
+                d => format!("quedan para unos {:.0} días", d),
+            };
+            println!(
+                "{} en {}: {:.2} {} al día, hay x{} {}; {}",
+                c.objeto.nombre,
+                get_string_name(&c.lugar),
+                c.diario,
+                c.objeto.medida,
+                c.existencias,
+                c.objeto.medida,
+                remaining
+            );
+        }
+    }
+}
@@ -1,15 +1,45 @@
 ///Módulo que gestiona la base de datos a través de una conexión
 pub mod db_manager {
+    use crate::auth::auth::{check_permission, Permiso, Usuario};
+    use crate::error::error::BodegaError;
+    use crate::expiry::expiry::{add_lot, default_storage, fit_lots, transfer_lots, Conservacion};
+    use crate::hogar::hogar::{Hogar, Sesion};
+    use crate::places::places::fit_places;
+    use crate::selector::selector::normalize;
     use mysql::prelude::Queryable;
-    use mysql::{params, PooledConn};
+    use mysql::{params, PooledConn, TxOpts};
+    use serde::Serialize;
+
+    ///Margen con el que se comparan cantidades, ya que la columna cantidad guarda solo 2 decimales
+    pub const EPSILON: f64 = 0.005;
 
     ///Enum para indicar la base de datos de la que procede la existencia.
-    #[derive(PartialEq, Clone, Debug)]
+    #[derive(PartialEq, Clone, Debug, Serialize)]
     pub enum Procedencia {
         Casa,
         Tara,
     }
 
+    impl Procedencia {
+        pub fn all() -> [Procedencia; 2] {
+            //!Devuelve todos los lugares, en el orden en el que se muestran.
+            return [Procedencia::Casa, Procedencia::Tara];
+        }
+    }
+
+    ///Tipo de un movimiento de existencias registrado en la tabla movimientos.
+    #[derive(PartialEq, Clone, Debug)]
+    pub enum TipoMovimiento {
+        ///Se añadieron, retiraron o fijaron existencias
+        Ajuste,
+        ///Las existencias se transladaron de un lugar a otro
+        Translado,
+        ///Las existencias se corrigieron al hacer inventario
+        Inventario,
+        ///Las existencias se gastaron al cocinar una receta
+        Receta,
+    }
+
     pub fn contrary(loc: &Procedencia) -> Procedencia {
         if *loc == Procedencia::Casa {
             return Procedencia::Tara;
@@ -22,214 +52,472 @@ pub mod db_manager {
         if *loc == Procedencia::Casa {
             return String::from("Casa");
         } else {
-            return String::from("???");
+            return String::from("Tara");
+        }
+    }
+
+    pub fn get_table_name(loc: &Procedencia) -> &'static str {
+        //!Devuelve el nombre de la tabla de existencias que corresponde a la procedencia.
+        match loc {
+            Procedencia::Casa => "existencias_home",
+            Procedencia::Tara => "existencias_tara",
+        }
+    }
+
+    pub fn parse_location(name: &str) -> Option<Procedencia> {
+        //!Obtiene la procedencia a partir de su nombre, sin importar mayúsculas ni minúsculas.
+        match name.trim().to_lowercase().as_str() {
+            "casa" => Some(Procedencia::Casa),
+            "tara" => Some(Procedencia::Tara),
+            _ => None,
         }
     }
 
-    #[derive(Clone)]
+    #[derive(Clone, Serialize)]
     ///Estructura basada en la tabla categorias de la base de datos bodega-db
     pub struct Categoria {
-        id: i32,
-        nombre: String,
-        descripcion: String,
+        pub id: i32,
+        pub nombre: String,
+        pub descripcion: String,
+        ///Categoría de la que cuelga, o None si está en la raíz
+        pub padre: Option<i32>,
     }
 
-    #[derive(Clone)]
+    ///Fila de la tabla objetos tal y como se lee: id, categoría, nombre, medida, envase y precio por unidad de la última compra
+    type FilaObjeto = (i32, i32, String, String, Option<f64>, Option<f64>);
+
+    #[derive(Clone, Serialize)]
     ///Estructura basada en la tabla objetos de la base de datos bodega-db
     pub struct Objeto {
         pub id: i32,
-        categoria: Categoria,
+        pub categoria: Categoria,
         pub nombre: String,
         pub medida: String,
+        ///Cantidad que trae cada envase, en la unidad de medida, si se conoce
+        pub envase: Option<f64>,
+        ///Precio por unidad de medida de la última compra registrada, si hay alguna
+        pub precio: Option<f64>,
+        ///Etiquetas libres del objeto, como "sin gluten" o "congelado", por orden alfabético
+        pub etiquetas: Vec<String>,
     }
 
     ///Estructura basada en la tabla existencias-home o existencias-tara de la base de datos bodega-db
-    #[derive(Clone)]
+    #[derive(Clone, Serialize)]
     pub struct Existencia {
-        objeto: Objeto,
-        cantidad: f64,
-        procedencia: Procedencia,
+        pub objeto: Objeto,
+        pub cantidad: f64,
+        pub procedencia: Procedencia,
     }
 
-    pub fn read_objects(connection: &mut PooledConn) -> Vec<Objeto> {
-        //!Lee la tabla objetos, y la devuelve como un vector de estructuras de "Categoría". En lugar de guardar la categoría como un id, obtiene la categoría que corresponde a ese id, y la guarda dentro de la estructura "Objeto".
-        let categories = read_categories(connection);
+    pub fn read_objects(
+        connection: &mut PooledConn,
+        hogar: &Hogar,
+    ) -> Result<Vec<Objeto>, BodegaError> {
+        //!Lee los objetos del hogar dado, y los devuelve como un vector de estructuras de "Categoría". En lugar de guardar la categoría como un id, obtiene la categoría que corresponde a ese id, y la guarda dentro de la estructura "Objeto". Los objetos cuya categoría no existe no pertenecen a ningún hogar, así que no se devuelven; los encuentra la comprobación de integridad.
+        let categories = read_categories(connection, hogar)?;
         let mut result: Vec<Objeto> = Vec::new();
-        let list: Vec<(i32, i32, String, String)> = connection
-            .query_map(
-                "SELECT id, categoria, nombre, medida FROM objetos;",
-                |(id, id_cat, nombre, medida)| (id, id_cat, nombre, medida),
-            )
-            .unwrap();
+        let list: Vec<FilaObjeto> = connection.exec_map(
+            "SELECT o.id, o.categoria, o.nombre, o.medida, o.envase,
+                    (SELECT p.precio / p.cantidad FROM precios p WHERE p.id_objeto = o.id
+                        ORDER BY p.fecha DESC, p.id DESC LIMIT 1)
+                FROM objetos o
+                JOIN categorias c ON o.categoria = c.id WHERE c.hogar = :hogar;",
+            params! {"hogar" => hogar.id},
+            |(id, id_cat, nombre, medida, envase, precio)| {
+                (id, id_cat, nombre, medida, envase, precio)
+            },
+        )?;
+        let tags: Vec<(i32, String)> = connection.exec(
+            "SELECT oe.id_objeto, e.nombre FROM objetos_etiquetas oe
+                JOIN etiquetas e ON e.id = oe.id_etiqueta
+                WHERE e.hogar = :hogar ORDER BY e.nombre;",
+            params! {"hogar" => hogar.id},
+        )?;
         for o in list {
-            match get_category_by_id(o.1, categories.clone()) {
-                Some(cat) => result.push(Objeto {
+            if let Some(cat) = get_category_by_id(o.1, categories.clone()) {
+                result.push(Objeto {
                     categoria: cat,
                     id: o.0,
                     nombre: o.2,
                     medida: o.3,
-                }),
-                None => {
-                    result.push(Objeto {
-                        categoria: Categoria {
-                            id: 0,
-                            descripcion: String::from("Error al obtener la categoría. "),
-                            nombre: String::from("ERROR"),
-                        },
-                        id: o.0,
-                        nombre: o.2,
-                        medida: o.3,
-                    });
-                }
+                    envase: o.4,
+                    precio: o.5,
+                    etiquetas: tags
+                        .iter()
+                        .filter(|(id, _)| *id == o.0)
+                        .map(|(_, nombre)| nombre.clone())
+                        .collect(),
+                });
             }
         }
-        return result;
+        return Ok(result);
+    }
+
+    pub fn with_tag(objects: Vec<Objeto>, tag: &str) -> Vec<Objeto> {
+        //!Devuelve los objetos que tienen la etiqueta dada, sin distinguir mayúsculas ni tildes.
+        let tag = normalize(tag);
+        return objects
+            .into_iter()
+            .filter(|o| o.etiquetas.iter().any(|e| normalize(e) == tag))
+            .collect();
     }
 
     pub fn print_objects(objects: Vec<Objeto>) {
-        //!Imprime los objetos de la tabla objetos de la base de datos.
+        //!Imprime los objetos de la tabla objetos de la base de datos, con sus etiquetas si tienen alguna.
         for o in objects {
-            println!(
-                "{} (ID:{}, CT:{}, MD:{})",
-                o.nombre, o.id, o.categoria.nombre, o.medida
-            );
+            let etiquetas = match o.etiquetas.is_empty() {
+                true => String::new(),
+                false => format!(", ET:{}", o.etiquetas.join(", ")),
+            };
+            match o.envase {
+                Some(envase) => println!(
+                    "{} (ID:{}, CT:{}, MD:{}, ENV:{}{})",
+                    o.nombre, o.id, o.categoria.nombre, o.medida, envase, etiquetas
+                ),
+                None => println!(
+                    "{} (ID:{}, CT:{}, MD:{}{})",
+                    o.nombre, o.id, o.categoria.nombre, o.medida, etiquetas
+                ),
+            }
         }
     }
 
     pub fn insert_object(
         conn: &mut PooledConn,
+        sesion: &Sesion,
         cat: Categoria,
         name: String,
         measure: String,
-    ) -> Result<(), mysql::Error> {
-        //!Inserta un registro en la tabla objeto dada su categoría, nombre y unidad de medida
-        return conn.exec_drop(
-            "INSERT INTO objetos (categoria, nombre, medida) VALUES (:cat, :name, :measure)",
+    ) -> Result<i32, BodegaError> {
+        //!Inserta un registro en la tabla objeto dada su categoría, nombre y unidad de medida, si el usuario puede gestionar el catálogo y la categoría es del hogar activo. Devuelve el id del objeto creado
+        check_permission(&sesion.usuario, &Permiso::GestionarCatalogo)?;
+        conn.exec_drop(
+            "INSERT INTO objetos (categoria, nombre, medida)
+                SELECT id, :name, :measure FROM categorias WHERE id=:cat AND hogar=:hogar;",
             params! {
                 "cat" => cat.id,
                 "name" => name,
                 "measure" => measure,
+                "hogar" => sesion.hogar.id,
             },
-        );
+        )?;
+        if conn.affected_rows() == 0 {
+            return Err(BodegaError::NoEncontrado(format!(
+                "No existe ninguna categoría con id {} en {}",
+                cat.id, sesion.hogar.nombre
+            )));
+        }
+        return Ok(conn.last_insert_id() as i32);
     }
 
-    pub fn delete_object(conn: &mut PooledConn, id: i32) -> Result<(), mysql::Error> {
-        //!Borra un registro de la tabla objetos dado su id
-        return conn.exec_drop(
-            "DELETE FROM objetos WHERE id=:id;",
+    pub fn check_pack_size(envase: f64) -> Result<(), BodegaError> {
+        //!Comprueba que el tamaño de un envase es un número positivo.
+        if !envase.is_finite() || envase <= 0.0 {
+            return Err(BodegaError::Invalido(format!(
+                "El tamaño del envase debe ser positivo: {}",
+                envase
+            )));
+        }
+        return Ok(());
+    }
+
+    pub fn delete_object(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        id: i32,
+    ) -> Result<(), BodegaError> {
+        //!Borra un registro de la tabla objetos dado su id, si el usuario puede gestionar el catálogo y el objeto es del hogar activo
+        check_permission(&sesion.usuario, &Permiso::GestionarCatalogo)?;
+        conn.exec_drop(
+            "DELETE o FROM objetos o JOIN categorias c ON o.categoria = c.id
+                WHERE o.id=:id AND c.hogar=:hogar;",
             params! {
                 "id" => id,
+                "hogar" => sesion.hogar.id,
             },
-        );
+        )?;
+        if conn.affected_rows() == 0 {
+            return Err(BodegaError::NoEncontrado(format!(
+                "No existe ningún objeto con id {} en {}",
+                id, sesion.hogar.nombre
+            )));
+        }
+        return Ok(());
     }
 
     pub fn get_object_by_id(id: i32, objects: Vec<Objeto>) -> Option<Objeto> {
         //!Busca el objeto dentro de un vector de objetos que corresponde a un id dado.
-        for o in objects {
-            if o.id == id {
-                return Some(o);
+        return objects.into_iter().find(|o| o.id == id);
+    }
+
+    pub fn category_tree(categories: &[Categoria]) -> Vec<(usize, Categoria)> {
+        //!Ordena las categorías como un árbol, cada una seguida de sus subcategorías, y devuelve cada una con su profundidad. Las que cuelgan de una categoría que no está en la lista van a la raíz, y las que forman un ciclo se añaden al final en la raíz para que no se pierdan.
+        let mut sorted = categories.to_vec();
+        sorted.sort_by(|a, b| a.nombre.cmp(&b.nombre));
+        let is_root = |c: &Categoria| match c.padre {
+            Some(padre) => !sorted.iter().any(|p| p.id == padre),
+            None => true,
+        };
+        let mut pending: Vec<(usize, Categoria)> = sorted
+            .iter()
+            .filter(|c| is_root(c))
+            .rev()
+            .map(|c| (0, c.clone()))
+            .collect();
+        let mut result: Vec<(usize, Categoria)> = Vec::new();
+        loop {
+            while let Some((depth, c)) = pending.pop() {
+                if result.iter().any(|(_, r)| r.id == c.id) {
+                    continue;
+                }
+                for child in sorted.iter().rev().filter(|h| h.padre == Some(c.id)) {
+                    pending.push((depth + 1, child.clone()));
+                }
+                result.push((depth, c));
+            }
+            match sorted
+                .iter()
+                .find(|c| !result.iter().any(|(_, r)| r.id == c.id))
+            {
+                Some(c) => pending.push((0, c.clone())),
+                None => break,
             }
         }
-        return None;
+        return result;
+    }
+
+    pub fn subtree(categories: &[Categoria], id: i32) -> Vec<i32> {
+        //!Devuelve el id de la categoría dada y los de todas las que cuelgan de ella, directamente o no.
+        let mut result = vec![id];
+        let mut i = 0;
+        while i < result.len() {
+            for c in categories {
+                if c.padre == Some(result[i]) && !result.contains(&c.id) {
+                    result.push(c.id);
+                }
+            }
+            i += 1;
+        }
+        return result;
+    }
+
+    pub fn category_path(categories: &[Categoria], id: i32) -> String {
+        //!Devuelve el nombre de una categoría precedido de los de las categorías de las que cuelga, como "Bebidas / Vino".
+        let mut names: Vec<String> = Vec::new();
+        let mut seen: Vec<i32> = Vec::new();
+        let mut current = Some(id);
+        while let Some(id) = current {
+            match categories.iter().find(|c| c.id == id) {
+                Some(c) if !seen.contains(&c.id) => {
+                    names.push(c.nombre.clone());
+                    seen.push(c.id);
+                    current = c.padre;
+                }
+                _ => break,
+            }
+        }
+        names.reverse();
+        return names.join(" / ");
     }
 
     pub fn print_categories(categories: Vec<Categoria>) {
-        //!Imprime las categorías de la tabla categorias de la base de datos.
-        for c in categories {
-            println!("({}) {}: {}", c.id, c.nombre, c.descripcion);
+        //!Imprime las categorías de la tabla categorias de la base de datos, con las subcategorías sangradas bajo la categoría de la que cuelgan.
+        for (depth, c) in category_tree(&categories) {
+            println!(
+                "{}({}) {}: {}",
+                "    ".repeat(depth),
+                c.id,
+                c.nombre,
+                c.descripcion
+            );
         }
     }
 
+    pub fn set_parent(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        id: i32,
+        padre: Option<i32>,
+    ) -> Result<(), BodegaError> {
+        //!Cambia la categoría de la que cuelga una categoría del hogar activo, o la deja en la raíz con None, si el usuario puede gestionar el catálogo. No deja que una categoría cuelgue de sí misma ni de ninguna de sus subcategorías.
+        check_permission(&sesion.usuario, &Permiso::GestionarCatalogo)?;
+        let categories = read_categories(conn, &sesion.hogar)?;
+        if !categories.iter().any(|c| c.id == id) {
+            return Err(BodegaError::NoEncontrado(format!(
+                "No existe ninguna categoría con id {} en {}",
+                id, sesion.hogar.nombre
+            )));
+        }
+        if let Some(padre) = padre {
+            if !categories.iter().any(|c| c.id == padre) {
+                return Err(BodegaError::NoEncontrado(format!(
+                    "No existe ninguna categoría con id {} en {}",
+                    padre, sesion.hogar.nombre
+                )));
+            }
+            if subtree(&categories, id).contains(&padre) {
+                return Err(BodegaError::Invalido(format!(
+                    "{} no puede colgar de {}, que es ella misma o una de sus subcategorías",
+                    category_path(&categories, id),
+                    category_path(&categories, padre)
+                )));
+            }
+        }
+        conn.exec_drop(
+            "UPDATE categorias SET padre=:padre WHERE id=:id AND hogar=:hogar;",
+            params!("padre" => padre, "id" => id, "hogar" => sesion.hogar.id),
+        )?;
+        return Ok(());
+    }
+
     pub fn insert_category(
         conn: &mut PooledConn,
+        sesion: &Sesion,
         name: String,
         desc: String,
-    ) -> Result<(), mysql::Error> {
-        //!Inserta un registro de la tabla categorías dado nombre y descripción.
-        return conn.exec_drop(
-            "INSERT INTO categorias (nombre, descripcion) VALUES (:nombre, :descripcion);",
+        padre: Option<i32>,
+    ) -> Result<i32, BodegaError> {
+        //!Inserta un registro de la tabla categorías en el hogar activo dado nombre, descripción y, si cuelga de otra, la categoría padre, si el usuario puede gestionar el catálogo. Devuelve el id de la categoría creada.
+        check_permission(&sesion.usuario, &Permiso::GestionarCatalogo)?;
+        if let Some(padre) = padre {
+            if !read_categories(conn, &sesion.hogar)?
+                .iter()
+                .any(|c| c.id == padre)
+            {
+                return Err(BodegaError::NoEncontrado(format!(
+                    "No existe ninguna categoría con id {} en {}",
+                    padre, sesion.hogar.nombre
+                )));
+            }
+        }
+        conn.exec_drop(
+            "INSERT INTO categorias (nombre, descripcion, hogar, padre) VALUES (:nombre, :descripcion, :hogar, :padre);",
             params! {
                 "nombre" => name,
                 "descripcion" => desc,
+                "hogar" => sesion.hogar.id,
+                "padre" => padre,
             },
-        );
+        )?;
+        return Ok(conn.last_insert_id() as i32);
     }
 
-    pub fn delete_category(conn: &mut PooledConn, id: i32) -> Result<(), mysql::Error> {
-        //!Elimina un registro de la tabla categorías dado su id.
-        return conn.exec_drop("DELETE FROM categorias WHERE id=:id;", params!("id" => id));
+    pub fn delete_category(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        id: i32,
+    ) -> Result<(), BodegaError> {
+        //!Elimina un registro de la tabla categorías dado su id, si el usuario puede gestionar el catálogo y la categoría es del hogar activo.
+        check_permission(&sesion.usuario, &Permiso::GestionarCatalogo)?;
+        conn.exec_drop(
+            "DELETE FROM categorias WHERE id=:id AND hogar=:hogar;",
+            params!("id" => id, "hogar" => sesion.hogar.id),
+        )?;
+        if conn.affected_rows() == 0 {
+            return Err(BodegaError::NoEncontrado(format!(
+                "No existe ninguna categoría con id {} en {}",
+                id, sesion.hogar.nombre
+            )));
+        }
+        return Ok(());
     }
 
-    pub fn read_categories(connection: &mut PooledConn) -> Vec<Categoria> {
-        //!Lee la tabla categorias y la devuelve como un vector de estructuras "Categoria"
-        return connection
-            .query_map(
-                "SELECT id, nombre, descripcion FROM categorias;",
-                |(id, nombre, descripcion)| Categoria {
-                    id,
-                    nombre,
-                    descripcion,
-                },
-            )
-            .unwrap();
+    pub fn read_categories(
+        connection: &mut PooledConn,
+        hogar: &Hogar,
+    ) -> Result<Vec<Categoria>, BodegaError> {
+        //!Lee las categorías del hogar dado y las devuelve como un vector de estructuras "Categoria"
+        return Ok(connection.exec_map(
+            "SELECT id, nombre, descripcion, padre FROM categorias WHERE hogar=:hogar;",
+            params! {"hogar" => hogar.id},
+            |(id, nombre, descripcion, padre)| Categoria {
+                id,
+                nombre,
+                descripcion,
+                padre,
+            },
+        )?);
     }
 
     pub fn get_category_by_id(id: i32, categories: Vec<Categoria>) -> Option<Categoria> {
         //!Busca la categoría dentro de un vector de "Categoria" que corresponde a un id dado.
-        for c in categories {
-            if c.id == id {
-                return Some(c);
+        return categories.into_iter().find(|c| c.id == id);
+    }
+
+    pub fn get_stock_by_id(
+        conn: &mut PooledConn,
+        hogar: &Hogar,
+        obj_id: i32,
+    ) -> Result<(Option<Existencia>, Option<Existencia>), BodegaError> {
+        //!Obtiene un par de opciones que determinan si hay o no registros de un objeto del hogar dado, dado su id, en la tabla existencias_home y existencias_tara. Si el objeto no es del hogar, no hay registros. Una cantidad nula cuenta como 0.
+        let objeto = match get_object_by_id(obj_id, read_objects(conn, hogar)?) {
+            Some(objeto) => objeto,
+            None => {
+                return Ok((None, None));
             }
+        };
+        let mut found = Vec::new();
+        for location in Procedencia::all() {
+            let row: Option<Option<f64>> = conn.exec_first(
+                format!(
+                    "SELECT cantidad FROM {} WHERE id_objeto=:id",
+                    get_table_name(&location)
+                ),
+                params! {"id"=>obj_id},
+            )?;
+            found.push(row.map(|cantidad| Existencia {
+                objeto: objeto.clone(),
+                cantidad: cantidad.unwrap_or(0.0),
+                procedencia: location,
+            }));
         }
-        return None;
+        let tara = found.pop().flatten();
+        let home = found.pop().flatten();
+        return Ok((home, tara));
     }
 
-    pub fn get_item_index(list: Vec<Existencia>, id: i32, location: Procedencia) -> Option<usize> {
-        //!Obtiene la cantidad de un objeto y su posición en la lista, dado su id, de una de las tablas indicadas en location
-        for i in 0..list.len() {
-            if list.get(i).unwrap().objeto.id == id && list.get(i).unwrap().procedencia == location
-            {
-                return Some(i);
+    pub fn read_stock(
+        conn: &mut PooledConn,
+        objects: &[Objeto],
+    ) -> Result<Vec<Existencia>, BodegaError> {
+        //!Lee las dos tablas de existencias y las devuelve como un único vector de "Existencia". Los registros de objetos que no están en la lista dada se ignoran, y una cantidad nula cuenta como 0.
+        let mut result: Vec<Existencia> = Vec::new();
+        for location in Procedencia::all() {
+            let rows: Vec<(i32, Option<f64>)> = conn.query(format!(
+                "SELECT id_objeto, cantidad FROM {};",
+                get_table_name(&location)
+            ))?;
+            for (id_objeto, cantidad) in rows {
+                if let Some(objeto) = objects.iter().find(|o| o.id == id_objeto) {
+                    result.push(Existencia {
+                        objeto: objeto.clone(),
+                        cantidad: cantidad.unwrap_or(0.0),
+                        procedencia: location.clone(),
+                    });
+                }
             }
         }
-        return None;
+        return Ok(result);
     }
 
-    pub fn get_stock_by_id(
+    pub fn get_quantity(list: &[Existencia], id: i32, location: &Procedencia) -> f64 {
+        //!Obtiene la cantidad de un objeto, dado su id, en el lugar indicado por location. Si no hay registro, la cantidad es 0.
+        return list
+            .iter()
+            .find(|e| e.objeto.id == id && e.procedencia == *location)
+            .map(|e| e.cantidad)
+            .unwrap_or(0.0);
+    }
+
+    pub fn print_all_stock(
         conn: &mut PooledConn,
-        obj_id: i32,
-    ) -> (Option<Existencia>, Option<Existencia>) {
-        //!Obtiene un par de opciones que determinan si hay o no registros de un objeto dado su id en la tabla existencias_home y existencias_tara
-        let objs = read_objects(conn);
-        let vec_home: Vec<Existencia> = conn
-            .exec_map(
-                "SELECT * FROM existencias_home WHERE id_objeto=:id",
-                params! {"id"=>obj_id},
-                |(id_objeto, cantidad)| Existencia {
-                    objeto: get_object_by_id(id_objeto, objs.clone()).unwrap(),
-                    cantidad: cantidad,
-                    procedencia: Procedencia::Casa,
-                },
-            )
-            .unwrap();
-        let vec_tara: Vec<Existencia> = conn
-            .exec_map(
-                "SELECT * FROM existencias_tara WHERE id_objeto=:id",
-                params! {"id"=>obj_id},
-                |(id_objeto, cantidad)| Existencia {
-                    objeto: get_object_by_id(id_objeto, objs.clone()).unwrap(),
-                    cantidad: cantidad,
-                    procedencia: Procedencia::Tara,
-                },
-            )
-            .unwrap();
-        return (vec_home.get(0).cloned(), vec_tara.get(0).cloned());
-    }
-
-    pub fn print_all_stock(conn: &mut PooledConn, list: Vec<Objeto>, print_id: bool) {
-        //!Imprime aquellos objetos de los que hayan existencias en cualquiera de las dos tablas de existencias.
+        hogar: &Hogar,
+        list: Vec<Objeto>,
+        print_id: bool,
+    ) -> Result<(), BodegaError> {
+        //!Imprime aquellos objetos del hogar de los que hayan existencias en cualquiera de las dos tablas de existencias.
         for o in list {
-            match get_stock_by_id(conn, o.id) {
+            match get_stock_by_id(conn, hogar, o.id)? {
                 (Some(h), Some(t)) => {
                     if print_id {
                         print!("[ID:{}]", o.id);
@@ -262,61 +550,382 @@ pub mod db_manager {
                 }
             }
         }
+        return Ok(());
     }
 
-    pub fn update_stock(
-        conn: &mut PooledConn,
+    pub fn update_stock<Q: Queryable>(
+        conn: &mut Q,
         id: i32,
         set_mode: bool,
         quant: f32,
         location: &Procedencia,
     ) -> Result<(), mysql::Error> {
-        //!Actualiza un valor de existencias de un objeto con la id dada. Si set_mode es verdadero, se reemplazará el valor actual por quant, y si es false, se sumará el valor quant, positivo o negativo. location indica en qué base de datos realizar la operación
-        let mut query: String;
-        let mut mode: &str;
-        match set_mode {
-            true => {
-                mode = ":quant";
-            }
-            false => {
-                mode = "cantidad + :quant";
-            }
-        }
-        match location {
-            Procedencia::Casa => {
-                query = format!(
-                    "INSERT INTO {} (id_objeto, cantidad) VALUES (:id, :quant)
+        //!Actualiza un valor de existencias de un objeto con la id dada. Si set_mode es verdadero, se reemplazará el valor actual por quant, y si es false, se sumará el valor quant, positivo o negativo. location indica en qué base de datos realizar la operación. Si el nuevo total es menor que lo que había en las ubicaciones o en los lotes del lugar, los ajusta con fit_places() y fit_lots().
+        let mode = match set_mode {
+            true => ":quant",
+            false => "cantidad + :quant",
+        };
+        let query = format!(
+            "INSERT INTO {} (id_objeto, cantidad) VALUES (:id, :quant)
                 ON DUPLICATE KEY UPDATE cantidad = {};",
-                    "existencias_home", mode
-                );
-            }
-            Procedencia::Tara => {
-                query = format!(
-                    "INSERT INTO {} (id_objeto, cantidad) VALUES (:id, :quant)
-                ON DUPLICATE KEY UPDATE cantidad = {};",
-                    "existencias_tara", mode
-                );
-            }
+            get_table_name(location),
+            mode
+        );
+        conn.exec_drop(
+            &query,
+            params! {
+                "quant" => quant,
+                "id" => id
+            },
+        )?;
+        fit_places(conn, id, location)?;
+        return fit_lots(conn, id, location);
+    }
+
+    fn transfer_stock<Q: Queryable>(
+        conn: &mut Q,
+        id: i32,
+        quant: f32,
+        from: &Procedencia,
+    ) -> Result<(), mysql::Error> {
+        //!Translada la cantidad quant de existencias del objeto con la id dada desde el lugar from hasta el contrario.
+        update_stock(conn, id, false, -quant, from)?;
+        return update_stock(conn, id, false, quant, &contrary(from));
+    }
+
+    pub fn get_movement_name(tipo: &TipoMovimiento) -> &'static str {
+        //!Devuelve el nombre con el que se guarda el tipo de movimiento en la tabla movimientos.
+        match tipo {
+            TipoMovimiento::Ajuste => "ajuste",
+            TipoMovimiento::Translado => "translado",
+            TipoMovimiento::Inventario => "inventario",
+            TipoMovimiento::Receta => "receta",
         }
-        match set_mode {
-            true => {
-                return conn.exec_drop(
-                    &query,
-                    params! {
-                        "quant" => quant,
-                        "id" => id
-                    },
-                );
+    }
+
+    pub fn record_movement<Q: Queryable>(
+        conn: &mut Q,
+        id: i32,
+        quant: f64,
+        location: &Procedencia,
+        tipo: &TipoMovimiento,
+        usuario: &Usuario,
+    ) -> Result<(), mysql::Error> {
+        //!Registra en la tabla movimientos un cambio de quant en las existencias del objeto con la id dada, en location, hecho por el usuario dado.
+        return conn.exec_drop(
+            "INSERT INTO movimientos (id_objeto, lugar, cantidad, tipo, id_usuario)
+                VALUES (:id, :lugar, :cantidad, :tipo, :usuario);",
+            params! {
+                "id" => id,
+                "lugar" => get_string_name(location),
+                "cantidad" => quant,
+                "tipo" => get_movement_name(tipo),
+                "usuario" => usuario.id,
+            },
+        );
+    }
+
+    pub fn record_price<Q: Queryable>(
+        conn: &mut Q,
+        id: i32,
+        quant: f64,
+        price: f64,
+        location: &Procedencia,
+        usuario: &Usuario,
+    ) -> Result<(), mysql::Error> {
+        //!Registra en la tabla precios la compra de quant existencias del objeto con la id dada, por las que se pagó price en total, hecha por el usuario dado para location.
+        return conn.exec_drop(
+            "INSERT INTO precios (id_objeto, lugar, cantidad, precio, id_usuario)
+                VALUES (:id, :lugar, :cantidad, :precio, :usuario);",
+            params! {
+                "id" => id,
+                "lugar" => get_string_name(location),
+                "cantidad" => quant,
+                "precio" => price,
+                "usuario" => usuario.id,
+            },
+        );
+    }
+
+    pub fn parse_quantity(text: &str) -> Result<f32, BodegaError> {
+        //!Convierte el texto escrito por el usuario en una cantidad.
+        return text.trim().parse::<f32>().map_err(|e| {
+            BodegaError::Invalido(format!(
+                "Hubo un error con la cantidad ingresada. ¿Ha dado un número? {}",
+                e
+            ))
+        });
+    }
+
+    pub fn validate_stock_change(
+        conn: &mut PooledConn,
+        hogar: &Hogar,
+        id: i32,
+        quant: f32,
+    ) -> Result<Objeto, BodegaError> {
+        //!Comprueba que se puede pedir un cambio de existencias como los de update_stock(): que la cantidad es un número y que el objeto existe en el hogar. Devuelve el objeto afectado. Que las existencias no queden en negativo lo comprueba lock_stock() ya dentro de la transacción.
+        if !quant.is_finite() {
+            return Err(BodegaError::Invalido(String::from(
+                "La cantidad debe ser un número",
+            )));
+        }
+        let obj = match get_object_by_id(id, read_objects(conn, hogar)?) {
+            Some(obj) => obj,
+            None => {
+                return Err(BodegaError::NoEncontrado(format!(
+                    "No existe ningún objeto con id {}",
+                    id
+                )));
             }
-            false => {
-                return conn.exec_drop(
-                    &query,
-                    params! {
-                        "quant" => quant,
-                        "id" => id
-                    },
-                );
+        };
+        return Ok(obj);
+    }
+
+    pub fn lock_stock<Q: Queryable>(
+        tx: &mut Q,
+        obj: &Objeto,
+        set_mode: bool,
+        quant: f32,
+        location: &Procedencia,
+    ) -> Result<f64, BodegaError> {
+        //!Bloquea hasta el final de la transacción la fila de existencias del objeto en location, para que dos peticiones a la vez no puedan pasar las dos la comprobación, y comprueba que con el cambio no quedarían en negativo. Devuelve la cantidad actual.
+        let current: Option<Option<f64>> = tx.exec_first(
+            format!(
+                "SELECT cantidad FROM {} WHERE id_objeto = :id FOR UPDATE;",
+                get_table_name(location)
+            ),
+            params! {"id" => obj.id},
+        )?;
+        let current = current.flatten().unwrap_or(0.0);
+        let result = if set_mode {
+            quant as f64
+        } else {
+            current + quant as f64
+        };
+        if result < -EPSILON {
+            return Err(BodegaError::Invalido(format!(
+                "No hay suficientes existencias de {} en {}: hay x{} {}",
+                obj.nombre,
+                get_string_name(location),
+                current,
+                obj.medida
+            )));
+        }
+        return Ok(current);
+    }
+
+    pub fn adjust_stock(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        id: i32,
+        set_mode: bool,
+        quant: f32,
+        location: &Procedencia,
+        tipo: Option<&Conservacion>,
+    ) -> Result<(), BodegaError> {
+        //!Valida el cambio con validate_stock_change() y lock_stock() y, si es correcto, lo aplica con update_stock(), guarda lo añadido en un lote con su caducidad según el tipo de conservación dado (o, sin él, el que da default_storage()) y lo registra como un movimiento del usuario, en una única transacción. Retirar existencias requiere el permiso de consumir; añadirlas o fijarlas, el de gestionar existencias.
+        if set_mode || quant > 0.0 {
+            check_permission(&sesion.usuario, &Permiso::GestionarExistencias)?;
+        } else {
+            check_permission(&sesion.usuario, &Permiso::Consumir)?;
+        }
+        let obj = validate_stock_change(conn, &sesion.hogar, id, quant)?;
+        let tipo = match tipo {
+            Some(tipo) => tipo.clone(),
+            None => default_storage(conn, id)?,
+        };
+        let mut tx = conn.start_transaction(TxOpts::default())?;
+        let current = lock_stock(&mut tx, &obj, set_mode, quant, location)?;
+        let change = if set_mode {
+            quant as f64 - current
+        } else {
+            quant as f64
+        };
+        update_stock(&mut tx, id, set_mode, quant, location)?;
+        add_lot(&mut tx, id, change, location, &tipo)?;
+        record_movement(
+            &mut tx,
+            id,
+            change,
+            location,
+            &TipoMovimiento::Ajuste,
+            &sesion.usuario,
+        )?;
+        tx.commit()?;
+        return Ok(());
+    }
+
+    pub fn purchase_stock(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        id: i32,
+        quant: f32,
+        price: f64,
+        location: &Procedencia,
+        tipo: Option<&Conservacion>,
+    ) -> Result<(), BodegaError> {
+        //!Añade existencias compradas como adjust_stock(), y además registra lo que se pagó por ellas en total, todo en una única transacción. Requiere el permiso de gestionar existencias.
+        check_permission(&sesion.usuario, &Permiso::GestionarExistencias)?;
+        if quant <= 0.0 {
+            return Err(BodegaError::Invalido(String::from(
+                "Una compra debe añadir existencias: la cantidad tiene que ser positiva",
+            )));
+        }
+        if !price.is_finite() || price < 0.0 {
+            return Err(BodegaError::Invalido(format!(
+                "El precio debe ser un número no negativo: {}",
+                price
+            )));
+        }
+        let obj = validate_stock_change(conn, &sesion.hogar, id, quant)?;
+        let tipo = match tipo {
+            Some(tipo) => tipo.clone(),
+            None => default_storage(conn, id)?,
+        };
+        let mut tx = conn.start_transaction(TxOpts::default())?;
+        lock_stock(&mut tx, &obj, false, quant, location)?;
+        update_stock(&mut tx, id, false, quant, location)?;
+        add_lot(&mut tx, id, quant as f64, location, &tipo)?;
+        record_movement(
+            &mut tx,
+            id,
+            quant as f64,
+            location,
+            &TipoMovimiento::Ajuste,
+            &sesion.usuario,
+        )?;
+        record_price(&mut tx, id, quant as f64, price, location, &sesion.usuario)?;
+        tx.commit()?;
+        return Ok(());
+    }
+
+    pub fn parse_price(text: &str) -> Result<Option<f64>, BodegaError> {
+        //!Convierte el precio escrito por el usuario, que puede llevar coma decimal y el símbolo del euro. Si no se escribe nada, devuelve None.
+        let text = text.trim().trim_end_matches('€').trim().replace(',', ".");
+        if text.is_empty() {
+            return Ok(None);
+        }
+        return match text.parse::<f64>() {
+            Ok(price) if price.is_finite() && price >= 0.0 => Ok(Some(price)),
+            _ => Err(BodegaError::Invalido(format!(
+                "\"{}\" no es un precio válido",
+                text
+            ))),
+        };
+    }
+
+    pub fn move_stock(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        id: i32,
+        quant: f32,
+        from: &Procedencia,
+    ) -> Result<(), BodegaError> {
+        //!Valida que la cantidad a transladar es positiva y que hay suficientes existencias en el origen, y si es así, translada con transfer_stock() y registra la salida y la entrada como movimientos del usuario, en una única transacción. Requiere el permiso de gestionar existencias.
+        check_permission(&sesion.usuario, &Permiso::GestionarExistencias)?;
+        if quant.is_nan() || quant <= 0.0 {
+            return Err(BodegaError::Invalido(String::from(
+                "La cantidad a transladar debe ser mayor que 0",
+            )));
+        }
+        let obj = validate_stock_change(conn, &sesion.hogar, id, -quant)?;
+        let mut tx = conn.start_transaction(TxOpts::default())?;
+        lock_stock(&mut tx, &obj, false, -quant, from)?;
+        transfer_lots(&mut tx, id, quant as f64, from, &contrary(from))?;
+        transfer_stock(&mut tx, id, quant, from)?;
+        record_movement(
+            &mut tx,
+            id,
+            -quant as f64,
+            from,
+            &TipoMovimiento::Translado,
+            &sesion.usuario,
+        )?;
+        record_movement(
+            &mut tx,
+            id,
+            quant as f64,
+            &contrary(from),
+            &TipoMovimiento::Translado,
+            &sesion.usuario,
+        )?;
+        tx.commit()?;
+        return Ok(());
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn categoria(id: i32, nombre: &str, padre: Option<i32>) -> Categoria {
+            return Categoria {
+                id,
+                nombre: String::from(nombre),
+                descripcion: String::new(),
+                padre,
+            };
+        }
+
+        fn tree(categories: &[Categoria]) -> Vec<(usize, i32)> {
+            return category_tree(categories)
+                .into_iter()
+                .map(|(depth, c)| (depth, c.id))
+                .collect();
+        }
+
+        #[test]
+        fn tree_puts_children_under_parents_by_name() {
+            let categories = vec![
+                categoria(1, "Bebidas", None),
+                categoria(2, "Vino", Some(1)),
+                categoria(3, "Agua", Some(1)),
+                categoria(4, "Aceite", None),
+                categoria(5, "Tinto", Some(2)),
+            ];
+            assert_eq!(
+                tree(&categories),
+                vec![(0, 4), (0, 1), (1, 3), (1, 2), (2, 5)]
+            );
+        }
+
+        #[test]
+        fn tree_keeps_categories_with_missing_parent_at_root() {
+            let categories = vec![categoria(1, "Bebidas", Some(99))];
+            assert_eq!(tree(&categories), vec![(0, 1)]);
+        }
+
+        #[test]
+        fn tree_does_not_lose_or_repeat_a_cycle() {
+            let categories = vec![
+                categoria(1, "Conservas", None),
+                categoria(2, "A", Some(3)),
+                categoria(3, "B", Some(2)),
+            ];
+            let result = tree(&categories);
+            assert_eq!(result.len(), 3);
+            for id in [1, 2, 3] {
+                assert_eq!(result.iter().filter(|(_, c)| *c == id).count(), 1);
             }
         }
+
+        #[test]
+        fn subtree_and_path_stop_at_a_cycle() {
+            let categories = vec![
+                categoria(1, "A", Some(2)),
+                categoria(2, "B", Some(1)),
+                categoria(3, "C", Some(2)),
+            ];
+            let mut ids = subtree(&categories, 1);
+            ids.sort();
+            assert_eq!(ids, vec![1, 2, 3]);
+            assert_eq!(category_path(&categories, 3), "A / B / C");
+        }
+
+        #[test]
+        fn path_of_nested_category() {
+            let categories = vec![categoria(1, "Bebidas", None), categoria(2, "Vino", Some(1))];
+            assert_eq!(category_path(&categories, 2), "Bebidas / Vino");
+            assert_eq!(subtree(&categories, 2), vec![2]);
+        }
     }
 }
@@ -0,0 +1,437 @@
+///Módulo que comprueba que el programa está bien instalado: la configuración, la conexión con el servidor de mysql, las credenciales, la base de datos y su esquema, y que no hay registros huérfanos. Para cada comprobación que falla explica qué hacer.
+pub mod doctor {
+    use crate::config::config::{describe, load, Configuracion, Perfil};
+    use crate::connection_manager::connection_manager::get_opts;
+    use mysql::prelude::Queryable;
+    use mysql::{params, Conn, DriverError, OptsBuilder};
+    use std::path::PathBuf;
+
+    ///Versión del esquema que espera el programa. Cada script de sql-queries/upgrades que cambia el esquema la aumenta en uno.
+    pub const SCHEMA_VERSION: i32 = 14;
+
+    ///Código de error de mysql cuando el usuario o la contraseña no son correctos
+    const ER_ACCESS_DENIED: u16 = 1045;
+
+    ///Tablas que necesita el programa, con las columnas que se han ido añadiendo y el script que añade cada una.
+    const TABLES: [(&str, &[(&str, &str)]); 21] = [
+        ("hogares", &[]),
+        (
+            "categorias",
+            &[
+                ("hogar", "02-hogares.sql"),
+                ("padre", "10-subcategorias.sql"),
+            ],
+        ),
+        ("objetos", &[("envase", "07-envases.sql")]),
+        ("existencias_home", &[]),
+        ("existencias_tara", &[]),
+        ("usuarios", &[("rol", "01-roles-usuarios.sql")]),
+        ("miembros", &[("rol", "14-roles-por-hogar.sql")]),
+        ("movimientos", &[]),
+        ("raciones", &[]),
+        ("recetas", &[]),
+        ("ingredientes", &[]),
+        ("codigos", &[]),
+        ("precios", &[]),
+        ("tiendas", &[]),
+        ("proveedores", &[]),
+        ("etiquetas", &[]),
+        ("objetos_etiquetas", &[]),
+        ("ubicaciones", &[("tipo", "13-caducidades.sql")]),
+        ("existencias_ubicaciones", &[]),
+        ("conservacion", &[]),
+        ("lotes", &[]),
+    ];
+
+    ///Claves ajenas que necesita el programa: tabla, columna y tabla a la que apunta.
+    const FOREIGN_KEYS: [(&str, &str, &str); 28] = [
+        ("categorias", "hogar", "hogares"),
+        ("categorias", "padre", "categorias"),
+        ("objetos", "categoria", "categorias"),
+        ("existencias_home", "id_objeto", "objetos"),
+        ("existencias_tara", "id_objeto", "objetos"),
+        ("miembros", "id_usuario", "usuarios"),
+        ("miembros", "id_hogar", "hogares"),
+        ("movimientos", "id_objeto", "objetos"),
+        ("movimientos", "id_usuario", "usuarios"),
+        ("raciones", "id_objeto", "objetos"),
+        ("recetas", "hogar", "hogares"),
+        ("ingredientes", "id_receta", "recetas"),
+        ("ingredientes", "id_objeto", "objetos"),
+        ("codigos", "id_objeto", "objetos"),
+        ("precios", "id_objeto", "objetos"),
+        ("precios", "id_usuario", "usuarios"),
+        ("tiendas", "hogar", "hogares"),
+        ("proveedores", "id_objeto", "objetos"),
+        ("proveedores", "id_tienda", "tiendas"),
+        ("etiquetas", "hogar", "hogares"),
+        ("objetos_etiquetas", "id_objeto", "objetos"),
+        ("objetos_etiquetas", "id_etiqueta", "etiquetas"),
+        ("ubicaciones", "padre", "ubicaciones"),
+        ("ubicaciones", "hogar", "hogares"),
+        ("existencias_ubicaciones", "id_objeto", "objetos"),
+        ("existencias_ubicaciones", "id_ubicacion", "ubicaciones"),
+        ("conservacion", "id_objeto", "objetos"),
+        ("lotes", "id_objeto", "objetos"),
+    ];
+
+    ///Resultado de una comprobación.
+    enum Estado {
+        ///La comprobación ha ido bien
+        Bien(String),
+        ///La comprobación ha fallado: qué pasa y qué hacer
+        Fallo(String, String),
+        ///No se ha hecho porque ha fallado otra de la que depende
+        Omitida,
+    }
+
+    fn report(name: &str, estado: Estado) -> bool {
+        //!Muestra el resultado de una comprobación y devuelve si ha ido bien.
+        match estado {
+            Estado::Bien(detail) => {
+                println!("[ OK  ] {}: {}", name, detail);
+                return true;
+            }
+            Estado::Fallo(detail, advice) => {
+                println!("[FALLO] {}: {}\n        → {}", name, detail, advice);
+                return false;
+            }
+            Estado::Omitida => {
+                println!("[ --  ] {}: no comprobado", name);
+                return false;
+            }
+        }
+    }
+
+    fn check_config(
+        path: Option<PathBuf>,
+        profile: Option<String>,
+        flags: Perfil,
+    ) -> (Estado, Option<Configuracion>) {
+        //!Comprueba que la configuración se puede leer y está completa, y muestra de dónde sale.
+        return match load(path, profile, flags) {
+            Ok(config) => {
+                let env_file = match PathBuf::from(".env").exists() {
+                    true => ", con .env en el directorio actual",
+                    false => "",
+                };
+                let source = match &config.fichero {
+                    Some(_) => String::new(),
+                    None => String::from(" (sin fichero de configuración)"),
+                };
+                (
+                    Estado::Bien(format!("{}{}{}", describe(&config), source, env_file)),
+                    Some(config),
+                )
+            }
+            Err(e) => (
+                Estado::Fallo(
+                    e.to_string(),
+                    String::from("Corrige el fichero de configuración, las variables de entorno o los argumentos; el README explica cada ajuste"),
+                ),
+                None,
+            ),
+        };
+    }
+
+    fn check_server(config: &Configuracion) -> (Estado, Estado, Option<Conn>) {
+        //!Intenta conectarse al servidor sin elegir base de datos, para distinguir si no se llega al servidor o si no acepta las credenciales.
+        let opts = OptsBuilder::from_opts(get_opts(config)).db_name(None::<String>);
+        let server = format!("{}:{}", config.host, config.puerto);
+        return match Conn::new(opts) {
+            Ok(conn) => (
+                Estado::Bien(format!("{} responde", server)),
+                Estado::Bien(format!("{} ha iniciado sesión", config.usuario)),
+                Some(conn),
+            ),
+            Err(mysql::Error::MySqlError(e)) if e.code == ER_ACCESS_DENIED => (
+                Estado::Bien(format!("{} responde", server)),
+                Estado::Fallo(
+                    e.message,
+                    format!("Comprueba la contraseña de {} o créalo en mysql con CREATE USER y GRANT", config.usuario),
+                ),
+                None,
+            ),
+            Err(mysql::Error::DriverError(DriverError::TlsNotSupported)) => (
+                Estado::Fallo(
+                    format!("{} no admite TLS", server),
+                    String::from("Activa TLS en el servidor de mysql o quita tls del perfil"),
+                ),
+                Estado::Omitida,
+                None,
+            ),
+            Err(e @ mysql::Error::TlsError(_)) => (
+                Estado::Fallo(
+                    e.to_string(),
+                    String::from("Indica con tls_ca el certificado de la autoridad que firma el del servidor, o usa tls_verificar = false si es autofirmado"),
+                ),
+                Estado::Omitida,
+                None,
+            ),
+            Err(e) => (
+                Estado::Fallo(
+                    e.to_string(),
+                    format!("Comprueba que mysql está arrancado en {}, que el host y el puerto son correctos y que ningún cortafuegos bloquea la conexión", server),
+                ),
+                Estado::Omitida,
+                None,
+            ),
+        };
+    }
+
+    fn check_database(conn: &mut Conn, database: &str) -> Result<Estado, mysql::Error> {
+        //!Comprueba que existe la base de datos configurada y que el usuario puede usarla.
+        let found: Option<String> = conn.exec_first(
+            "SELECT SCHEMA_NAME FROM information_schema.SCHEMATA WHERE SCHEMA_NAME = :db;",
+            params! {"db" => database},
+        )?;
+        if found.is_none() {
+            return Ok(Estado::Fallo(
+                format!("no existe la base de datos {}, o el usuario no tiene permisos sobre ella", database),
+                format!("Ejecuta sql-queries/init-db.sql (que la crea con el nombre bodega) y da permisos al usuario con GRANT ALL ON {}.* TO ...", database),
+            ));
+        }
+        conn.query_drop(format!("USE `{}`;", database.replace('`', "``")))?;
+        return Ok(Estado::Bien(format!("{} existe", database)));
+    }
+
+    fn check_tables(conn: &mut Conn, database: &str) -> Result<Estado, mysql::Error> {
+        //!Comprueba que existen todas las tablas y las columnas que se han ido añadiendo con los scripts de actualización.
+        let columns: Vec<(String, String)> = conn.exec(
+            "SELECT TABLE_NAME, COLUMN_NAME FROM information_schema.COLUMNS WHERE TABLE_SCHEMA = :db;",
+            params! {"db" => database},
+        )?;
+        let mut missing = Vec::new();
+        let mut scripts = Vec::new();
+        for (table, added) in TABLES {
+            if !columns.iter().any(|(t, _)| t == table) {
+                missing.push(String::from(table));
+                continue;
+            }
+            for (column, script) in added {
+                if !columns.iter().any(|(t, c)| t == table && c == column) {
+                    missing.push(format!("{}.{}", table, column));
+                    scripts.push(*script);
+                }
+            }
+        }
+        if missing.is_empty() {
+            return Ok(Estado::Bien(format!(
+                "las {} tablas están completas",
+                TABLES.len()
+            )));
+        }
+        let advice = match scripts.is_empty() {
+            true => String::from("Vuelve a ejecutar sql-queries/init-db.sql, que solo crea las tablas que falten"),
+            false => format!(
+                "Vuelve a ejecutar sql-queries/init-db.sql y después, en orden, sql-queries/upgrades/{}",
+                scripts.join(", sql-queries/upgrades/")
+            ),
+        };
+        return Ok(Estado::Fallo(
+            format!("faltan {}", missing.join(", ")),
+            advice,
+        ));
+    }
+
+    fn check_version(conn: &mut Conn) -> Result<Estado, mysql::Error> {
+        //!Comprueba que la versión del esquema guardada en la base de datos es la que espera el programa. Con la tabla esquema vacía, MAX devuelve una fila con NULL, que se lee como None.
+        let exists: Option<String> = conn.query_first(
+            "SELECT TABLE_NAME FROM information_schema.TABLES WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = 'esquema';",
+        )?;
+        if exists.is_none() {
+            return Ok(Estado::Fallo(
+                String::from("la base de datos no guarda la versión del esquema"),
+                String::from("Ejecuta los scripts de sql-queries/upgrades que falten, al menos 03-version-esquema.sql"),
+            ));
+        }
+        let version: Option<i32> = conn
+            .query_first::<Option<i32>, _>("SELECT MAX(version) FROM esquema;")?
+            .flatten();
+        return Ok(match version {
+            Some(v) if v == SCHEMA_VERSION => Estado::Bien(format!("versión {}", v)),
+            Some(v) if v > SCHEMA_VERSION => Estado::Fallo(
+                format!(
+                    "la base de datos tiene la versión {} y el programa espera la {}",
+                    v, SCHEMA_VERSION
+                ),
+                String::from(
+                    "Actualiza bodega-manager a la versión que corresponde a la base de datos",
+                ),
+            ),
+            Some(v) => Estado::Fallo(
+                format!(
+                    "la base de datos tiene la versión {} y el programa espera la {}",
+                    v, SCHEMA_VERSION
+                ),
+                format!(
+                    "Ejecuta en orden los scripts de sql-queries/upgrades posteriores al {:02}",
+                    v
+                ),
+            ),
+            None => Estado::Fallo(
+                String::from("la tabla esquema está vacía"),
+                String::from("Vuelve a ejecutar sql-queries/upgrades/03-version-esquema.sql"),
+            ),
+        });
+    }
+
+    fn check_foreign_keys(conn: &mut Conn, database: &str) -> Result<Estado, mysql::Error> {
+        //!Comprueba que existen las claves ajenas que mantienen la base de datos coherente.
+        let keys: Vec<(String, String, String)> = conn.exec(
+            "SELECT TABLE_NAME, COLUMN_NAME, REFERENCED_TABLE_NAME FROM information_schema.KEY_COLUMN_USAGE
+                    WHERE TABLE_SCHEMA = :db AND REFERENCED_TABLE_NAME IS NOT NULL;",
+            params! {"db" => database},
+        )?;
+        let missing: Vec<&(&str, &str, &str)> = FOREIGN_KEYS
+            .iter()
+            .filter(|(table, column, reference)| {
+                !keys
+                    .iter()
+                    .any(|(t, c, r)| t == table && c == column && r == reference)
+            })
+            .collect();
+        if missing.is_empty() {
+            return Ok(Estado::Bien(String::from("todas presentes")));
+        }
+        return Ok(Estado::Fallo(
+            format!(
+                "faltan {}",
+                missing
+                    .iter()
+                    .map(|(t, c, r)| format!("{}.{} → {}", t, c, r))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            format!(
+                "Después de quitar las filas huérfanas, añádelas con {}",
+                missing
+                    .iter()
+                    .map(|(t, c, r)| format!(
+                        "ALTER TABLE {} ADD FOREIGN KEY ({}) REFERENCES {}(id);",
+                        t, c, r
+                    ))
+                    .collect::<Vec<String>>()
+                    .join(" ")
+            ),
+        ));
+    }
+
+    fn check_orphans(conn: &mut Conn) -> Result<Estado, mysql::Error> {
+        //!Busca registros que apuntan a otros que ya no existen, que pueden quedar si faltan las claves ajenas: existencias de objetos borrados, objetos de categorías borradas y categorías de hogares borrados. No son de ningún hogar, así que solo se buscan aquí, con las credenciales del servidor, y no en la comprobación de integridad de cada hogar.
+        let checks = [
+            (
+                "existencias_home",
+                "SELECT COUNT(*) FROM existencias_home e LEFT JOIN objetos o ON o.id = e.id_objeto WHERE o.id IS NULL;",
+                "DELETE e FROM existencias_home e LEFT JOIN objetos o ON o.id = e.id_objeto WHERE o.id IS NULL;",
+            ),
+            (
+                "existencias_tara",
+                "SELECT COUNT(*) FROM existencias_tara e LEFT JOIN objetos o ON o.id = e.id_objeto WHERE o.id IS NULL;",
+                "DELETE e FROM existencias_tara e LEFT JOIN objetos o ON o.id = e.id_objeto WHERE o.id IS NULL;",
+            ),
+            (
+                "objetos",
+                "SELECT COUNT(*) FROM objetos o LEFT JOIN categorias c ON c.id = o.categoria WHERE c.id IS NULL;",
+                "UPDATE objetos o LEFT JOIN categorias c ON c.id = o.categoria SET o.categoria = <id de una categoría> WHERE c.id IS NULL;",
+            ),
+            (
+                "categorias",
+                "SELECT COUNT(*) FROM categorias c LEFT JOIN hogares h ON h.id = c.hogar WHERE h.id IS NULL;",
+                "UPDATE categorias c LEFT JOIN hogares h ON h.id = c.hogar SET c.hogar = <id de un hogar> WHERE h.id IS NULL;",
+            ),
+        ];
+        let mut orphans = Vec::new();
+        for (table, query, fix) in checks {
+            let count: Option<i64> = conn.query_first(query)?;
+            if let Some(count) = count.filter(|c| *c > 0) {
+                orphans.push((table, count, fix));
+            }
+        }
+        if orphans.is_empty() {
+            return Ok(Estado::Bien(String::from("ninguno")));
+        }
+        return Ok(Estado::Fallo(
+            orphans
+                .iter()
+                .map(|(t, c, _)| format!("{} filas en {}", c, t))
+                .collect::<Vec<String>>()
+                .join(", "),
+            orphans
+                .iter()
+                .map(|(_, _, fix)| *fix)
+                .collect::<Vec<&str>>()
+                .join(" "),
+        ));
+    }
+
+    fn report_query(name: &str, result: Result<Estado, mysql::Error>) -> bool {
+        //!Muestra el resultado de una comprobación que consulta la base de datos; si la consulta falla, lo muestra como un fallo.
+        return match result {
+            Ok(estado) => report(name, estado),
+            Err(e) => report(
+                name,
+                Estado::Fallo(
+                    e.to_string(),
+                    String::from("Comprueba que el usuario tiene permisos para leer la base de datos y information_schema"),
+                ),
+            ),
+        };
+    }
+
+    pub fn diagnose(path: Option<PathBuf>, profile: Option<String>, flags: Perfil) -> bool {
+        //!Hace todas las comprobaciones en orden, mostrando el resultado de cada una, y devuelve si han ido todas bien. Las que dependen de una que ha fallado no se hacen.
+        let (estado, config) = check_config(path, profile, flags);
+        let mut ok = report("Configuración", estado);
+        let (server, credentials, conn) = match &config {
+            Some(config) => check_server(config),
+            None => (Estado::Omitida, Estado::Omitida, None),
+        };
+        ok &= report("Conexión con el servidor", server);
+        ok &= report("Credenciales", credentials);
+        let names = [
+            "Base de datos",
+            "Tablas",
+            "Versión del esquema",
+            "Registros huérfanos",
+            "Claves ajenas",
+        ];
+        match (config, conn) {
+            (Some(config), Some(mut conn)) => {
+                let database = config.base_datos.as_str();
+                if report_query(names[0], check_database(&mut conn, database)) {
+                    let tables = report_query(names[1], check_tables(&mut conn, database));
+                    ok &= tables;
+                    ok &= report_query(names[2], check_version(&mut conn));
+                    match tables {
+                        true => {
+                            ok &= report_query(names[3], check_orphans(&mut conn));
+                        }
+                        false => {
+                            ok &= report(names[3], Estado::Omitida);
+                        }
+                    }
+                    ok &= report_query(names[4], check_foreign_keys(&mut conn, database));
+                } else {
+                    ok = false;
+                    for name in &names[1..] {
+                        report(name, Estado::Omitida);
+                    }
+                }
+            }
+            _ => {
+                ok = false;
+                for name in names {
+                    report(name, Estado::Omitida);
+                }
+            }
+        }
+        match ok {
+            true => println!("\nTodo está bien."),
+            false => println!(
+                "\nHay problemas: sigue las indicaciones de las comprobaciones que han fallado."
+            ),
+        }
+        return ok;
+    }
+}
@@ -0,0 +1,66 @@
+///Módulo con el error común de las operaciones sobre la bodega, que distingue los datos inválidos y los registros inexistentes de los fallos de la base de datos.
+pub mod error {
+    use std::fmt::{Display, Formatter};
+
+    ///Código de error de mysql cuando se borra un registro al que otro hace referencia
+    const ER_ROW_IS_REFERENCED: u16 = 1451;
+    ///Código de error de mysql cuando se inserta un registro que hace referencia a otro que no existe
+    const ER_NO_REFERENCED_ROW: u16 = 1452;
+
+    ///Error de una operación sobre la bodega.
+    #[derive(Debug)]
+    pub enum BodegaError {
+        ///Los datos dados no son válidos para la operación
+        Invalido(String),
+        ///El registro al que se refiere la operación no existe
+        NoEncontrado(String),
+        ///La operación rompería una referencia entre tablas
+        Conflicto(String),
+        ///No se ha iniciado sesión o las credenciales no son correctas
+        NoAutenticado(String),
+        ///El rol del usuario no permite la operación
+        SinPermiso(String),
+        ///La configuración del programa falta o no es válida
+        Configuracion(String),
+        ///No se puede conectar con el servidor de la base de datos
+        SinConexion(String),
+        ///La petición es más grande de lo que se admite
+        DemasiadoGrande(String),
+        ///No se pudo usar la terminal
+        Terminal(String),
+        ///Error de la base de datos
+        BaseDeDatos(mysql::Error),
+    }
+
+    impl Display for BodegaError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            match self {
+                BodegaError::Invalido(m) => write!(f, "{}", m),
+                BodegaError::NoEncontrado(m) => write!(f, "{}", m),
+                BodegaError::Conflicto(m) => write!(f, "{}", m),
+                BodegaError::NoAutenticado(m) => write!(f, "{}", m),
+                BodegaError::SinPermiso(m) => write!(f, "{}", m),
+                BodegaError::Configuracion(m) => write!(f, "{}", m),
+                BodegaError::SinConexion(m) => write!(f, "{}", m),
+                BodegaError::DemasiadoGrande(m) => write!(f, "{}", m),
+                BodegaError::Terminal(m) => write!(f, "{}", m),
+                BodegaError::BaseDeDatos(e) => write!(f, "{}", e),
+            }
+        }
+    }
+
+    impl From<mysql::Error> for BodegaError {
+        fn from(e: mysql::Error) -> Self {
+            //!Convierte un error de mysql, separando los errores de claves ajenas como conflictos.
+            if let mysql::Error::MySqlError(ref err) = e {
+                if err.code == ER_ROW_IS_REFERENCED || err.code == ER_NO_REFERENCED_ROW {
+                    return BodegaError::Conflicto(format!(
+                        "La operación afecta a registros relacionados: {}",
+                        err.message
+                    ));
+                }
+            }
+            return BodegaError::BaseDeDatos(e);
+        }
+    }
+}
@@ -0,0 +1,395 @@
+///Módulo que calcula cuándo caducan las existencias. Cada objeto puede tener los días que dura en la despensa, en la nevera y en el congelador, y al añadir existencias se guarda un lote con la fecha de caducidad según dónde se guardan. Al retirar existencias se gastan primero los lotes que caducan antes, y al moverlas a un sitio con otra conservación la fecha se vuelve a calcular.
+pub mod expiry {
+    use crate::auth::auth::{check_permission, Permiso};
+    use crate::db_manager::db_manager::{
+        get_string_name, get_table_name, parse_location, read_objects, Objeto, Procedencia, EPSILON,
+    };
+    use crate::error::error::BodegaError;
+    use crate::hogar::hogar::{Hogar, Sesion};
+    use crate::selector::selector::normalize;
+    use mysql::prelude::Queryable;
+    use mysql::{params, PooledConn};
+    use serde::Serialize;
+
+    ///Tipo de conservación de un sitio, del que depende cuánto duran las existencias que se guardan en él. Lo que no está en ninguna ubicación está en la despensa.
+    #[derive(PartialEq, Clone, Debug, Serialize)]
+    pub enum Conservacion {
+        Despensa,
+        Nevera,
+        Congelador,
+    }
+
+    ///Días que dura un objeto en un tipo de conservación. Se guarda en la tabla conservacion.
+    #[derive(Clone, Serialize)]
+    pub struct VidaUtil {
+        pub tipo: Conservacion,
+        pub dias: i32,
+    }
+
+    ///Existencias de un objeto que caducan el mismo día. Se guarda en la tabla lotes.
+    #[derive(Clone, Serialize)]
+    pub struct Lote {
+        pub objeto: Objeto,
+        pub lugar: Procedencia,
+        pub tipo: Conservacion,
+        pub cantidad: f64,
+        ///Fecha de caducidad, como AAAA-MM-DD
+        pub caduca: String,
+        ///Días que faltan para que caduque; negativo si ya ha caducado
+        pub dias: i64,
+    }
+
+    pub fn get_storage_name(tipo: &Conservacion) -> &'static str {
+        //!Devuelve el nombre con el que se guarda el tipo de conservación en la base de datos.
+        match tipo {
+            Conservacion::Despensa => "despensa",
+            Conservacion::Nevera => "nevera",
+            Conservacion::Congelador => "congelador",
+        }
+    }
+
+    pub fn parse_storage(name: &str) -> Option<Conservacion> {
+        //!Obtiene el tipo de conservación a partir de su nombre, sin importar mayúsculas ni tildes. Acepta también frigorífico y nevera como lo mismo.
+        match normalize(name).as_str() {
+            "despensa" => Some(Conservacion::Despensa),
+            "nevera" | "frigorifico" => Some(Conservacion::Nevera),
+            "congelador" => Some(Conservacion::Congelador),
+            _ => None,
+        }
+    }
+
+    pub fn read_shelf_life(
+        conn: &mut PooledConn,
+        objeto: &Objeto,
+    ) -> Result<Vec<VidaUtil>, BodegaError> {
+        //!Lee los días que dura un objeto en cada tipo de conservación que tiene indicado.
+        let rows: Vec<(String, i32)> = conn.exec(
+            "SELECT tipo, dias FROM conservacion WHERE id_objeto = :id;",
+            params! {"id" => objeto.id},
+        )?;
+        let mut result: Vec<VidaUtil> = rows
+            .into_iter()
+            .filter_map(|(tipo, dias)| parse_storage(&tipo).map(|tipo| VidaUtil { tipo, dias }))
+            .collect();
+        result.sort_by_key(|v| v.dias);
+        return Ok(result);
+    }
+
+    pub fn set_shelf_life(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        objeto: &Objeto,
+        tipo: &Conservacion,
+        dias: u32,
+    ) -> Result<(), BodegaError> {
+        //!Fija los días que dura un objeto del hogar activo en un tipo de conservación, o los quita con 0, si el usuario puede gestionar el catálogo. Solo cuenta para las existencias que se añadan después.
+        check_permission(&sesion.usuario, &Permiso::GestionarCatalogo)?;
+        if !read_objects(conn, &sesion.hogar)?
+            .iter()
+            .any(|o| o.id == objeto.id)
+        {
+            return Err(BodegaError::NoEncontrado(format!(
+                "No existe ningún objeto con id {} en {}",
+                objeto.id, sesion.hogar.nombre
+            )));
+        }
+        match dias {
+            0 => conn.exec_drop(
+                "DELETE FROM conservacion WHERE id_objeto = :id AND tipo = :tipo;",
+                params! {"id" => objeto.id, "tipo" => get_storage_name(tipo)},
+            )?,
+            _ => conn.exec_drop(
+                "INSERT INTO conservacion (id_objeto, tipo, dias) VALUES (:id, :tipo, :dias)
+                    ON DUPLICATE KEY UPDATE dias = :dias;",
+                params! {"id" => objeto.id, "tipo" => get_storage_name(tipo), "dias" => dias},
+            )?,
+        }
+        return Ok(());
+    }
+
+    pub fn default_storage<Q: Queryable>(
+        conn: &mut Q,
+        id: i32,
+    ) -> Result<Conservacion, mysql::Error> {
+        //!Devuelve dónde se guarda un objeto cuando no se indica, con choose_storage(): en la despensa si dura algo en ella o no tiene indicado nada, y si no, en la nevera o, como último recurso, en el congelador. Así lo que solo tiene días de nevera recibe su fecha aunque se añada sin decir dónde.
+        let tipos: Vec<String> = conn.exec(
+            "SELECT tipo FROM conservacion WHERE id_objeto = :id;",
+            params! {"id" => id},
+        )?;
+        let tipos: Vec<Conservacion> = tipos.iter().filter_map(|t| parse_storage(t)).collect();
+        return Ok(choose_storage(&tipos));
+    }
+
+    pub fn choose_storage(tipos: &[Conservacion]) -> Conservacion {
+        //!Elige dónde se guarda un objeto a partir de los tipos de conservación para los que tiene indicado cuánto dura, como se explica en default_storage().
+        if tipos.is_empty() || tipos.contains(&Conservacion::Despensa) {
+            return Conservacion::Despensa;
+        }
+        if tipos.contains(&Conservacion::Nevera) {
+            return Conservacion::Nevera;
+        }
+        return Conservacion::Congelador;
+    }
+
+    pub fn add_lot<Q: Queryable>(
+        conn: &mut Q,
+        id: i32,
+        quant: f64,
+        location: &Procedencia,
+        tipo: &Conservacion,
+    ) -> Result<(), mysql::Error> {
+        //!Guarda un lote de quant existencias del objeto con la id dada, que caduca cuando se cumplan desde hoy los días que dura en el tipo de conservación dado. Si el objeto no tiene indicados esos días, no se guarda nada.
+        if quant <= EPSILON {
+            return Ok(());
+        }
+        return conn.exec_drop(
+            "INSERT INTO lotes (id_objeto, lugar, tipo, cantidad, caduca)
+                SELECT id_objeto, :lugar, tipo, :quant, CURDATE() + INTERVAL dias DAY
+                FROM conservacion WHERE id_objeto = :id AND tipo = :tipo;",
+            params! {
+                "id" => id,
+                "lugar" => get_string_name(location),
+                "tipo" => get_storage_name(tipo),
+                "quant" => quant,
+            },
+        );
+    }
+
+    fn take_lots<Q: Queryable>(
+        conn: &mut Q,
+        id: i32,
+        quant: f64,
+        location: &Procedencia,
+        tipo: Option<&Conservacion>,
+    ) -> Result<Vec<(String, f64, String)>, mysql::Error> {
+        //!Retira hasta quant existencias de los lotes del objeto con la id dada en un lugar, de los de un tipo de conservación o de todos, empezando por los que caducan antes. Devuelve la fecha de caducidad, la cantidad y el tipo de conservación de lo que ha retirado de cada lote.
+        let lots: Vec<(i32, String, f64, String)> = conn.exec(
+            "SELECT id, DATE_FORMAT(caduca, '%Y-%m-%d'), cantidad, tipo FROM lotes
+                WHERE id_objeto = :id AND lugar = :lugar ORDER BY caduca, id;",
+            params! {"id" => id, "lugar" => get_string_name(location)},
+        )?;
+        let mut left = quant;
+        let mut taken = Vec::new();
+        for (lote, caduca, cantidad, lot_tipo) in lots {
+            if left <= EPSILON {
+                break;
+            }
+            if let Some(tipo) = tipo {
+                if lot_tipo != get_storage_name(tipo) {
+                    continue;
+                }
+            }
+            let amount = cantidad.min(left);
+            conn.exec_drop(
+                "UPDATE lotes SET cantidad = cantidad - :amount WHERE id = :lote;",
+                params! {"amount" => amount, "lote" => lote},
+            )?;
+            taken.push((caduca, amount, lot_tipo));
+            left -= amount;
+        }
+        conn.exec_drop(
+            "DELETE FROM lotes WHERE id_objeto = :id AND cantidad <= 0;",
+            params! {"id" => id},
+        )?;
+        return Ok(taken);
+    }
+
+    pub fn use_lots<Q: Queryable>(
+        conn: &mut Q,
+        id: i32,
+        quant: f64,
+        location: &Procedencia,
+        tipo: &Conservacion,
+    ) -> Result<(), mysql::Error> {
+        //!Retira quant existencias de los lotes de un tipo de conservación de un lugar, empezando por los que caducan antes. Se usa al retirar existencias de una ubicación concreta, antes de cambiar el total.
+        take_lots(conn, id, quant, location, Some(tipo))?;
+        return Ok(());
+    }
+
+    pub fn move_lots<Q: Queryable>(
+        conn: &mut Q,
+        id: i32,
+        quant: f64,
+        from: (&Procedencia, &Conservacion),
+        to: (&Procedencia, &Conservacion),
+    ) -> Result<(), mysql::Error> {
+        //!Mueve quant existencias de un objeto entre lugares o tipos de conservación. Si la conservación es la misma, los lotes conservan su fecha; si cambia, lo movido se guarda en un lote nuevo con la fecha que corresponde a la nueva conservación desde hoy, como al congelar algo fresco.
+        if from.0 == to.0 && from.1 == to.1 {
+            return Ok(());
+        }
+        let taken = take_lots(conn, id, quant, from.0, Some(from.1))?;
+        if from.1 != to.1 {
+            return add_lot(conn, id, quant, to.0, to.1);
+        }
+        return put_lots(conn, id, to.0, taken);
+    }
+
+    pub fn transfer_lots<Q: Queryable>(
+        conn: &mut Q,
+        id: i32,
+        quant: f64,
+        from: &Procedencia,
+        to: &Procedencia,
+    ) -> Result<(), mysql::Error> {
+        //!Lleva quant existencias de un objeto de un lugar a otro con sus lotes, de cualquier tipo de conservación y empezando por los que caducan antes, sin cambiar ni su fecha ni su conservación. Se usa al transladar sin indicar ubicaciones.
+        let taken = take_lots(conn, id, quant, from, None)?;
+        return put_lots(conn, id, to, taken);
+    }
+
+    fn put_lots<Q: Queryable>(
+        conn: &mut Q,
+        id: i32,
+        location: &Procedencia,
+        lots: Vec<(String, f64, String)>,
+    ) -> Result<(), mysql::Error> {
+        //!Guarda en un lugar los lotes retirados con take_lots(), cada uno con su fecha de caducidad y su tipo de conservación.
+        for (caduca, amount, tipo) in lots {
+            conn.exec_drop(
+                "INSERT INTO lotes (id_objeto, lugar, tipo, cantidad, caduca)
+                    VALUES (:id, :lugar, :tipo, :quant, :caduca);",
+                params! {
+                    "id" => id,
+                    "lugar" => get_string_name(location),
+                    "tipo" => tipo,
+                    "quant" => amount,
+                    "caduca" => caduca,
+                },
+            )?;
+        }
+        return Ok(());
+    }
+
+    pub fn fit_lots<Q: Queryable>(
+        conn: &mut Q,
+        id: i32,
+        location: &Procedencia,
+    ) -> Result<(), mysql::Error> {
+        //!Comprueba que los lotes de un objeto en un lugar no suman más que el total del lugar y, si lo hacen, retira lo que sobra de los que caducan antes, que son los que se deberían haber gastado. La llama update_stock() cada vez que cambia un total.
+        let total: f64 = conn
+            .exec_first(
+                format!(
+                    "SELECT cantidad FROM {} WHERE id_objeto = :id;",
+                    get_table_name(location)
+                ),
+                params! {"id" => id},
+            )?
+            .unwrap_or(None::<f64>)
+            .unwrap_or(0.0);
+        let in_lots: f64 = conn
+            .exec_first(
+                "SELECT SUM(cantidad) FROM lotes WHERE id_objeto = :id AND lugar = :lugar;",
+                params! {"id" => id, "lugar" => get_string_name(location)},
+            )?
+            .unwrap_or(None::<f64>)
+            .unwrap_or(0.0);
+        if in_lots - total.max(0.0) > EPSILON {
+            take_lots(conn, id, in_lots - total.max(0.0), location, None)?;
+        }
+        return Ok(());
+    }
+
+    pub fn use_first(
+        conn: &mut PooledConn,
+        hogar: &Hogar,
+        dias: Option<i64>,
+    ) -> Result<Vec<Lote>, BodegaError> {
+        //!Devuelve los lotes del hogar dado, de los que caducan antes a los que caducan después, incluidos los ya caducados. Con dias, solo los que caducan en ese número de días o menos.
+        let objects = read_objects(conn, hogar)?;
+        let rows: Vec<(i32, String, String, f64, String, i64)> = conn.exec(
+            "SELECT l.id_objeto, l.lugar, l.tipo, l.cantidad, DATE_FORMAT(l.caduca, '%Y-%m-%d'),
+                    DATEDIFF(l.caduca, CURDATE())
+                FROM lotes l
+                JOIN objetos o ON o.id = l.id_objeto
+                JOIN categorias c ON c.id = o.categoria
+                WHERE c.hogar = :hogar AND l.cantidad > 0
+                ORDER BY l.caduca, o.nombre;",
+            params! {"hogar" => hogar.id},
+        )?;
+        return Ok(rows
+            .into_iter()
+            .filter(|row| match dias {
+                Some(d) => row.5 <= d,
+                None => true,
+            })
+            .filter_map(|(id, lugar, tipo, cantidad, caduca, restantes)| {
+                let objeto = objects.iter().find(|o| o.id == id)?.clone();
+                Some(Lote {
+                    objeto,
+                    lugar: parse_location(&lugar)?,
+                    tipo: parse_storage(&tipo)?,
+                    cantidad,
+                    caduca,
+                    dias: restantes,
+                })
+            })
+            .collect());
+    }
+
+    pub fn print_use_first(lotes: &[Lote]) {
+        //!Muestra qué gastar primero: cada lote con cuándo caduca, lo caducado al principio.
+        if lotes.is_empty() {
+            println!("No hay nada con fecha de caducidad");
+            return;
+        }
+        for l in lotes {
+            let cuando = match l.dias {
+                d if d < 0 => format!("CADUCADO hace {} días", -d),
+                0 => String::from("caduca hoy"),
+                1 => String::from("caduca mañana"),
+                d => format!("caduca en {} días", d),
+            };
+            println!(
+                "{}  {}: x{} {} en {} ({}), {}",
+                l.caduca,
+                l.objeto.nombre,
+                l.cantidad,
+                l.objeto.medida,
+                get_string_name(&l.lugar),
+                get_storage_name(&l.tipo),
+                cuando
+            );
+        }
+    }
+
+    pub fn print_shelf_life(objeto: &Objeto, vidas: &[VidaUtil]) {
+        //!Muestra los días que dura un objeto en cada tipo de conservación.
+        if vidas.is_empty() {
+            println!("{} no tiene indicado cuánto dura", objeto.nombre);
+            return;
+        }
+        for v in vidas {
+            println!(
+                "{}: {} días en {}",
+                objeto.nombre,
+                v.dias,
+                get_storage_name(&v.tipo)
+            );
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn pantry_when_nothing_is_known() {
+            assert_eq!(choose_storage(&[]), Conservacion::Despensa);
+        }
+
+        #[test]
+        fn pantry_first_then_fridge_then_freezer() {
+            assert_eq!(
+                choose_storage(&[Conservacion::Congelador, Conservacion::Despensa]),
+                Conservacion::Despensa
+            );
+            assert_eq!(
+                choose_storage(&[Conservacion::Congelador, Conservacion::Nevera]),
+                Conservacion::Nevera
+            );
+            assert_eq!(
+                choose_storage(&[Conservacion::Congelador]),
+                Conservacion::Congelador
+            );
+        }
+    }
+}
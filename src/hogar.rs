@@ -0,0 +1,285 @@
+///Módulo que gestiona los hogares: inventarios independientes que comparten la misma base de datos, cada uno con sus propias categorías, objetos y existencias, y los usuarios que son miembros de cada uno.
+pub mod hogar {
+    use crate::auth::auth::{
+        check_permission, has_permission, parse_role, read_users, Permiso, Rol, Usuario,
+    };
+    use crate::error::error::BodegaError;
+    use crate::selector::selector::{choose, read_input, resolve, Resolucion};
+    use mysql::prelude::Queryable;
+    use mysql::{params, PooledConn, TxOpts};
+    use serde::Serialize;
+
+    ///Estructura basada en la tabla hogares de la base de datos bodega-db
+    #[derive(Clone, Serialize)]
+    pub struct Hogar {
+        pub id: i32,
+        pub nombre: String,
+    }
+
+    ///Usuario que ha iniciado sesión y hogar sobre el que trabaja. Todas las operaciones sobre el inventario la reciben, para comprobar los permisos y no salir nunca del hogar activo.
+    #[derive(Clone)]
+    pub struct Sesion {
+        pub usuario: Usuario,
+        pub hogar: Hogar,
+    }
+
+    pub fn read_households(conn: &mut PooledConn) -> Result<Vec<Hogar>, BodegaError> {
+        //!Lee la tabla hogares y la devuelve como un vector de "Hogar".
+        return Ok(
+            conn.query_map("SELECT id, nombre FROM hogares;", |(id, nombre)| Hogar {
+                id,
+                nombre,
+            })?,
+        );
+    }
+
+    pub fn read_user_households(
+        conn: &mut PooledConn,
+        usuario: &Usuario,
+    ) -> Result<Vec<Hogar>, BodegaError> {
+        //!Devuelve los hogares de los que es miembro el usuario dado.
+        return Ok(conn.exec_map(
+            "SELECT h.id, h.nombre FROM hogares h
+                    JOIN miembros m ON m.id_hogar = h.id
+                    WHERE m.id_usuario = :usuario ORDER BY h.nombre;",
+            params! {"usuario" => usuario.id},
+            |(id, nombre)| Hogar { id, nombre },
+        )?);
+    }
+
+    pub fn open_session(
+        conn: &mut PooledConn,
+        usuario: Usuario,
+        hogar: Hogar,
+    ) -> Result<Sesion, BodegaError> {
+        //!Empieza a trabajar en un hogar del que el usuario es miembro, con el rol que tiene en él. Se llama cada vez que se elige o se cambia de hogar.
+        let rol: Option<String> = conn.exec_first(
+            "SELECT rol FROM miembros WHERE id_usuario = :usuario AND id_hogar = :hogar;",
+            params! {"usuario" => usuario.id, "hogar" => hogar.id},
+        )?;
+        return match rol {
+            Some(rol) => Ok(Sesion {
+                usuario: Usuario {
+                    rol: parse_role(&rol).unwrap_or(Rol::Lectura),
+                    ..usuario
+                },
+                hogar,
+            }),
+            None => Err(BodegaError::SinPermiso(format!(
+                "{} no es miembro de {}",
+                usuario.nombre, hogar.nombre
+            ))),
+        };
+    }
+
+    pub fn read_addable_users(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+    ) -> Result<Vec<Usuario>, BodegaError> {
+        //!Devuelve los usuarios que se pueden añadir al hogar activo: los que no son miembros de él pero sí de otro hogar que administra el usuario de la sesión. Así nadie ve ni toma usuarios de hogares ajenos. Entran con el rol de solo lectura.
+        return Ok(conn.exec_map(
+            "SELECT DISTINCT u.id, u.nombre FROM usuarios u
+                JOIN miembros m ON m.id_usuario = u.id
+                JOIN miembros yo ON yo.id_hogar = m.id_hogar
+                    AND yo.id_usuario = :usuario AND yo.rol = 'admin'
+                WHERE u.id NOT IN (SELECT id_usuario FROM miembros WHERE id_hogar = :hogar)
+                ORDER BY u.nombre;",
+            params! {"usuario" => sesion.usuario.id, "hogar" => sesion.hogar.id},
+            |(id, nombre)| Usuario {
+                id,
+                nombre,
+                rol: Rol::Lectura,
+            },
+        )?);
+    }
+
+    fn create_household(
+        conn: &mut PooledConn,
+        usuario: &Usuario,
+        name: String,
+    ) -> Result<Hogar, BodegaError> {
+        //!Crea un hogar con el nombre dado y hace al usuario miembro y administrador de él, en una única transacción. Comprueba que el nombre no está vacío ni repetido.
+        let name = String::from(name.trim());
+        if name.is_empty() {
+            return Err(BodegaError::Invalido(String::from(
+                "El nombre no puede estar vacío",
+            )));
+        }
+        if read_households(conn)?.iter().any(|h| h.nombre == name) {
+            return Err(BodegaError::Invalido(format!(
+                "Ya existe un hogar llamado {}",
+                name
+            )));
+        }
+        let mut tx = conn.start_transaction(TxOpts::default())?;
+        tx.exec_drop(
+            "INSERT INTO hogares (nombre) VALUES (:nombre);",
+            params! {"nombre" => &name},
+        )?;
+        let id = tx.last_insert_id().unwrap_or(0) as i32;
+        tx.exec_drop(
+            "INSERT INTO miembros (id_usuario, id_hogar, rol) VALUES (:usuario, :hogar, 'admin');",
+            params! {"usuario" => usuario.id, "hogar" => id},
+        )?;
+        tx.commit()?;
+        return Ok(Hogar { id, nombre: name });
+    }
+
+    pub fn insert_household(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        name: String,
+    ) -> Result<Hogar, BodegaError> {
+        //!Crea un hogar vacío del que el usuario pasa a ser miembro, si puede gestionar hogares.
+        check_permission(&sesion.usuario, &Permiso::GestionarHogares)?;
+        return create_household(conn, &sesion.usuario, name);
+    }
+
+    pub fn add_member(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        target: &Usuario,
+    ) -> Result<(), BodegaError> {
+        //!Hace al usuario dado miembro del hogar activo, con el rol de solo lectura, si el usuario de la sesión puede gestionar hogares. Solo se pueden añadir los que da read_addable_users().
+        check_permission(&sesion.usuario, &Permiso::GestionarHogares)?;
+        if !read_addable_users(conn, sesion)?
+            .iter()
+            .any(|u| u.id == target.id)
+        {
+            return Err(BodegaError::NoEncontrado(format!(
+                "{} no es miembro de ningún otro hogar que administres, o ya es miembro de {}",
+                target.nombre, sesion.hogar.nombre
+            )));
+        }
+        conn.exec_drop(
+            "INSERT IGNORE INTO miembros (id_usuario, id_hogar) VALUES (:usuario, :hogar);",
+            params! {"usuario" => target.id, "hogar" => sesion.hogar.id},
+        )?;
+        return Ok(());
+    }
+
+    pub fn remove_member(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        target: &Usuario,
+    ) -> Result<(), BodegaError> {
+        //!Quita al usuario dado del hogar activo, si el usuario de la sesión puede gestionar hogares. No permite dejar un hogar sin miembros.
+        check_permission(&sesion.usuario, &Permiso::GestionarHogares)?;
+        let members = read_users(conn, &sesion.hogar)?;
+        if !members.iter().any(|u| u.id == target.id) {
+            return Err(BodegaError::NoEncontrado(format!(
+                "{} no es miembro de {}",
+                target.nombre, sesion.hogar.nombre
+            )));
+        }
+        if members.len() <= 1 {
+            return Err(BodegaError::Invalido(format!(
+                "{} es el único miembro de {}",
+                target.nombre, sesion.hogar.nombre
+            )));
+        }
+        conn.exec_drop(
+            "DELETE FROM miembros WHERE id_usuario = :usuario AND id_hogar = :hogar;",
+            params! {"usuario" => target.id, "hogar" => sesion.hogar.id},
+        )?;
+        return Ok(());
+    }
+
+    pub fn find_household(
+        conn: &mut PooledConn,
+        usuario: &Usuario,
+        name: Option<&str>,
+    ) -> Result<Hogar, BodegaError> {
+        //!Obtiene, sin preguntar nada, el hogar del usuario con el id o el nombre dado. Si no se da ninguno, solo es posible cuando el usuario es miembro de un único hogar.
+        let households = read_user_households(conn, usuario)?;
+        match name.map(str::trim).filter(|n| !n.is_empty()) {
+            Some(name) => match resolve(name, &households) {
+                Resolucion::Unico(h) => {
+                    return Ok(h);
+                }
+                Resolucion::Varios(_) => {
+                    return Err(BodegaError::Invalido(format!(
+                        "\"{}\" coincide con varios hogares",
+                        name
+                    )));
+                }
+                Resolucion::Ninguno => {
+                    return Err(BodegaError::NoEncontrado(format!(
+                        "{} no es miembro de ningún hogar llamado \"{}\"",
+                        usuario.nombre, name
+                    )));
+                }
+            },
+            None => match households.len() {
+                0 => {
+                    return Err(BodegaError::SinPermiso(format!(
+                        "{} no es miembro de ningún hogar",
+                        usuario.nombre
+                    )));
+                }
+                1 => {
+                    return Ok(households[0].clone());
+                }
+                _ => {
+                    return Err(BodegaError::Invalido(format!(
+                        "{} es miembro de varios hogares. Indica cuál: {}",
+                        usuario.nombre,
+                        households
+                            .iter()
+                            .map(|h| h.nombre.as_str())
+                            .collect::<Vec<&str>>()
+                            .join(", ")
+                    )));
+                }
+            },
+        }
+    }
+
+    pub fn ask_household(
+        conn: &mut PooledConn,
+        usuario: &Usuario,
+        name: Option<String>,
+    ) -> Option<Hogar> {
+        //!Elige el hogar con el que trabajar por la terminal: el indicado, el único del que es miembro el usuario, o el que elija de una lista. Si todavía no hay ningún hogar y el usuario puede gestionarlos, pide crear el primero.
+        let households = match read_user_households(conn, usuario) {
+            Ok(households) => households,
+            Err(e) => {
+                println!("No se pudieron leer los hogares: {}", e);
+                return None;
+            }
+        };
+        if let Some(name) = name {
+            return choose(&name, &households);
+        }
+        match households.len() {
+            0 => {
+                if !read_households(conn).map(|h| h.is_empty()).unwrap_or(false)
+                    || !has_permission(&usuario.rol, &Permiso::GestionarHogares)
+                {
+                    println!("{} no es miembro de ningún hogar", usuario.nombre);
+                    return None;
+                }
+                println!("Todavía no hay hogares. Nombre del primero: ");
+                match create_household(conn, usuario, read_input()) {
+                    Ok(hogar) => {
+                        return Some(hogar);
+                    }
+                    Err(e) => {
+                        println!("No se pudo crear el hogar: {}", e);
+                        return None;
+                    }
+                }
+            }
+            1 => {
+                return Some(households[0].clone());
+            }
+            _ => {
+                for h in &households {
+                    println!("({}) {}", h.id, h.nombre);
+                }
+                println!("\nIntroduce el ID o el nombre del hogar: ");
+                return choose(&read_input(), &households);
+            }
+        }
+    }
+}
@@ -0,0 +1,390 @@
+///Módulo que comprueba la integridad de los datos del hogar activo: nombres repetidos, cantidades nulas o negativas y unidades de medida desconocidas. Cada problema encontrado se puede reparar, preguntando al usuario qué hacer.
+pub mod integrity {
+    use crate::auth::auth::{check_permission, Permiso};
+    use crate::db_manager::db_manager::{
+        get_string_name, get_table_name, lock_stock, parse_quantity, read_categories, read_objects,
+        record_movement, update_stock, validate_stock_change, Objeto, Procedencia, TipoMovimiento,
+        EPSILON,
+    };
+    use crate::error::error::BodegaError;
+    use crate::hogar::hogar::{Hogar, Sesion};
+    use crate::selector::selector::{normalize, read_input};
+    use mysql::prelude::Queryable;
+    use mysql::{params, PooledConn, TxOpts};
+    use serde::Serialize;
+
+    ///Unidades de medida conocidas, ya normalizadas. Cualquier otra se señala para poder corregir erratas.
+    pub const UNITS: [&str; 43] = [
+        "g",
+        "gr",
+        "gramo",
+        "gramos",
+        "kg",
+        "kilo",
+        "kilos",
+        "ml",
+        "mililitro",
+        "mililitros",
+        "cl",
+        "l",
+        "litro",
+        "litros",
+        "u",
+        "ud",
+        "uds",
+        "unidad",
+        "unidades",
+        "lata",
+        "latas",
+        "botella",
+        "botellas",
+        "bote",
+        "botes",
+        "paquete",
+        "paquetes",
+        "bolsa",
+        "bolsas",
+        "caja",
+        "cajas",
+        "docena",
+        "docenas",
+        "rollo",
+        "rollos",
+        "sobre",
+        "sobres",
+        "tarro",
+        "tarros",
+        "brick",
+        "bricks",
+        "racion",
+        "raciones",
+    ];
+
+    ///Problema de integridad encontrado en la base de datos.
+    #[derive(Clone, Serialize)]
+    #[serde(tag = "tipo", rename_all = "snake_case")]
+    pub enum Problema {
+        ///Varias categorías del hogar se llaman igual, así que no se pueden elegir por nombre
+        CategoriaRepetida { nombre: String, ids: Vec<i32> },
+        ///Varios objetos del hogar se llaman igual, así que no se pueden elegir por nombre
+        ObjetoRepetido { nombre: String, ids: Vec<i32> },
+        ///La cantidad de un objeto en un lugar es nula
+        CantidadNula { lugar: Procedencia, objeto: Objeto },
+        ///La cantidad de un objeto en un lugar es negativa
+        CantidadNegativa {
+            lugar: Procedencia,
+            objeto: Objeto,
+            cantidad: f64,
+        },
+        ///Unos objetos usan una unidad de medida que no está en UNITS
+        MedidaDesconocida {
+            medida: String,
+            objetos: Vec<Objeto>,
+        },
+    }
+
+    pub fn describe_problem(problema: &Problema) -> String {
+        //!Describe un problema para mostrarlo al usuario.
+        match problema {
+            Problema::CategoriaRepetida { nombre, ids } => format!(
+                "Hay {} categorías llamadas {}: {}",
+                ids.len(),
+                nombre,
+                join_ids(ids)
+            ),
+            Problema::ObjetoRepetido { nombre, ids } => format!(
+                "Hay {} objetos llamados {}: {}",
+                ids.len(),
+                nombre,
+                join_ids(ids)
+            ),
+            Problema::CantidadNula { lugar, objeto } => format!(
+                "La cantidad de {} ({}) en {} es nula",
+                objeto.nombre,
+                objeto.id,
+                get_string_name(lugar)
+            ),
+            Problema::CantidadNegativa {
+                lugar,
+                objeto,
+                cantidad,
+            } => format!(
+                "La cantidad de {} ({}) en {} es negativa: x{} {}",
+                objeto.nombre,
+                objeto.id,
+                get_string_name(lugar),
+                cantidad,
+                objeto.medida
+            ),
+            Problema::MedidaDesconocida { medida, objetos } => format!(
+                "La unidad \"{}\" no es conocida. La usan: {}",
+                medida,
+                objetos
+                    .iter()
+                    .map(|o| o.nombre.as_str())
+                    .collect::<Vec<&str>>()
+                    .join(", ")
+            ),
+        }
+    }
+
+    fn join_ids(ids: &[i32]) -> String {
+        //!Une una lista de ids para mostrarla.
+        return ids
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<String>>()
+            .join(", ");
+    }
+
+    fn duplicates(names: Vec<(i32, String)>) -> Vec<(String, Vec<i32>)> {
+        //!Agrupa los registros dados por su nombre normalizado y devuelve los grupos de más de uno, con el nombre del primero.
+        let mut groups: Vec<(String, String, Vec<i32>)> = Vec::new();
+        for (id, name) in names {
+            let key = normalize(&name);
+            match groups.iter_mut().find(|(k, _, _)| *k == key) {
+                Some((_, _, ids)) => ids.push(id),
+                None => groups.push((key, name, vec![id])),
+            }
+        }
+        return groups
+            .into_iter()
+            .filter(|(_, _, ids)| ids.len() > 1)
+            .map(|(_, name, ids)| (name, ids))
+            .collect();
+    }
+
+    pub fn scan(conn: &mut PooledConn, hogar: &Hogar) -> Result<Vec<Problema>, BodegaError> {
+        //!Busca todos los problemas de integridad del hogar dado. Las referencias rotas no son de ningún hogar, así que no se buscan aquí sino en check_orphans() de doctor.
+        let mut problems = Vec::new();
+        let categories = read_categories(conn, hogar)?;
+        for (nombre, ids) in duplicates(categories.into_iter().map(|c| (c.id, c.nombre)).collect())
+        {
+            problems.push(Problema::CategoriaRepetida { nombre, ids });
+        }
+        let objects = read_objects(conn, hogar)?;
+        for (nombre, ids) in duplicates(objects.iter().map(|o| (o.id, o.nombre.clone())).collect())
+        {
+            problems.push(Problema::ObjetoRepetido { nombre, ids });
+        }
+
+        for location in Procedencia::all() {
+            let rows: Vec<(i32, Option<f64>)> = conn.query(format!(
+                "SELECT id_objeto, cantidad FROM {};",
+                get_table_name(&location)
+            ))?;
+            for (id, cantidad) in rows {
+                let objeto = match objects.iter().find(|o| o.id == id) {
+                    Some(objeto) => objeto.clone(),
+                    None => continue,
+                };
+                match cantidad {
+                    None => problems.push(Problema::CantidadNula {
+                        lugar: location.clone(),
+                        objeto,
+                    }),
+                    Some(cantidad) if cantidad < -EPSILON => {
+                        problems.push(Problema::CantidadNegativa {
+                            lugar: location.clone(),
+                            objeto,
+                            cantidad,
+                        })
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+
+        let mut units: Vec<(String, Vec<Objeto>)> = Vec::new();
+        for objeto in objects {
+            if UNITS.contains(&normalize(&objeto.medida).as_str()) {
+                continue;
+            }
+            match units.iter_mut().find(|(m, _)| *m == objeto.medida) {
+                Some((_, list)) => list.push(objeto),
+                None => units.push((objeto.medida.clone(), vec![objeto])),
+            }
+        }
+        for (medida, objetos) in units {
+            problems.push(Problema::MedidaDesconocida { medida, objetos });
+        }
+        return Ok(problems);
+    }
+
+    pub fn correct_stock(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        id: i32,
+        quant: f32,
+        location: &Procedencia,
+    ) -> Result<(), BodegaError> {
+        //!Fija directamente la cantidad de un objeto del hogar activo en location, sin crear lotes, y registra la corrección como un movimiento de inventario, en una única transacción. Requiere el permiso de gestionar existencias.
+        check_permission(&sesion.usuario, &Permiso::GestionarExistencias)?;
+        let obj = validate_stock_change(conn, &sesion.hogar, id, quant)?;
+        let mut tx = conn.start_transaction(TxOpts::default())?;
+        let current = lock_stock(&mut tx, &obj, true, quant, location)?;
+        update_stock(&mut tx, id, true, quant, location)?;
+        record_movement(
+            &mut tx,
+            id,
+            quant as f64 - current,
+            location,
+            &TipoMovimiento::Inventario,
+            &sesion.usuario,
+        )?;
+        tx.commit()?;
+        return Ok(());
+    }
+
+    pub fn rename_category(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        id: i32,
+        name: String,
+    ) -> Result<(), BodegaError> {
+        //!Cambia el nombre de una categoría del hogar activo, si el usuario puede gestionar el catálogo.
+        check_permission(&sesion.usuario, &Permiso::GestionarCatalogo)?;
+        let name = check_name(name)?;
+        conn.exec_drop(
+            "UPDATE categorias SET nombre = :nombre WHERE id = :id AND hogar = :hogar;",
+            params! {"nombre" => name, "id" => id, "hogar" => sesion.hogar.id},
+        )?;
+        return Ok(());
+    }
+
+    pub fn rename_object(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        id: i32,
+        name: String,
+    ) -> Result<(), BodegaError> {
+        //!Cambia el nombre de un objeto del hogar activo, si el usuario puede gestionar el catálogo.
+        check_permission(&sesion.usuario, &Permiso::GestionarCatalogo)?;
+        let name = check_name(name)?;
+        conn.exec_drop(
+            "UPDATE objetos o JOIN categorias c ON c.id = o.categoria
+                SET o.nombre = :nombre WHERE o.id = :id AND c.hogar = :hogar;",
+            params! {"nombre" => name, "id" => id, "hogar" => sesion.hogar.id},
+        )?;
+        return Ok(());
+    }
+
+    pub fn change_unit(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        objetos: &[Objeto],
+        medida: String,
+    ) -> Result<(), BodegaError> {
+        //!Cambia la unidad de medida de los objetos dados del hogar activo, si el usuario puede gestionar el catálogo.
+        check_permission(&sesion.usuario, &Permiso::GestionarCatalogo)?;
+        let medida = check_name(medida)?;
+        let mut tx = conn.start_transaction(TxOpts::default())?;
+        for objeto in objetos {
+            tx.exec_drop(
+                "UPDATE objetos o JOIN categorias c ON c.id = o.categoria
+                    SET o.medida = :medida WHERE o.id = :id AND c.hogar = :hogar;",
+                params! {"medida" => &medida, "id" => objeto.id, "hogar" => sesion.hogar.id},
+            )?;
+        }
+        tx.commit()?;
+        return Ok(());
+    }
+
+    fn check_name(name: String) -> Result<String, BodegaError> {
+        //!Comprueba que un nombre nuevo no está vacío y lo devuelve sin espacios en los extremos.
+        let name = String::from(name.trim());
+        if name.is_empty() {
+            return Err(BodegaError::Invalido(String::from(
+                "El nombre no puede estar vacío",
+            )));
+        }
+        return Ok(name);
+    }
+
+    fn ask_option(options: &[&str]) -> String {
+        //!Muestra las opciones de reparación numeradas, además de la de dejarlo como está, y devuelve lo que elija el usuario.
+        for (i, option) in options.iter().enumerate() {
+            println!("  {}. {}", i + 1, option);
+        }
+        println!("  Otra cosa: dejarlo como está");
+        return read_input();
+    }
+
+    fn repair(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        problema: &Problema,
+    ) -> Result<bool, BodegaError> {
+        //!Pregunta cómo reparar un problema y lo repara. Devuelve si se ha cambiado algo.
+        match problema {
+            Problema::CategoriaRepetida { ids, .. } | Problema::ObjetoRepetido { ids, .. } => {
+                if ask_option(&["Cambiar el nombre de todas menos la primera"]) != "1" {
+                    return Ok(false);
+                }
+                for id in &ids[1..] {
+                    println!("Nuevo nombre para {}: ", id);
+                    match problema {
+                        Problema::CategoriaRepetida { .. } => {
+                            rename_category(conn, sesion, *id, read_input())?
+                        }
+                        _ => rename_object(conn, sesion, *id, read_input())?,
+                    }
+                }
+            }
+            Problema::CantidadNula { lugar, objeto }
+            | Problema::CantidadNegativa { lugar, objeto, .. } => {
+                match ask_option(&["Poner la cantidad a 0", "Escribir la cantidad"]).as_str() {
+                    "1" => correct_stock(conn, sesion, objeto.id, 0.0, lugar)?,
+                    "2" => {
+                        println!(
+                            "Cantidad de {} en {}: ",
+                            objeto.nombre,
+                            get_string_name(lugar)
+                        );
+                        let quant = parse_quantity(&read_input())?;
+                        correct_stock(conn, sesion, objeto.id, quant, lugar)?;
+                    }
+                    _ => return Ok(false),
+                }
+            }
+            Problema::MedidaDesconocida { objetos, .. } => {
+                if ask_option(&["Cambiarla por otra en todos esos objetos"]) != "1" {
+                    return Ok(false);
+                }
+                println!("Nueva unidad de medida: ");
+                change_unit(conn, sesion, objetos, read_input())?;
+            }
+        }
+        return Ok(true);
+    }
+
+    pub fn repair_interactive(conn: &mut PooledConn, sesion: &Sesion, problems: &[Problema]) {
+        //!Recorre los problemas dados preguntando cómo reparar cada uno. Los errores de una reparación se muestran y se sigue con el siguiente problema.
+        let mut repaired = 0;
+        for (i, problema) in problems.iter().enumerate() {
+            println!(
+                "\n({}/{}) {}",
+                i + 1,
+                problems.len(),
+                describe_problem(problema)
+            );
+            match repair(conn, sesion, problema) {
+                Ok(true) => {
+                    repaired += 1;
+                    println!("Reparado");
+                }
+                Ok(false) => {
+                    println!("Se deja como está");
+                }
+                Err(e) => {
+                    println!("No se pudo reparar: {}", e);
+                }
+            }
+        }
+        println!(
+            "\nSe repararon {} de {} problemas",
+            repaired,
+            problems.len()
+        );
+    }
+}
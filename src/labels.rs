@@ -0,0 +1,299 @@
+///Módulo que genera etiquetas para las estanterías, con el nombre, la medida y el lugar de cada objeto y un código QR que lo identifica. Las etiquetas se dibujan en hojas A4 de 3 x 8 etiquetas de 70 x 37 mm, cada una en un fichero SVG que se puede imprimir desde el navegador.
+pub mod labels {
+    use crate::db_manager::db_manager::{get_string_name, parse_location, Objeto, Procedencia};
+    use crate::error::error::BodegaError;
+    use crate::selector::selector::normalize;
+    use qrcodegen::{QrCode, QrCodeEcc};
+    use std::path::{Path, PathBuf};
+
+    ///Primera palabra del contenido de los códigos QR, que los distingue de cualquier otra cosa que se escriba
+    const PREFIX: &str = "BODEGA";
+    ///Columnas de etiquetas de cada hoja
+    const COLUMNS: usize = 3;
+    ///Filas de etiquetas de cada hoja
+    const ROWS: usize = 8;
+    ///Ancho de la hoja, en mm
+    const PAGE_WIDTH: f64 = 210.0;
+    ///Alto de la hoja, en mm
+    const PAGE_HEIGHT: f64 = 297.0;
+    ///Ancho de cada etiqueta, en mm
+    const LABEL_WIDTH: f64 = 70.0;
+    ///Alto de cada etiqueta, en mm
+    const LABEL_HEIGHT: f64 = 37.0;
+    ///Margen alrededor del código QR, en mm
+    const MARGIN: f64 = 2.0;
+    ///Módulos en blanco alrededor del código QR, para que los lectores lo encuentren
+    const QUIET_ZONE: i32 = 4;
+    ///Caracteres que caben en cada línea del nombre
+    const LINE_LENGTH: usize = 16;
+
+    ///Etiqueta de un objeto, para un lugar o para cualquiera.
+    #[derive(Clone)]
+    pub struct Etiqueta {
+        pub objeto: Objeto,
+        pub lugar: Option<Procedencia>,
+    }
+
+    pub fn label_payload(objeto: &Objeto, lugar: &Option<Procedencia>) -> String {
+        //!Devuelve el contenido del código QR de un objeto: "BODEGA 12" o, con lugar, "BODEGA 12 TARA". Solo lleva letras, números y espacios, porque los lectores que escriben como un teclado cambian los símbolos si la distribución del teclado no es la suya.
+        return match lugar {
+            Some(lugar) => format!(
+                "{} {} {}",
+                PREFIX,
+                objeto.id,
+                get_string_name(lugar).to_uppercase()
+            ),
+            None => format!("{} {}", PREFIX, objeto.id),
+        };
+    }
+
+    pub fn parse_label(text: &str) -> Option<(i32, Option<Procedencia>)> {
+        //!Lee el contenido del código QR de una etiqueta y devuelve el id del objeto y, si lo tiene, el lugar. Devuelve None si el texto no es de una etiqueta.
+        let text = normalize(text);
+        let words: Vec<&str> = text.split(' ').collect();
+        return match words.as_slice() {
+            [prefix, id] if *prefix == PREFIX.to_lowercase() => {
+                id.parse::<i32>().ok().map(|id| (id, None))
+            }
+            [prefix, id, lugar] if *prefix == PREFIX.to_lowercase() => {
+                match (id.parse::<i32>(), parse_location(lugar)) {
+                    (Ok(id), Some(lugar)) => Some((id, Some(lugar))),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+    }
+
+    pub fn labels_for(objects: &[Objeto], lugares: &[Procedencia]) -> Vec<Etiqueta> {
+        //!Devuelve una etiqueta por cada objeto y lugar dados, ordenadas por categoría y nombre. Sin lugares, una por objeto que vale para cualquiera.
+        let mut objects = objects.to_vec();
+        objects.sort_by(|a, b| {
+            (&a.categoria.nombre, &a.nombre).cmp(&(&b.categoria.nombre, &b.nombre))
+        });
+        let lugares: Vec<Option<Procedencia>> = match lugares.is_empty() {
+            true => vec![None],
+            false => lugares.iter().cloned().map(Some).collect(),
+        };
+        let mut result = Vec::new();
+        for objeto in objects {
+            for lugar in &lugares {
+                result.push(Etiqueta {
+                    objeto: objeto.clone(),
+                    lugar: lugar.clone(),
+                });
+            }
+        }
+        return result;
+    }
+
+    fn escape(text: &str) -> String {
+        //!Escapa un texto para ponerlo dentro de un SVG.
+        return text
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;");
+    }
+
+    fn split_name(name: &str) -> Vec<String> {
+        //!Reparte un nombre en dos líneas de como mucho LINE_LENGTH caracteres, cortando por palabras, y acorta lo que no quepa.
+        let mut lines: Vec<String> = vec![String::new()];
+        for word in name.split_whitespace() {
+            let last = lines.len() - 1;
+            if lines[last].is_empty()
+                || lines[last].chars().count() + 1 + word.chars().count() <= LINE_LENGTH
+            {
+                if !lines[last].is_empty() {
+                    lines[last].push(' ');
+                }
+                lines[last].push_str(word);
+            } else if lines.len() < 2 {
+                lines.push(String::from(word));
+            } else {
+                lines[last].push_str(" …");
+                break;
+            }
+        }
+        return lines
+            .into_iter()
+            .map(|l| match l.chars().count() > LINE_LENGTH {
+                true => l.chars().take(LINE_LENGTH - 1).chain(['…']).collect(),
+                false => l,
+            })
+            .collect();
+    }
+
+    fn draw_qr(payload: &str, x: f64, y: f64, size: f64) -> Result<String, BodegaError> {
+        //!Dibuja el código QR del contenido dado en un cuadrado de lado size, como un único trazado con un cuadrado por módulo oscuro.
+        let qr = QrCode::encode_text(payload, QrCodeEcc::Medium).map_err(|e| {
+            BodegaError::Invalido(format!(
+                "No se pudo generar el código QR de {}: {}",
+                payload, e
+            ))
+        })?;
+        let scale = size / (qr.size() + 2 * QUIET_ZONE) as f64;
+        let mut path = String::new();
+        for row in 0..qr.size() {
+            for col in 0..qr.size() {
+                if qr.get_module(col, row) {
+                    path.push_str(&format!(
+                        "M{},{}h1v1h-1z",
+                        col + QUIET_ZONE,
+                        row + QUIET_ZONE
+                    ));
+                }
+            }
+        }
+        return Ok(format!(
+            "<path transform=\"translate({:.2},{:.2}) scale({:.4})\" d=\"{}\" fill=\"#000\" shape-rendering=\"crispEdges\"/>",
+            x, y, scale, path
+        ));
+    }
+
+    fn draw_label(etiqueta: &Etiqueta, x: f64, y: f64) -> Result<String, BodegaError> {
+        //!Dibuja una etiqueta con su esquina superior izquierda en (x, y): el código QR a la izquierda y el nombre, la medida, el lugar y el contenido del código a la derecha.
+        let payload = label_payload(&etiqueta.objeto, &etiqueta.lugar);
+        let qr_size = LABEL_HEIGHT - 2.0 * MARGIN;
+        let text_x = x + qr_size + 2.0 * MARGIN;
+        let mut svg = format!(
+            "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{}\" height=\"{}\" fill=\"none\" stroke=\"#ccc\" stroke-width=\"0.2\"/>\n",
+            x, y, LABEL_WIDTH, LABEL_HEIGHT
+        );
+        svg.push_str(&draw_qr(&payload, x + MARGIN, y + MARGIN, qr_size)?);
+        svg.push('\n');
+        let mut line_y = y + 9.0;
+        for line in split_name(&etiqueta.objeto.nombre) {
+            svg.push_str(&format!(
+                "<text x=\"{:.2}\" y=\"{:.2}\" font-size=\"4\" font-weight=\"bold\">{}</text>\n",
+                text_x,
+                line_y,
+                escape(&line)
+            ));
+            line_y += 5.0;
+        }
+        let medida = match etiqueta.objeto.envase {
+            Some(envase) => format!("{} {} por envase", envase, etiqueta.objeto.medida),
+            None => etiqueta.objeto.medida.clone(),
+        };
+        svg.push_str(&format!(
+            "<text x=\"{:.2}\" y=\"{:.2}\" font-size=\"3\">{}</text>\n",
+            text_x,
+            y + 21.0,
+            escape(&medida)
+        ));
+        if let Some(lugar) = &etiqueta.lugar {
+            svg.push_str(&format!(
+                "<text x=\"{:.2}\" y=\"{:.2}\" font-size=\"3\">{}</text>\n",
+                text_x,
+                y + 26.0,
+                escape(&get_string_name(lugar))
+            ));
+        }
+        svg.push_str(&format!(
+            "<text x=\"{:.2}\" y=\"{:.2}\" font-size=\"2.2\" fill=\"#666\">{}</text>\n",
+            text_x,
+            y + LABEL_HEIGHT - 3.0,
+            escape(&payload)
+        ));
+        return Ok(svg);
+    }
+
+    pub fn render_pages(etiquetas: &[Etiqueta]) -> Result<Vec<String>, BodegaError> {
+        //!Dibuja las etiquetas en hojas A4, y devuelve el SVG de cada hoja.
+        let top = (PAGE_HEIGHT - ROWS as f64 * LABEL_HEIGHT) / 2.0;
+        let left = (PAGE_WIDTH - COLUMNS as f64 * LABEL_WIDTH) / 2.0;
+        let mut pages = Vec::new();
+        for page in etiquetas.chunks(COLUMNS * ROWS) {
+            let mut svg = format!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}mm\" height=\"{h}mm\" viewBox=\"0 0 {w} {h}\" font-family=\"sans-serif\">\n",
+                w = PAGE_WIDTH,
+                h = PAGE_HEIGHT
+            );
+            for (i, etiqueta) in page.iter().enumerate() {
+                let x = left + (i % COLUMNS) as f64 * LABEL_WIDTH;
+                let y = top + (i / COLUMNS) as f64 * LABEL_HEIGHT;
+                svg.push_str(&draw_label(etiqueta, x, y)?);
+            }
+            svg.push_str("</svg>\n");
+            pages.push(svg);
+        }
+        return Ok(pages);
+    }
+
+    pub fn write_labels(path: &Path, etiquetas: &[Etiqueta]) -> Result<Vec<PathBuf>, BodegaError> {
+        //!Guarda las etiquetas en el fichero dado o, si ocupan varias hojas, en un fichero por hoja numerado a partir de su nombre. Devuelve los ficheros escritos.
+        if etiquetas.is_empty() {
+            return Err(BodegaError::Invalido(String::from(
+                "No hay ningún objeto del que hacer etiquetas",
+            )));
+        }
+        let pages = render_pages(etiquetas)?;
+        let mut written = Vec::new();
+        for (i, page) in pages.iter().enumerate() {
+            let file = match pages.len() {
+                1 => path.to_path_buf(),
+                _ => {
+                    let stem = path
+                        .file_stem()
+                        .map(|s| s.to_string_lossy().into_owned())
+                        .unwrap_or(String::from("etiquetas"));
+                    path.with_file_name(format!("{}-{}.svg", stem, i + 1))
+                }
+            };
+            std::fs::write(&file, page).map_err(|e| {
+                BodegaError::Invalido(format!("No se pudo escribir {}: {}", file.display(), e))
+            })?;
+            written.push(file);
+        }
+        return Ok(written);
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::db_manager::db_manager::Categoria;
+
+        fn objeto(id: i32) -> Objeto {
+            return Objeto {
+                id,
+                categoria: Categoria {
+                    id: 1,
+                    nombre: String::from("Despensa"),
+                    descripcion: String::new(),
+                    padre: None,
+                },
+                nombre: String::from("Arroz"),
+                medida: String::from("kg"),
+                envase: None,
+                precio: None,
+                etiquetas: Vec::new(),
+            };
+        }
+
+        #[test]
+        fn payload_round_trip() {
+            for lugar in [None, Some(Procedencia::Casa), Some(Procedencia::Tara)] {
+                let payload = label_payload(&objeto(42), &lugar);
+                assert_eq!(parse_label(&payload), Some((42, lugar)));
+            }
+        }
+
+        #[test]
+        fn payload_is_keyboard_safe() {
+            let payload = label_payload(&objeto(7), &Some(Procedencia::Tara));
+            assert!(payload
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == ' '));
+        }
+
+        #[test]
+        fn other_text_is_not_a_label() {
+            assert_eq!(parse_label("Arroz"), None);
+            assert_eq!(parse_label("BODEGA"), None);
+            assert_eq!(parse_label("BODEGA doce"), None);
+            assert_eq!(parse_label("BODEGA 12 SOTANO"), None);
+            assert_eq!(parse_label("  bodega   12 "), Some((12, None)));
+        }
+    }
+}
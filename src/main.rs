@@ -1,11 +1,76 @@
+#![allow(clippy::needless_return, clippy::module_inception)]
+
+mod auth;
+mod barcode;
+mod catalog;
+mod cli;
+mod config;
 mod connection_manager;
+mod consumption;
 mod db_manager;
+mod doctor;
+mod error;
+mod expiry;
+mod hogar;
+mod integrity;
+mod labels;
+mod places;
+mod planner;
+mod prices;
+mod recipes;
+mod selector;
+mod server;
+mod shops;
+mod stocktake;
+mod tags;
+mod tui;
+mod web;
 
+use auth::auth::*;
+use barcode::barcode::{add_code, choose_object, read_codes, remove_code, scan_interactive};
+use catalog::catalog::import_interactive;
+use clap::Parser;
 use clearscreen::clear;
-use connection_manager::connection_manager::connect;
+use cli::cli::{config_flags, run, Cli, Comando};
+use config::config::{describe, load, Configuracion};
+use connection_manager::connection_manager::{connect, get_conn};
+use consumption::consumption::{
+    days_until, print_consumption, read_consumption, running_out, DEFAULT_WINDOW,
+};
 use db_manager::db_manager::*;
+use doctor::doctor::diagnose;
+use error::error::BodegaError;
+use expiry::expiry::{
+    get_storage_name, parse_storage, print_shelf_life, print_use_first, read_shelf_life,
+    set_shelf_life, use_first,
+};
+use hogar::hogar::*;
+use integrity::integrity::{describe_problem, repair_interactive, scan};
+use labels::labels::{labels_for, parse_label, write_labels};
 use mysql::PooledConn;
+use places::places::{
+    delete_place, insert_place, move_between, object_places, parse_sitio, place_contents,
+    place_stock, print_object_places, print_place_contents, print_places, read_placed, read_places,
+    sitio_storage,
+};
+use planner::planner::{plan_stay, print_plan, read_rations, set_ration, stay_days};
+use prices::prices::{
+    monthly_spend, print_purchases, print_spend, print_valuation, read_purchases, valuation,
+    DEFAULT_MONTHS,
+};
+use recipes::recipes::{
+    availability, cook, delete_recipe, insert_recipe, print_availability, print_recipe,
+    read_recipes, set_ingredient,
+};
+use selector::selector::{ask, choose, read_input, Seleccionable};
+use server::server::serve;
+use shops::shops::{
+    delete_shop, export_shopping_list, insert_shop, link_supplier, object_suppliers,
+    print_shopping_list, read_shops, shopping_list, unlink_supplier, update_shop,
+};
 use std::io::{stdin, BufRead};
+use stocktake::stocktake::count_interactive;
+use tags::tags::{find_tag, print_tags, read_tags, tag_object, untag_object};
 
 ///Título del programa
 const TITLE: &str = "LA BODEGA ALBERO";
@@ -45,364 +110,1613 @@ macro_rules! print_header {
     };
 }
 
-fn menu(connection: &mut PooledConn, option: &mut String) {
-    //!Ejecuta el menú de selección de operaciones.
+fn menu(
+    connection: &mut PooledConn,
+    sesion: &mut Sesion,
+    option: &mut String,
+    config: &Configuracion,
+) -> Result<(), BodegaError> {
+    //!Ejecuta el menú de selección de operaciones en nombre del usuario que ha iniciado sesión, sobre su hogar activo. Devuelve los errores al leer de la base de datos; los de cada operación se muestran en el propio menú.
     print_title!();
+    println!("Hogar: {}\n", sesion.hogar.nombre);
     println!("1. Consultar existencias");
     println!("2. Añadir o retirar existencias");
     println!("3. Transladar existencias");
     println!("4. Editar categorías");
     println!("5. Editar objetos");
     println!("6. Sobre el programa");
+    println!("7. Usuarios");
+    println!("8. Hogares");
+    println!("9. Comprobar la integridad de los datos");
+    println!("10. Hacer inventario");
+    println!("11. Consumo y previsión");
+    println!("12. Planificar una estancia");
+    println!("13. Recetas");
+    println!("14. Códigos de barras");
+    println!("15. Importar un objeto de Open Food Facts");
+    println!("16. Imprimir etiquetas");
+    println!("17. Precios y valor de las existencias");
+    println!("18. Tiendas y lista de la compra");
+    println!("19. Ubicaciones: habitaciones, estanterías y congeladores");
+    println!("20. Caducidades: qué gastar primero");
     println!("\n¿Qué deseas hacer?");
-    *option = String::from("");
-    stdin().read_line(option);
-    match option.trim() {
-        "1" => {
-            *option = String::from("");
-            clear();
-            let list = read_objects(connection);
-            print_header!("EXISTENCIAS");
-            print_all_stock(connection, list, false);
+    *option = read_input();
+    match option.as_str() {
+        "1" => show_stock(connection, sesion)?,
+        "2" => change_stock(connection, sesion, option)?,
+        "3" => move_menu(connection, sesion, option)?,
+        "4" => categories_menu(connection, sesion, option)?,
+        "5" => objects_menu(connection, sesion, option)?,
+        "6" => about(),
+        "7" => users_menu(connection, sesion, option)?,
+        "8" => households_menu(connection, sesion, option)?,
+        "9" => integrity_menu(connection, sesion)?,
+        "10" => inventory_menu(connection, sesion)?,
+        "11" => consumption_menu(connection, sesion)?,
+        "12" => stay_menu(connection, sesion, option)?,
+        "13" => recipes_menu(connection, sesion, option)?,
+        "14" => barcodes_menu(connection, sesion)?,
+        "15" => import_menu(connection, sesion, config)?,
+        "16" => labels_menu(connection, sesion)?,
+        "17" => prices_menu(connection, sesion)?,
+        "18" => shops_menu(connection, sesion)?,
+        "19" => places_menu(connection, sesion)?,
+        "20" => expiry_menu(connection, sesion)?,
+        _ => {
+            println!("No ha seleccionado ninguna opción. Volviendo al menú");
         }
-        "2" => {
-            *option = String::from("");
-            clear();
-            print_objects(read_objects(connection));
-            let mut id = String::from("");
-            println!("\nInserta el ID del objeto que desea añadir o retirar:");
-            stdin().read_line(&mut id);
-            match id.trim().parse::<i32>() {
-                Ok(obj_id) => {
-                    let id = obj_id;
-                    match get_object_by_id(obj_id, read_objects(connection)) {
-                        Some(obj) => {
-                            clear();
-                            let mut mode = String::from("");
-                            print_all_stock(connection, vec![obj.clone()], false);
-                            println!(
-                                "\n1. SET: El número que introduzcas sobrescribirá la cantidad"
-                            );
-                            println!("2. ADD: El número que introduzcas se sumará, o se restará si es negativo");
-                            println!("Especifica el modo de inserción:");
-                            let mut set_mode: bool = false;
-                            stdin().read_line(&mut mode);
-                            match mode.trim() {
+    }
+    return Ok(());
+}
+
+fn show_stock(connection: &mut PooledConn, sesion: &Sesion) -> Result<(), BodegaError> {
+    //!Muestra las existencias de todos los objetos, filtradas por una etiqueta si se elige una.
+    let _ = clear();
+    let mut list = read_objects(connection, &sesion.hogar)?;
+    let tags = read_tags(connection, &sesion.hogar)?;
+    if !tags.is_empty() {
+        print_tags(&tags);
+        println!("\nEtiqueta por la que filtrar (nada para verlo todo):");
+        let input = read_input();
+        if !input.is_empty() {
+            list = with_tag(list, &input);
+        }
+        let _ = clear();
+    }
+    print_header!("EXISTENCIAS");
+    print_all_stock(connection, &sesion.hogar, list, false)?;
+    return Ok(());
+}
+
+fn change_stock(
+    connection: &mut PooledConn,
+    sesion: &Sesion,
+    option: &mut String,
+) -> Result<(), BodegaError> {
+    //!Añade o retira existencias de un objeto elegido por el usuario.
+    *option = String::from("");
+    let _ = clear();
+    let objs = read_objects(connection, &sesion.hogar)?;
+    print_objects(objs.clone());
+    println!("\nInserta el ID, el nombre o el código del objeto que desea añadir o retirar:");
+    let input = read_input();
+    let label_place = parse_label(&input).and_then(|(_, lugar)| lugar);
+    match choose_object(connection, &sesion.hogar, &input, &objs)? {
+        Some(obj) => {
+            let _ = clear();
+            print_all_stock(connection, &sesion.hogar, vec![obj.clone()], false)?;
+            println!("\n1. SET: El número que introduzcas sobrescribirá la cantidad");
+            println!("2. ADD: El número que introduzcas se sumará, o se restará si es negativo");
+            println!("Especifica el modo de inserción:");
+            let mut set_mode: bool = false;
+            match read_input().as_str() {
+                "1" => {
+                    set_mode = true;
+                }
+                "2" => {
+                    set_mode = false;
+                }
+                _ => {
+                    println!("Ningún modo fue seleccionado. Se asignará el modo ADD");
+                }
+            }
+            println!("Ingresa la cantidad a realizar la operación");
+            match parse_quantity(&read_input()) {
+                Ok(cantidad) => {
+                    let mut precio = None;
+                    if !set_mode && cantidad > 0.0 {
+                        println!("Precio pagado en total (nada si no quieres registrarlo)");
+                        match parse_price(&read_input()) {
+                            Ok(p) => {
+                                precio = p;
+                            }
+                            Err(e) => {
+                                println!("{}. No se registrará el precio", e);
+                            }
+                        }
+                    }
+                    let mut tipo = None;
+                    if set_mode || cantidad > 0.0 {
+                        println!("Conservación de lo que se añade: despensa, nevera o congelador (nada para la habitual del objeto)");
+                        let input = read_input();
+                        if !input.is_empty() {
+                            tipo = parse_storage(&input);
+                            if tipo.is_none() {
+                                println!("\"{}\" no es un tipo de conservación válido. Se usará la habitual del objeto", input);
+                            }
+                        }
+                    }
+                    let mut location = Procedencia::Casa;
+                    match label_place {
+                        Some(lugar) => {
+                            location = lugar;
+                        }
+                        None => {
+                            println!("1. Aplicar cambios en CASA");
+                            println!("2. Aplicar cambios en TARA");
+                            println!("\nSelecciona el lugar en el que hacer la operación");
+                            match read_input().as_str() {
                                 "1" => {
-                                    set_mode = true;
+                                    location = Procedencia::Casa;
                                 }
                                 "2" => {
-                                    set_mode = false;
+                                    location = Procedencia::Tara;
                                 }
                                 _ => {
-                                    println!(
-                                        "Ningún modo fue seleccionado. Se asignará el modo ADD"
-                                    );
+                                    println!("Ningún lugar fue seleccionado. Se asignará CASA");
                                 }
                             }
-                            let mut cantidad = String::from("");
-                            println!("Ingresa la cantidad a realizar la operación");
-                            stdin().read_line(&mut cantidad);
-                            match cantidad.trim().parse::<f32>() {
-                                Ok(cantidad) => {
-                                    let mut loc = String::from("");
-                                    println!("1. Aplicar cambios en CASA");
-                                    println!("2. Aplicar cambios en TARA");
-                                    println!("\nSelecciona el lugar en el que hacer la operación");
-                                    stdin().read_line(&mut loc);
-                                    let mut location = Procedencia::Casa;
-                                    match loc.trim() {
-                                        "1" => {
-                                            location = Procedencia::Casa;
-                                        }
-                                        "2" => {
-                                            location = Procedencia::Tara;
-                                        }
-                                        _ => {
-                                            println!(
-                                                "Ningún lugar fue seleccionado. Se asignará CASA"
-                                            );
-                                        }
-                                    }
-                                    println!("Se realizará una operación con la siguiente configuración:");
+                        }
+                    }
+                    println!("Se realizará una operación con la siguiente configuración:");
+                    println!(
+                        "OBJ:{}\nSET: {}\nCAN:{}\nLOC:{:?}",
+                        obj.nombre, set_mode, cantidad, location
+                    );
+                    if let Some(p) = precio {
+                        println!("PRE:{:.2} €", p);
+                    }
+                    println!("\nContinuar? (Pon S para aceptar, y cualquier cosa para cancelar)");
+                    *option = read_input();
+                    match option.as_str() {
+                        "S" | "s" => {
+                            let result = match precio {
+                                Some(p) => purchase_stock(
+                                    connection,
+                                    sesion,
+                                    obj.id,
+                                    cantidad,
+                                    p,
+                                    &location,
+                                    tipo.as_ref(),
+                                ),
+                                None => adjust_stock(
+                                    connection,
+                                    sesion,
+                                    obj.id,
+                                    set_mode,
+                                    cantidad,
+                                    &location,
+                                    tipo.as_ref(),
+                                ),
+                            };
+                            match result {
+                                Ok(()) => {
+                                    println!("La base de datos se actualizó satisfactoriamente");
+                                }
+                                Err(e) => {
                                     println!(
-                                        "OBJ:{}\nSET: {}\nCAN:{}\nLOC:{:?}",
-                                        obj.nombre, set_mode, cantidad, location
+                                        "Ocurrió un error al actualizar la base de datos: {}",
+                                        e
                                     );
-                                    println!("\nContinuar? (Pon S para aceptar, y cualquier cosa para cancelar)");
-                                    stdin().read_line(option);
-                                    match option.trim() {
-                                        "S" | "s" => {
-                                            match update_stock(
-                                                connection, id, set_mode, cantidad, &location,
-                                            ) {
-                                                Ok(()) => {
-                                                    println!("La base de datos se actualizó satisfactoriamente");
-                                                }
-                                                Err(e) => {
-                                                    println!("Ocurrió un error al actualizar la base de datos: {}", e);
-                                                }
-                                            }
-                                        }
-                                        _ => {
-                                            println!("Operación cancelada.");
-                                        }
-                                    }
+                                }
+                            }
+                        }
+                        _ => {
+                            println!("Operación cancelada.");
+                        }
+                    }
+                }
+                Err(e) => {
+                    println!("{}", e);
+                }
+            }
+        }
+        None => {
+            println!("Vuelve a intentarlo");
+        }
+    }
+    return Ok(());
+}
+
+fn move_menu(
+    connection: &mut PooledConn,
+    sesion: &Sesion,
+    option: &mut String,
+) -> Result<(), BodegaError> {
+    //!Translada existencias de un objeto de un lugar a otro.
+    *option = String::from("");
+    let objs = read_objects(connection, &sesion.hogar)?;
+    print_all_stock(connection, &sesion.hogar, objs.clone(), true)?;
+    println!("Introduce el ID, el nombre o el código del objeto a transladar");
+    match choose_object(connection, &sesion.hogar, &read_input(), &objs)? {
+        Some(obj) => {
+            println!("1. Transferir de Casa a Tara\n2. Transferir de Tara a Casa");
+            let mut procedence = Procedencia::Casa;
+            match read_input().as_str() {
+                "1" => {
+                    procedence = Procedencia::Casa;
+                }
+                "2" => {
+                    procedence = Procedencia::Tara;
+                }
+                _ => {
+                    println!("Opción inválida, usando por defecto: de Casa a Tara");
+                }
+            }
+            println!("Ingresa la cantidad a transladar: ");
+            match parse_quantity(&read_input()) {
+                Ok(quant) => {
+                    println!("¿Transladar {} {} de {}, {} -> {}? (Pon S para aceptar, cualquier otra cosa para cancelar)", quant, obj.medida, obj.nombre, get_string_name(&procedence), get_string_name(&contrary(&procedence)));
+                    *option = read_input();
+                    match option.as_str() {
+                        "S" | "s" => {
+                            match move_stock(connection, sesion, obj.id, quant, &procedence) {
+                                Ok(()) => {
+                                    println!("Operación realizada satisfactoriamente.");
                                 }
                                 Err(e) => {
-                                    println!("Hubo un error con la cantidad ingresada. ¿Ha dado un número? {}", e);
+                                    println!("Error al hacer la solicitud de translado en la base de datos: {}", e);
                                 }
                             }
                         }
-                        None => {
-                            println!(
-                                "No se encontró el id en la base de datos. Vuelve a intentarlo"
-                            );
-                        }
+                        _ => {}
                     }
                 }
                 Err(e) => {
-                    println!(
-                        "Ocurrió un error con el id proporcionado. ¿Ha dado un número? {}",
-                        e
-                    );
+                    println!("{}", e);
+                }
+            }
+        }
+        None => {
+            println!("Vuelve a intentarlo");
+        }
+    }
+    return Ok(());
+}
+
+fn categories_menu(
+    connection: &mut PooledConn,
+    sesion: &Sesion,
+    option: &mut String,
+) -> Result<(), BodegaError> {
+    //!Crea, borra o cambia las categorías del hogar activo.
+    *option = String::from("");
+    let _ = clear();
+    let categories = read_categories(connection, &sesion.hogar)?;
+    print_categories(categories.clone());
+    println!("Qué desea realizar?");
+    println!("1. Agregar una categoría");
+    println!("2. Eliminar una categoría");
+    println!("3. Cambiar la categoría de la que cuelga una categoría");
+    *option = read_input();
+    match option.as_str() {
+        "1" => {
+            *option = String::from("");
+            println!("Nombre de la nueva categoría: ");
+            let nombre = read_input();
+            println!("\nDescripción de la nueva categoría: ");
+            let desc = read_input();
+            println!(
+                "\nID o nombre de la categoría de la que cuelga (vacío para ponerla en la raíz): "
+            );
+            let input = read_input();
+            let padre = match input.is_empty() {
+                true => None,
+                false => match choose(&input, &categories) {
+                    Some(padre) => Some(padre.id),
+                    None => {
+                        println!("No se creó la categoría");
+                        return Ok(());
+                    }
+                },
+            };
+            match insert_category(connection, sesion, nombre, desc, padre) {
+                Ok(_) => {
+                    println!("La categoría se creó satisfactoriamente");
+                }
+                Err(e) => {
+                    println!("Ocurrió un error al crear la categoría: {}", e);
+                }
+            }
+        }
+        "2" => {
+            *option = String::from("");
+            match ask(
+                "Introduce el ID o el nombre de la categoría a eliminar: ",
+                &categories,
+            ) {
+                Some(cat) => match delete_category(connection, sesion, cat.id) {
+                    Ok(()) => {
+                        println!(
+                            "La categoría {} (id: {}) fue eliminada satisfactoriamente",
+                            cat.nombre, cat.id
+                        );
+                    }
+                    Err(e) => {
+                        println!("Ocurrió un error al eliminar la categoría: {}", e);
+                    }
+                },
+                None => {
+                    println!("No se eliminó ninguna categoría");
                 }
             }
         }
         "3" => {
             *option = String::from("");
-            let objs = read_objects(connection);
-            print_all_stock(connection, objs.clone(), true);
-            let mut id = String::new();
-            println!("Introduce el ID del objeto a transladar");
-            stdin().read_line(&mut id);
-            match id.trim().parse::<i32>() {
-                Ok(id) => match get_object_by_id(id, objs) {
-                    Some(obj) => {
-                        println!("1. Transferir de Casa a Tara\n2. Transferir de Tara a Casa");
-                        let mut proc = String::new();
-                        stdin().read_line(&mut proc);
-                        let mut procedence = Procedencia::Casa;
-                        match proc.trim() {
-                            "1" => {
-                                procedence = Procedencia::Casa;
-                            }
-                            "2" => {
-                                procedence = Procedencia::Tara;
+            match ask(
+                "Introduce el ID o el nombre de la categoría que quieres mover: ",
+                &categories,
+            ) {
+                Some(cat) => {
+                    println!("ID o nombre de la categoría de la que colgará (vacío para ponerla en la raíz): ");
+                    let input = read_input();
+                    let padre = match input.is_empty() {
+                        true => None,
+                        false => match choose(&input, &categories) {
+                            Some(padre) => Some(padre),
+                            None => {
+                                println!("No se cambió la categoría");
+                                return Ok(());
                             }
-                            _ => {
-                                println!("Opción inválida, usando por defecto: de Casa a Tara");
+                        },
+                    };
+                    match set_parent(connection, sesion, cat.id, padre.as_ref().map(|p| p.id)) {
+                        Ok(()) => match padre {
+                            Some(padre) => {
+                                println!("{} cuelga ahora de {}", cat.nombre, padre.nombre)
                             }
+                            None => println!("{} está ahora en la raíz", cat.nombre),
+                        },
+                        Err(e) => {
+                            println!("Ocurrió un error al cambiar la categoría: {}", e);
                         }
-                        println!("Ingresa la cantidad a transladar: ");
-                        let mut quant = String::new();
-                        stdin().read_line(&mut quant);
-                        match quant.trim().parse::<f32>() {
-                            Ok(quant) => {
-                                println!("¿Transladar {} {} de {}, {} -> {}? (Pon S para aceptar, cualquier otra cosa para cancelar)", quant, obj.medida, obj.nombre, get_string_name(&procedence), get_string_name(&contrary(&procedence)));
-                                stdin().read_line(option);
-                                match option.trim() {
-                                    "S" | "s" => {
-                                        match update_stock(
-                                            connection,
-                                            obj.id,
-                                            false,
-                                            -quant,
-                                            &procedence,
-                                        ) {
-                                            Ok(()) => {
-                                                match update_stock(
-                                                    connection,
-                                                    obj.id,
-                                                    false,
-                                                    quant,
-                                                    &contrary(&procedence),
-                                                ) {
-                                                    Ok(()) => {
-                                                        println!("Operación realizada satisfactoriamente.");
-                                                    }
-                                                    Err(e) => {
-                                                        println!("Error al hacer la solicitud de añadir en la base de datos: {}", e);
-                                                    }
-                                                }
-                                            }
-                                            Err(e) => {
-                                                println!("Error al hacer la solicitud de retirada en la base de datos: {}", e);
-                                            }
-                                        }
-                                    }
-                                    _ => {}
-                                }
-                            }
-                            Err(e) => {
-                                println!("Hubo un error con la cantidad proporcionada. ¿Has puesto un número? {}", e);
-                            }
+                    }
+                }
+                None => {
+                    println!("No se cambió ninguna categoría");
+                }
+            }
+        }
+        _ => {
+            println!("No ha seleccionado ninguna opción. Volviendo al menú");
+        }
+    }
+    return Ok(());
+}
+
+fn objects_menu(
+    connection: &mut PooledConn,
+    sesion: &Sesion,
+    option: &mut String,
+) -> Result<(), BodegaError> {
+    //!Crea, borra o cambia los objetos del hogar activo.
+    *option = String::from("");
+    let _ = clear();
+    let objs = read_objects(connection, &sesion.hogar)?;
+    print_objects(objs.clone());
+    println!("Qué desea realizar?");
+    println!("1. Agregar un objeto");
+    println!("2. Eliminar un objeto");
+    println!("3. Poner una etiqueta a un objeto");
+    println!("4. Quitar una etiqueta de un objeto");
+    println!("5. Ver los objetos con una etiqueta");
+    *option = read_input();
+    match option.as_str() {
+        "1" => {
+            let categories = read_categories(connection, &sesion.hogar)?;
+            print_categories(categories.clone());
+            match ask(
+                "Inserta el ID o el nombre de la categoría a la que pertenece: ",
+                &categories,
+            ) {
+                Some(cat) => {
+                    println!("Inserta el nombre del objeto: ");
+                    let nombre = read_input();
+                    println!("Inserta la unidad de medida del objeto: ");
+                    let medida = read_input();
+                    match insert_object(connection, sesion, cat, nombre, medida) {
+                        Ok(_) => {
+                            println!("El objeto se creó satisfactoriamente");
+                        }
+                        Err(e) => {
+                            println!("Ocurrió un error al crear el objeto: {}", e);
                         }
                     }
-                    None => {
-                        println!("La id proporcionada no corresponde a ningún objeto existente");
+                }
+                None => {
+                    println!("No se creó ningún objeto");
+                }
+            }
+        }
+        "2" => {
+            *option = String::from("");
+            println!("Introduce el ID, el nombre o el código del objeto a eliminar: ");
+            match choose_object(connection, &sesion.hogar, &read_input(), &objs)? {
+                Some(obj) => match delete_object(connection, sesion, obj.id) {
+                    Ok(()) => {
+                        println!("Objeto {} eliminado satisfactoriamente", obj.nombre);
+                    }
+                    Err(e) => {
+                        println!("Ocurrió un error al eliminar el objeto: {}", e);
                     }
                 },
-                Err(e) => {
-                    println!("Error con el id proporcionado. ¿Ha puesto un número? {}", e);
+                None => {
+                    println!("No se eliminó ningún objeto");
                 }
             }
         }
-        "4" => {
+        "3" => {
             *option = String::from("");
-            clear();
-            print_categories(read_categories(connection));
-            println!("Qué desea realizar?");
-            println!("1. Agregar una categoría");
-            println!("2. Eliminar una categoría");
-            stdin().read_line(option);
-            match option.trim() {
-                "1" => {
-                    *option = String::from("");
-                    let mut nombre = String::new();
-                    let mut desc = String::new();
-                    println!("Nombre de la nueva categoría: ");
-                    stdin().read_line(&mut nombre);
-                    println!("\nDescripción de la nueva categoría: ");
-                    stdin().read_line(&mut desc);
-                    match insert_category(
-                        connection,
-                        String::from(nombre.trim()),
-                        String::from(desc.trim()),
-                    ) {
+            println!("Introduce el ID, el nombre o el código del objeto: ");
+            match choose_object(connection, &sesion.hogar, &read_input(), &objs)? {
+                Some(obj) => {
+                    print_tags(&read_tags(connection, &sesion.hogar)?);
+                    println!("\nEtiqueta que quieres ponerle (puede ser una nueva): ");
+                    let tag = read_input();
+                    match tag_object(connection, sesion, &obj, &tag) {
                         Ok(()) => {
-                            println!("La categoría se creó satisfactoriamente");
+                            println!("{} tiene ahora la etiqueta {}", obj.nombre, tag);
                         }
                         Err(e) => {
-                            println!("Ocurrió un error al crear la categoría: {}", e);
+                            println!("Ocurrió un error al poner la etiqueta: {}", e);
                         }
                     }
                 }
-                "2" => {
-                    *option = String::from("");
-                    let mut inp = String::new();
-                    println!("Introduce el ID de la categoría a eliminar: ");
-
-                    stdin().read_line(&mut inp);
-                    match inp.trim().parse::<i32>() {
-                        Ok(id) => match delete_category(connection, id) {
-                            Ok(()) => {
-                                println!(
-                                    "La categoría con id: {}, fue eliminada satisfactoriamente",
-                                    id
-                                );
-                            }
-                            Err(e) => {
-                                println!("Ocurrió un error al eliminar la categoría: {}", e);
-                            }
-                        },
+                None => {
+                    println!("No se etiquetó ningún objeto");
+                }
+            }
+        }
+        "4" => {
+            *option = String::from("");
+            println!("Introduce el ID, el nombre o el código del objeto: ");
+            match choose_object(connection, &sesion.hogar, &read_input(), &objs)? {
+                Some(obj) if obj.etiquetas.is_empty() => {
+                    println!("{} no tiene ninguna etiqueta", obj.nombre);
+                }
+                Some(obj) => {
+                    println!("Etiquetas de {}: {}", obj.nombre, obj.etiquetas.join(", "));
+                    println!("\nEtiqueta que quieres quitarle: ");
+                    let tag = read_input();
+                    match untag_object(connection, sesion, &obj, &tag) {
+                        Ok(()) => {
+                            println!("Se quitó la etiqueta {} de {}", tag, obj.nombre);
+                        }
                         Err(e) => {
-                            println!("Ocurrió un error con los datos que ha proporcionado. ¿Ha puesto algo que no sea un número? {}", e);
+                            println!("Ocurrió un error al quitar la etiqueta: {}", e);
                         }
                     }
                 }
-                _ => {
-                    println!("No ha seleccionado ninguna opción. Volviendo al menú");
+                None => {
+                    println!("No se quitó ninguna etiqueta");
                 }
             }
         }
         "5" => {
             *option = String::from("");
-            clear();
-            print_objects(read_objects(connection));
-            println!("Qué desea realizar?");
-            println!("1. Agregar un objeto");
-            println!("2. Eliminar un objeto");
-            stdin().read_line(option);
-            match option.trim() {
-                "1" => {
-                    print_categories(read_categories(connection));
-                    let mut id_cat = String::new();
-                    println!("Inserta el ID de la categoría a la que pertenece: ");
-                    stdin().read_line(&mut id_cat);
-                    match id_cat.trim().parse::<i32>() {
-                        Ok(id) => match get_category_by_id(id, read_categories(connection)) {
-                            Some(cat) => {
-                                let mut nombre = String::new();
-                                println!("Inserta el nombre del objeto: ");
-                                stdin().read_line(&mut nombre);
-                                let mut medida = String::new();
-                                println!("Inserta la unidad de medida del objeto: ");
-                                stdin().read_line(&mut medida);
-                                nombre = String::from(nombre.trim());
-                                medida = String::from(medida.trim());
-                                match insert_object(connection, cat, nombre, medida) {
+            let tags = read_tags(connection, &sesion.hogar)?;
+            print_tags(&tags);
+            if !tags.is_empty() {
+                println!("\nEtiqueta de los objetos que quieres ver: ");
+                match find_tag(&tags, &read_input()) {
+                    Some(tag) => {
+                        println!("\nObjetos con la etiqueta {}:", tag.nombre);
+                        print_objects(with_tag(objs, &tag.nombre));
+                    }
+                    None => {
+                        println!("No existe esa etiqueta");
+                    }
+                }
+            }
+        }
+        _ => {
+            println!("No ha seleccionado ninguna opción. Volviendo al menú");
+        }
+    }
+    return Ok(());
+}
+
+fn about() {
+    //!Muestra información sobre el programa.
+    let _ = clear();
+    print_title!();
+    println!("Creada por Javier Albero para una necesidad personal y para aprender Rust y SQL.\nVer. alpha 1.0");
+}
+
+fn users_menu(
+    connection: &mut PooledConn,
+    sesion: &Sesion,
+    option: &mut String,
+) -> Result<(), BodegaError> {
+    //!Muestra los miembros del hogar activo y permite crear usuarios y cambiar sus roles.
+    *option = String::from("");
+    let _ = clear();
+    for u in read_users(connection, &sesion.hogar)? {
+        println!("({}) {}: {}", u.id, u.nombre, get_role_name(&u.rol));
+    }
+    println!(
+        "\nSesión iniciada como {} ({})",
+        sesion.usuario.nombre,
+        get_role_name(&sesion.usuario.rol)
+    );
+    println!("Qué desea realizar?");
+    println!("1. Agregar un usuario");
+    println!("2. Cambiar mi contraseña");
+    println!("3. Cambiar el rol de un usuario");
+    *option = read_input();
+    match option.as_str() {
+        "1" => match ask_credentials() {
+            Ok((nombre, password)) => match ask_role() {
+                Some(rol) => match insert_user(connection, sesion, nombre, &password, &rol) {
+                    Ok(()) => {
+                        println!(
+                            "El usuario se creó satisfactoriamente y es miembro de {}",
+                            sesion.hogar.nombre
+                        );
+                    }
+                    Err(e) => {
+                        println!("Ocurrió un error al crear el usuario: {}", e);
+                    }
+                },
+                None => {
+                    println!("No se eligió ningún rol. No se creó el usuario");
+                }
+            },
+            Err(e) => {
+                println!("Ocurrió un error al crear el usuario: {}", e);
+            }
+        },
+        "2" => {
+            let password = read_password("Nueva contraseña: ");
+            if read_password("Repite la contraseña: ") != password {
+                println!("Las contraseñas no coinciden");
+            } else {
+                match change_password(connection, &sesion.usuario, &password) {
+                    Ok(()) => {
+                        println!("La contraseña se cambió satisfactoriamente");
+                    }
+                    Err(e) => {
+                        println!("Ocurrió un error al cambiar la contraseña: {}", e);
+                    }
+                }
+            }
+        }
+        "3" => {
+            let users = read_users(connection, &sesion.hogar)?;
+            match ask("Introduce el ID o el nombre del usuario: ", &users) {
+                Some(target) => match ask_role() {
+                    Some(rol) => match set_role(connection, sesion, &target, &rol) {
+                        Ok(()) => {
+                            println!(
+                                "{} tiene ahora el rol {}",
+                                target.nombre,
+                                get_role_name(&rol)
+                            );
+                        }
+                        Err(e) => {
+                            println!("Ocurrió un error al cambiar el rol: {}", e);
+                        }
+                    },
+                    None => {
+                        println!("No se eligió ningún rol");
+                    }
+                },
+                None => {
+                    println!("No se cambió ningún rol");
+                }
+            }
+        }
+        _ => {
+            println!("No ha seleccionado ninguna opción. Volviendo al menú");
+        }
+    }
+    return Ok(());
+}
+
+fn households_menu(
+    connection: &mut PooledConn,
+    sesion: &mut Sesion,
+    option: &mut String,
+) -> Result<(), BodegaError> {
+    //!Muestra los hogares del usuario y permite cambiar de hogar activo, crear hogares y añadir miembros.
+    *option = String::from("");
+    let _ = clear();
+    let households = read_user_households(connection, &sesion.usuario)?;
+    for h in &households {
+        println!("({}) {}", h.id, h.nombre);
+    }
+    println!("\nHogar activo: {}", sesion.hogar.nombre);
+    print!("Miembros:");
+    for u in read_users(connection, &sesion.hogar)? {
+        print!(" {}", u.nombre);
+    }
+    println!("\n\nQué desea realizar?");
+    println!("1. Cambiar de hogar");
+    println!("2. Crear un hogar");
+    println!("3. Añadir un miembro al hogar activo");
+    println!("4. Quitar un miembro del hogar activo");
+    *option = read_input();
+    match option.as_str() {
+        "1" => match ask("Introduce el ID o el nombre del hogar: ", &households) {
+            Some(hogar) => {
+                *sesion = open_session(connection, sesion.usuario.clone(), hogar)?;
+                println!(
+                    "Ahora trabajas en {} como {}",
+                    sesion.hogar.nombre,
+                    get_role_name(&sesion.usuario.rol)
+                );
+            }
+            None => {
+                println!("No se cambió de hogar");
+            }
+        },
+        "2" => {
+            println!("Nombre del nuevo hogar: ");
+            match insert_household(connection, sesion, read_input()) {
+                Ok(hogar) => {
+                    println!(
+                        "El hogar {} se creó satisfactoriamente. Ahora trabajas en él",
+                        hogar.nombre
+                    );
+                    *sesion = open_session(connection, sesion.usuario.clone(), hogar)?;
+                }
+                Err(e) => {
+                    println!("Ocurrió un error al crear el hogar: {}", e);
+                }
+            }
+        }
+        "3" => {
+            let users = read_addable_users(connection, sesion)?;
+            match ask("Introduce el ID o el nombre del usuario: ", &users) {
+                Some(target) => match add_member(connection, sesion, &target) {
+                    Ok(()) => {
+                        println!(
+                            "{} es ahora miembro de {}",
+                            target.nombre, sesion.hogar.nombre
+                        );
+                    }
+                    Err(e) => {
+                        println!("Ocurrió un error al añadir el miembro: {}", e);
+                    }
+                },
+                None => {
+                    println!("No se añadió ningún miembro");
+                }
+            }
+        }
+        "4" => {
+            let members = read_users(connection, &sesion.hogar)?;
+            match ask("Introduce el ID o el nombre del miembro: ", &members) {
+                Some(target) => match remove_member(connection, sesion, &target) {
+                    Ok(()) => {
+                        println!(
+                            "{} ya no es miembro de {}",
+                            target.nombre, sesion.hogar.nombre
+                        );
+                    }
+                    Err(e) => {
+                        println!("Ocurrió un error al quitar el miembro: {}", e);
+                    }
+                },
+                None => {
+                    println!("No se quitó ningún miembro");
+                }
+            }
+        }
+        _ => {
+            println!("No ha seleccionado ninguna opción. Volviendo al menú");
+        }
+    }
+    return Ok(());
+}
+
+fn integrity_menu(connection: &mut PooledConn, sesion: &Sesion) -> Result<(), BodegaError> {
+    //!Busca problemas de integridad en el hogar activo y, si el usuario quiere, los repara.
+    let _ = clear();
+    print_header!("INTEGRIDAD");
+    let problems = scan(connection, &sesion.hogar)?;
+    if problems.is_empty() {
+        println!("No se encontró ningún problema");
+    } else {
+        for p in &problems {
+            println!("- {}", describe_problem(p));
+        }
+        println!("\n¿Quieres repararlos uno a uno? (s/n)");
+        if read_input().to_lowercase().starts_with('s') {
+            repair_interactive(connection, sesion, &problems);
+        }
+    }
+    return Ok(());
+}
+
+fn inventory_menu(connection: &mut PooledConn, sesion: &Sesion) -> Result<(), BodegaError> {
+    //!Hace inventario de un lugar, preguntando la cantidad real de cada objeto.
+    let _ = clear();
+    print_header!("INVENTARIO");
+    println!("1. Contar CASA");
+    println!("2. Contar TARA");
+    println!("\nSelecciona el lugar del que hacer inventario");
+    let location = match read_input().as_str() {
+        "1" => Some(Procedencia::Casa),
+        "2" => Some(Procedencia::Tara),
+        _ => None,
+    };
+    match location {
+        Some(location) => {
+            if let Err(e) = count_interactive(connection, sesion, &location) {
+                println!("Ocurrió un error al hacer inventario: {}", e);
+            }
+        }
+        None => {
+            println!("Ningún lugar fue seleccionado. Volviendo al menú");
+        }
+    }
+    return Ok(());
+}
+
+fn consumption_menu(connection: &mut PooledConn, sesion: &Sesion) -> Result<(), BodegaError> {
+    //!Muestra el consumo de cada objeto y lo que se va a agotar pronto.
+    let _ = clear();
+    print_header!("CONSUMO");
+    let consumos = read_consumption(connection, &sesion.hogar, DEFAULT_WINDOW)?;
+    println!("Consumo medio de los últimos {} días:\n", DEFAULT_WINDOW);
+    print_consumption(&consumos);
+    println!("\nEscribe una fecha (AAAA-MM-DD) para ver qué se agotará antes, o nada para volver:");
+    let date = read_input();
+    if !date.is_empty() {
+        match days_until(connection, &date) {
+            Ok(days) => {
+                println!("\nSe agotará antes del {}:\n", date);
+                print_consumption(&running_out(&consumos, days));
+            }
+            Err(e) => {
+                println!("{}", e);
+            }
+        }
+    }
+    return Ok(());
+}
+
+fn stay_menu(
+    connection: &mut PooledConn,
+    sesion: &Sesion,
+    option: &mut String,
+) -> Result<(), BodegaError> {
+    //!Planifica una estancia, mostrando lo que falta para ella, o fija lo que consume una persona al día de un objeto.
+    *option = String::from("");
+    let _ = clear();
+    print_header!("ESTANCIAS");
+    println!("1. Planificar una estancia");
+    println!("2. Fijar lo que consume una persona al día de un objeto");
+    println!("3. Ver lo que consume una persona al día");
+    println!("\nQué desea realizar?");
+    *option = read_input();
+    match option.as_str() {
+        "1" => {
+            println!("1. Estancia en CASA");
+            println!("2. Estancia en TARA");
+            let destino = match read_input().as_str() {
+                "1" => Procedencia::Casa,
+                _ => Procedencia::Tara,
+            };
+            println!("Fecha de llegada (AAAA-MM-DD): ");
+            let desde = read_input();
+            println!("Fecha de salida (AAAA-MM-DD): ");
+            let hasta = read_input();
+            println!("Número de personas: ");
+            match read_input().parse::<u32>() {
+                Ok(personas) => {
+                    match stay_days(connection, &desde, &hasta).and_then(|dias| {
+                        plan_stay(connection, &sesion.hogar, &destino, dias, personas)
+                    }) {
+                        Ok(plan) => {
+                            let _ = clear();
+                            print_plan(&plan);
+                        }
+                        Err(e) => {
+                            println!("No se pudo planificar la estancia: {}", e);
+                        }
+                    }
+                }
+                Err(_) => {
+                    println!("El número de personas no es válido");
+                }
+            }
+        }
+        "2" => {
+            let objs = read_objects(connection, &sesion.hogar)?;
+            print_objects(objs.clone());
+            println!("\nInserta el ID, el nombre o el código del objeto:");
+            match choose_object(connection, &sesion.hogar, &read_input(), &objs)? {
+                Some(obj) => {
+                    println!(
+                        "Cantidad que consume una persona al día, en {} (0 para quitarla): ",
+                        obj.medida
+                    );
+                    match parse_quantity(&read_input())
+                        .and_then(|quant| set_ration(connection, sesion, &obj, quant))
+                    {
+                        Ok(()) => {
+                            println!("Se guardó la ración de {}", obj.nombre);
+                        }
+                        Err(e) => {
+                            println!("Ocurrió un error al guardar la ración: {}", e);
+                        }
+                    }
+                }
+                None => {
+                    println!("No se cambió ninguna ración");
+                }
+            }
+        }
+        "3" => {
+            for r in read_rations(connection, &sesion.hogar)? {
+                println!(
+                    "{}: x{} {} por persona y día",
+                    r.objeto.nombre, r.por_persona_dia, r.objeto.medida
+                );
+            }
+        }
+        _ => {
+            println!("No ha seleccionado ninguna opción. Volviendo al menú");
+        }
+    }
+    return Ok(());
+}
+
+fn recipes_menu(
+    connection: &mut PooledConn,
+    sesion: &Sesion,
+    option: &mut String,
+) -> Result<(), BodegaError> {
+    //!Gestiona las recetas del hogar activo y permite cocinarlas.
+    *option = String::from("");
+    let _ = clear();
+    print_header!("RECETAS");
+    let recipes = read_recipes(connection, &sesion.hogar)?;
+    for r in &recipes {
+        print_recipe(r);
+    }
+    println!("\nQué desea realizar?");
+    println!("1. Ver qué se puede cocinar");
+    println!("2. Cocinar una receta");
+    println!("3. Crear una receta");
+    println!("4. Cambiar un ingrediente de una receta");
+    println!("5. Eliminar una receta");
+    *option = read_input();
+    match option.as_str() {
+        "1" | "2" => {
+            println!("1. En CASA");
+            println!("2. En TARA");
+            let location = match read_input().as_str() {
+                "2" => Procedencia::Tara,
+                _ => Procedencia::Casa,
+            };
+            if option == "1" {
+                print_availability(
+                    &availability(connection, &sesion.hogar, &location)?,
+                    &location,
+                );
+            } else {
+                match ask("Introduce el ID o el nombre de la receta: ", &recipes) {
+                    Some(receta) => {
+                        println!("Número de raciones: ");
+                        match read_input().parse::<u32>() {
+                            Ok(raciones) => {
+                                match cook(connection, sesion, &receta, raciones, &location) {
                                     Ok(()) => {
-                                        println!("El objeto se creó satisfactoriamente");
+                                        println!(
+                                            "Se cocinaron {} raciones de {}",
+                                            raciones, receta.nombre
+                                        );
                                     }
                                     Err(e) => {
-                                        println!("Ocurrió un error al crear el objeto: {}", e);
+                                        println!("No se pudo cocinar: {}", e);
                                     }
                                 }
                             }
-                            _ => {
-                                println!("Ocurrió un error al buscar la categoría. ¿Es posible que el id dado no corresponda a ninguna categoría?");
+                            Err(_) => {
+                                println!("El número de raciones no es válido");
                             }
-                        },
-                        Err(e) => {
-                            println!(
-                                "Ocurrió un error con el id proporcionado. ¿Ha dado un número? {}",
-                                e
-                            );
                         }
                     }
+                    None => {
+                        println!("No se cocinó nada");
+                    }
                 }
-                "2" => {
-                    *option = String::from("");
-                    let mut inp = String::new();
-                    println!("Introduce el ID del objeto a eliminar: ");
-                    stdin().read_line(&mut inp);
-                    match inp.trim().parse::<i32>() {
-                        Ok(id) => match delete_object(connection, id) {
+            }
+        }
+        "3" => {
+            println!("Nombre de la receta: ");
+            let name = read_input();
+            println!("Número de raciones para las que son las cantidades: ");
+            match read_input().parse::<u32>() {
+                Ok(raciones) => match insert_recipe(connection, sesion, name, raciones) {
+                    Ok(receta) => {
+                        println!(
+                            "La receta {} se creó satisfactoriamente. Añádele ingredientes con la opción 4",
+                            receta.nombre
+                        );
+                    }
+                    Err(e) => {
+                        println!("Ocurrió un error al crear la receta: {}", e);
+                    }
+                },
+                Err(_) => {
+                    println!("El número de raciones no es válido");
+                }
+            }
+        }
+        "4" => match ask("Introduce el ID o el nombre de la receta: ", &recipes) {
+            Some(receta) => {
+                let objs = read_objects(connection, &sesion.hogar)?;
+                print_objects(objs.clone());
+                println!("\nInserta el ID, el nombre o el código del ingrediente:");
+                match choose_object(connection, &sesion.hogar, &read_input(), &objs)? {
+                    Some(obj) => {
+                        println!(
+                            "Cantidad para {} raciones, en {} (0 para quitarlo): ",
+                            receta.raciones, obj.medida
+                        );
+                        match parse_quantity(&read_input()).and_then(|quant| {
+                            set_ingredient(connection, sesion, &receta, &obj, quant)
+                        }) {
                             Ok(()) => {
-                                println!("Objeto eliminado satisfactoriamente");
+                                println!("Se guardó el ingrediente");
                             }
                             Err(e) => {
-                                println!("Ocurrió un error al eliminar el objeto: {}", e);
+                                println!("Ocurrió un error al guardar el ingrediente: {}", e);
                             }
-                        },
+                        }
+                    }
+                    None => {
+                        println!("No se cambió ningún ingrediente");
+                    }
+                }
+            }
+            None => {
+                println!("No se cambió ninguna receta");
+            }
+        },
+        "5" => match ask("Introduce el ID o el nombre de la receta: ", &recipes) {
+            Some(receta) => match delete_recipe(connection, sesion, receta.id) {
+                Ok(()) => {
+                    println!("La receta {} se eliminó", receta.nombre);
+                }
+                Err(e) => {
+                    println!("Ocurrió un error al eliminar la receta: {}", e);
+                }
+            },
+            None => {
+                println!("No se eliminó ninguna receta");
+            }
+        },
+        _ => {
+            println!("No ha seleccionado ninguna opción. Volviendo al menú");
+        }
+    }
+    return Ok(());
+}
+
+fn barcodes_menu(connection: &mut PooledConn, sesion: &Sesion) -> Result<(), BodegaError> {
+    //!Gestiona los códigos de barras de los objetos y el escaneo rápido.
+    let _ = clear();
+    print_header!("CÓDIGOS DE BARRAS");
+    println!("1. Escaneo rápido: añadir una unidad por lectura");
+    println!("2. Escaneo rápido: retirar una unidad por lectura");
+    println!("3. Asociar un código a un objeto");
+    println!("4. Quitar un código");
+    println!("5. Ver los códigos de un objeto");
+    println!("\n¿Qué deseas hacer?");
+    let choice = read_input();
+    match choice.as_str() {
+        "1" | "2" => {
+            println!("1. Casa\n2. Tara");
+            let lugar = match read_input().as_str() {
+                "2" => Procedencia::Tara,
+                _ => Procedencia::Casa,
+            };
+            scan_interactive(connection, sesion, &lugar, choice == "2", false)?;
+        }
+        "3" => {
+            let objs = read_objects(connection, &sesion.hogar)?;
+            print_objects(objs.clone());
+            println!("\nInserta el ID, el nombre o el código del objeto:");
+            match choose_object(connection, &sesion.hogar, &read_input(), &objs)? {
+                Some(obj) => {
+                    println!("Escanea o escribe el código:");
+                    match add_code(connection, sesion, &obj, &read_input()) {
+                        Ok(()) => {
+                            println!("El código se asoció a {}", obj.nombre);
+                        }
                         Err(e) => {
-                            println!("Ocurrió un error con los datos que ha proporcionado. ¿Ha puesto algo que no sea un número? {}", e);
+                            println!("Ocurrió un error al asociar el código: {}", e);
                         }
                     }
                 }
-                _ => {
-                    println!("No ha seleccionado ninguna opción. Volviendo al menú");
+                None => {
+                    println!("No se asoció ningún código");
+                }
+            }
+        }
+        "4" => {
+            println!("Escanea o escribe el código a quitar:");
+            match remove_code(connection, sesion, &read_input()) {
+                Ok(obj) => {
+                    println!("El código se quitó de {}", obj.nombre);
+                }
+                Err(e) => {
+                    println!("Ocurrió un error al quitar el código: {}", e);
+                }
+            }
+        }
+        "5" => {
+            let objs = read_objects(connection, &sesion.hogar)?;
+            println!("Inserta el ID, el nombre o el código de barras del objeto:");
+            match choose_object(connection, &sesion.hogar, &read_input(), &objs)? {
+                Some(obj) => {
+                    let codes = read_codes(connection, &obj)?;
+                    match codes.is_empty() {
+                        true => println!("{} no tiene ningún código", obj.nombre),
+                        false => println!("{}: {}", obj.nombre, codes.join(", ")),
+                    }
+                }
+                None => {
+                    println!("No se encontró ningún objeto");
+                }
+            }
+        }
+        _ => {
+            println!("No ha seleccionado ninguna opción. Volviendo al menú");
+        }
+    }
+    return Ok(());
+}
+
+fn import_menu(
+    connection: &mut PooledConn,
+    sesion: &Sesion,
+    config: &Configuracion,
+) -> Result<(), BodegaError> {
+    //!Importa un objeto de un volcado de Open Food Facts.
+    let _ = clear();
+    print_header!("IMPORTAR DE OPEN FOOD FACTS");
+    let path = match &config.catalogo {
+        Some(path) => path.clone(),
+        None => {
+            println!("Ruta del volcado de Open Food Facts (CSV o JSONL, comprimido o no):");
+            std::path::PathBuf::from(read_input())
+        }
+    };
+    println!("Escanea o escribe el código de barras, o parte del nombre del producto:");
+    if let Err(e) = import_interactive(connection, sesion, &path, &read_input()) {
+        println!("Ocurrió un error al importar el producto: {}", e);
+    }
+    return Ok(());
+}
+
+fn labels_menu(connection: &mut PooledConn, sesion: &Sesion) -> Result<(), BodegaError> {
+    //!Genera las etiquetas con código QR de los objetos.
+    let _ = clear();
+    print_header!("ETIQUETAS");
+    let objs = read_objects(connection, &sesion.hogar)?;
+    print_objects(objs.clone());
+    println!("\nInserta el ID, el nombre o el código del objeto (nada para hacer las de todos):");
+    let input = read_input();
+    let selected = match input.is_empty() {
+        true => objs,
+        false => match choose_object(connection, &sesion.hogar, &input, &objs)? {
+            Some(obj) => vec![obj],
+            None => Vec::new(),
+        },
+    };
+    println!("1. Etiquetas para Casa\n2. Etiquetas para Tara\n3. Una de cada lugar\n4. Sin lugar");
+    let lugares = match read_input().as_str() {
+        "1" => vec![Procedencia::Casa],
+        "2" => vec![Procedencia::Tara],
+        "3" => Procedencia::all().to_vec(),
+        _ => Vec::new(),
+    };
+    println!("Fichero en el que guardarlas (nada para etiquetas.svg):");
+    let path = match read_input() {
+        p if p.is_empty() => std::path::PathBuf::from("etiquetas.svg"),
+        p => std::path::PathBuf::from(p),
+    };
+    match write_labels(&path, &labels_for(&selected, &lugares)) {
+        Ok(files) => {
+            for f in files {
+                println!("Se guardaron las etiquetas en {}", f.display());
+            }
+        }
+        Err(e) => {
+            println!("Ocurrió un error al hacer las etiquetas: {}", e);
+        }
+    }
+    return Ok(());
+}
+
+fn prices_menu(connection: &mut PooledConn, sesion: &Sesion) -> Result<(), BodegaError> {
+    //!Muestra los precios de los objetos y el valor de las existencias.
+    let _ = clear();
+    print_header!("PRECIOS");
+    println!("1. Valor de las existencias");
+    println!("2. Gasto mensual");
+    println!("3. Compras de un objeto");
+    println!("\n¿Qué deseas hacer?");
+    match read_input().as_str() {
+        "1" => print_valuation(&valuation(connection, &sesion.hogar)?),
+        "2" => {
+            println!("Gasto de los últimos {} meses:", DEFAULT_MONTHS);
+            print_spend(&monthly_spend(connection, &sesion.hogar, DEFAULT_MONTHS)?);
+        }
+        "3" => {
+            let objs = read_objects(connection, &sesion.hogar)?;
+            println!("Inserta el ID, el nombre o el código del objeto:");
+            match choose_object(connection, &sesion.hogar, &read_input(), &objs)? {
+                Some(obj) => {
+                    let compras = read_purchases(connection, &obj)?;
+                    print_purchases(&obj, &compras);
+                }
+                None => {
+                    println!("No se encontró ningún objeto");
+                }
+            }
+        }
+        _ => {
+            println!("No ha seleccionado ninguna opción. Volviendo al menú");
+        }
+    }
+    return Ok(());
+}
+
+fn shops_menu(connection: &mut PooledConn, sesion: &Sesion) -> Result<(), BodegaError> {
+    //!Gestiona las tiendas y muestra la lista de la compra.
+    let _ = clear();
+    print_header!("TIENDAS");
+    let shops = read_shops(connection, &sesion.hogar)?;
+    if shops.is_empty() {
+        println!("No hay ninguna tienda");
+    }
+    for t in &shops {
+        println!("{}", t.describir());
+    }
+    println!("\n1. Crear una tienda");
+    println!("2. Editar una tienda");
+    println!("3. Eliminar una tienda");
+    println!("4. Indicar dónde se compra un objeto");
+    println!("5. Quitar una tienda de un objeto");
+    println!("6. Lista de la compra");
+    println!("\n¿Qué deseas hacer?");
+    match read_input().as_str() {
+        "1" => {
+            println!("Nombre de la tienda:");
+            let name = read_input();
+            println!("Notas (dirección, horario...):");
+            match insert_shop(connection, sesion, &name, &read_input()) {
+                Ok(t) => {
+                    println!("Se creó la tienda {} (ID:{})", t.nombre, t.id);
+                }
+                Err(e) => {
+                    println!("Ocurrió un error al crear la tienda: {}", e);
+                }
+            }
+        }
+        "2" => match ask("Introduce el ID o el nombre de la tienda: ", &shops) {
+            Some(t) => {
+                println!("Nuevo nombre (nada para dejar \"{}\"):", t.nombre);
+                let name = match read_input() {
+                    n if n.is_empty() => t.nombre.clone(),
+                    n => n,
+                };
+                println!("Nuevas notas (nada para dejar las que tiene, \"-\" para borrarlas):");
+                let notes = match read_input() {
+                    n if n.is_empty() => t.notas.clone(),
+                    n if n == "-" => String::new(),
+                    n => n,
+                };
+                match update_shop(connection, sesion, &t, &name, &notes) {
+                    Ok(t) => {
+                        println!("Se guardó la tienda {}", t.nombre);
+                    }
+                    Err(e) => {
+                        println!("Ocurrió un error al editar la tienda: {}", e);
+                    }
+                }
+            }
+            None => {
+                println!("No se editó ninguna tienda");
+            }
+        },
+        "3" => match ask("Introduce el ID o el nombre de la tienda: ", &shops) {
+            Some(t) => match delete_shop(connection, sesion, t.id) {
+                Ok(()) => {
+                    println!("La tienda {} se eliminó", t.nombre);
+                }
+                Err(e) => {
+                    println!("Ocurrió un error al eliminar la tienda: {}", e);
+                }
+            },
+            None => {
+                println!("No se eliminó ninguna tienda");
+            }
+        },
+        choice @ ("4" | "5") => {
+            let objs = read_objects(connection, &sesion.hogar)?;
+            println!("Inserta el ID, el nombre o el código del objeto:");
+            let obj = match choose_object(connection, &sesion.hogar, &read_input(), &objs)? {
+                Some(obj) => obj,
+                None => return Ok(()),
+            };
+            let current = object_suppliers(connection, &sesion.hogar, &obj)?;
+            for p in &current {
+                println!(
+                    "{} se compra en {}{}",
+                    obj.nombre,
+                    p.tienda.nombre,
+                    if p.preferida { " (preferida)" } else { "" }
+                );
+            }
+            match ask("Introduce el ID o el nombre de la tienda: ", &shops) {
+                Some(t) if choice == "4" => {
+                    println!("¿Es la tienda preferida para este objeto? (s/n)");
+                    let preferida = read_input().to_lowercase().starts_with('s');
+                    match link_supplier(connection, sesion, &obj, &t, preferida) {
+                        Ok(()) => {
+                            println!("{} se compra en {}", obj.nombre, t.nombre);
+                        }
+                        Err(e) => {
+                            println!("Ocurrió un error al guardar la tienda: {}", e);
+                        }
+                    }
+                }
+                Some(t) => match unlink_supplier(connection, sesion, &obj, &t) {
+                    Ok(()) => {
+                        println!("{} ya no se compra en {}", obj.nombre, t.nombre);
+                    }
+                    Err(e) => {
+                        println!("Ocurrió un error al quitar la tienda: {}", e);
+                    }
+                },
+                None => {
+                    println!("No se cambió ninguna tienda");
                 }
             }
         }
         "6" => {
-            *option = String::from("");
-            clear();
-            print_title!();
-            println!("Creada por Javier Albero para una necesidad personal y para aprender Rust y SQL.\nVer. alpha 1.0");
+            let groups = shopping_list(connection, &sesion.hogar)?;
+            print_shopping_list(&groups);
+            if !groups.is_empty() {
+                println!("Fichero CSV en el que guardarla (nada para no guardarla):");
+                let path = read_input();
+                if !path.is_empty() {
+                    match export_shopping_list(std::path::Path::new(&path), &groups) {
+                        Ok(()) => {
+                            println!("Se guardó la lista en {}", path);
+                        }
+                        Err(e) => {
+                            println!("{}", e);
+                        }
+                    }
+                }
+            }
         }
         _ => {
             println!("No ha seleccionado ninguna opción. Volviendo al menú");
         }
     }
-    let _ = stdin().lock().lines().next();
+    return Ok(());
 }
 
-fn main() {
-    //!Se conecta a la base de datos, y ejecuta el menú para comenzar a realizar operaciones sobre ella.
-    println!("Conectando a la base de datos...");
-    match connect(connection_manager::connection_manager::get_envs()) {
-        Ok(c) => {
-            let mut connection = c;
-            let mut option = String::new();
-            loop {
-                clear();
-                menu(&mut connection, &mut option);
+fn places_menu(connection: &mut PooledConn, sesion: &Sesion) -> Result<(), BodegaError> {
+    //!Gestiona las ubicaciones y lo que hay en cada una.
+    let _ = clear();
+    print_header!("UBICACIONES");
+    let places = read_places(connection, &sesion.hogar)?;
+    print_places(connection, &sesion.hogar, &places)?;
+    println!("\n1. Crear una ubicación");
+    println!("2. Eliminar una ubicación");
+    println!("3. Ver qué hay en una ubicación");
+    println!("4. Ver dónde está un objeto");
+    println!("5. Añadir o retirar existencias de una ubicación");
+    println!("6. Mover existencias de un sitio a otro");
+    println!("\n¿Qué deseas hacer?");
+    match read_input().as_str() {
+        "1" => {
+            println!("Nombre de la ubicación:");
+            let name = read_input();
+            println!(
+                "¿Dónde está? Casa, tara o el ID o el nombre de la ubicación que la contiene:"
+            );
+            let dentro = match parse_sitio(&read_input(), &places) {
+                Some(dentro) => dentro,
+                None => {
+                    println!("No se creó ninguna ubicación");
+                    return Ok(());
+                }
+            };
+            println!(
+                "¿Cómo se conserva lo que se guarda en ella? Despensa, nevera o congelador (nada para {}):",
+                get_storage_name(&sitio_storage(&dentro))
+            );
+            let input = read_input();
+            let tipo = match input.is_empty() {
+                true => sitio_storage(&dentro),
+                false => match parse_storage(&input) {
+                    Some(tipo) => tipo,
+                    None => {
+                        println!("\"{}\" no es un tipo de conservación válido. No se creó ninguna ubicación", input);
+                        return Ok(());
+                    }
+                },
+            };
+            match insert_place(connection, sesion, &name, &dentro, &tipo) {
+                Ok(u) => {
+                    println!("Se creó la ubicación {} (ID:{})", u.ruta, u.id);
+                }
+                Err(e) => {
+                    println!("Ocurrió un error al crear la ubicación: {}", e);
+                }
+            }
+        }
+        "2" => match ask(
+            "Introduce el ID o el nombre de la ubicación a eliminar: ",
+            &places,
+        ) {
+            Some(u) => match delete_place(connection, sesion, u.id) {
+                Ok(()) => {
+                    println!(
+                        "Se eliminó {}. Lo que había en ella queda sin ubicar",
+                        u.ruta
+                    );
+                }
+                Err(e) => {
+                    println!("Ocurrió un error al eliminar la ubicación: {}", e);
+                }
+            },
+            None => {
+                println!("No se eliminó ninguna ubicación");
+            }
+        },
+        "3" => {
+            if let Some(u) = ask("Introduce el ID o el nombre de la ubicación: ", &places) {
+                let objs = read_objects(connection, &sesion.hogar)?;
+                let placed = read_placed(connection, &sesion.hogar)?;
+                print_place_contents(&u, &place_contents(&places, &placed, &objs, u.id));
+            }
+        }
+        "4" => {
+            let objs = read_objects(connection, &sesion.hogar)?;
+            println!("Introduce el ID, el nombre o el código del objeto: ");
+            if let Some(obj) = choose_object(connection, &sesion.hogar, &read_input(), &objs)? {
+                let repartos = object_places(connection, &sesion.hogar, &obj)?;
+                print_object_places(&obj, &repartos);
+            }
+        }
+        "5" => {
+            let objs = read_objects(connection, &sesion.hogar)?;
+            println!("Introduce el ID, el nombre o el código del objeto: ");
+            let obj = match choose_object(connection, &sesion.hogar, &read_input(), &objs)? {
+                Some(obj) => obj,
+                None => return Ok(()),
+            };
+            let u = match ask("Introduce el ID o el nombre de la ubicación: ", &places) {
+                Some(u) => u,
+                None => return Ok(()),
+            };
+            println!("Cantidad a añadir (negativa para retirar):");
+            match parse_quantity(&read_input())
+                .and_then(|quant| place_stock(connection, sesion, &obj, &u, quant))
+            {
+                Ok(()) => {
+                    print_object_places(&obj, &object_places(connection, &sesion.hogar, &obj)?);
+                }
+                Err(e) => {
+                    println!("Ocurrió un error al cambiar las existencias: {}", e);
+                }
+            }
+        }
+        "6" => {
+            let objs = read_objects(connection, &sesion.hogar)?;
+            println!("Introduce el ID, el nombre o el código del objeto: ");
+            let obj = match choose_object(connection, &sesion.hogar, &read_input(), &objs)? {
+                Some(obj) => obj,
+                None => return Ok(()),
+            };
+            print_object_places(&obj, &object_places(connection, &sesion.hogar, &obj)?);
+            println!("\nDesde dónde: casa o tara (lo que está sin ubicar), o el ID o el nombre de una ubicación:");
+            let desde = match parse_sitio(&read_input(), &places) {
+                Some(sitio) => sitio,
+                None => return Ok(()),
+            };
+            println!(
+                "Hasta dónde: casa o tara (sin ubicar), o el ID o el nombre de una ubicación:"
+            );
+            let hasta = match parse_sitio(&read_input(), &places) {
+                Some(sitio) => sitio,
+                None => return Ok(()),
+            };
+            println!("Cantidad a mover:");
+            match parse_quantity(&read_input())
+                .and_then(|quant| move_between(connection, sesion, &obj, quant, &desde, &hasta))
+            {
+                Ok(()) => {
+                    print_object_places(&obj, &object_places(connection, &sesion.hogar, &obj)?);
+                }
+                Err(e) => {
+                    println!("Ocurrió un error al mover las existencias: {}", e);
+                }
+            }
+        }
+        _ => {
+            println!("No ha seleccionado ninguna opción. Volviendo al menú");
+        }
+    }
+    return Ok(());
+}
+
+fn expiry_menu(connection: &mut PooledConn, sesion: &Sesion) -> Result<(), BodegaError> {
+    //!Muestra qué gastar primero y permite fijar cuánto dura cada objeto en despensa, nevera o congelador.
+    let _ = clear();
+    print_header!("CADUCIDADES");
+    print_use_first(&use_first(connection, &sesion.hogar, None)?);
+    println!("\n1. Ver solo lo que caduca pronto");
+    println!("2. Fijar cuánto dura un objeto en despensa, nevera o congelador");
+    println!("3. Ver cuánto dura un objeto");
+    println!("\n¿Qué deseas hacer?");
+    match read_input().as_str() {
+        "1" => {
+            println!("Número de días:");
+            match read_input().trim().parse::<i64>() {
+                Ok(dias) => {
+                    print_use_first(&use_first(connection, &sesion.hogar, Some(dias))?);
+                }
+                Err(_) => {
+                    println!("El número de días no es válido");
+                }
+            }
+        }
+        "2" => {
+            let objs = read_objects(connection, &sesion.hogar)?;
+            print_objects(objs.clone());
+            println!("\nInserta el ID, el nombre o el código del objeto:");
+            let obj = match choose_object(connection, &sesion.hogar, &read_input(), &objs)? {
+                Some(obj) => obj,
+                None => return Ok(()),
+            };
+            println!("Conservación: despensa, nevera o congelador:");
+            let input = read_input();
+            let tipo = match parse_storage(&input) {
+                Some(tipo) => tipo,
+                None => {
+                    println!("\"{}\" no es un tipo de conservación válido", input);
+                    return Ok(());
+                }
+            };
+            println!(
+                "Días que dura {} en {} (0 para quitarlo):",
+                obj.nombre,
+                get_storage_name(&tipo)
+            );
+            match read_input().trim().parse::<u32>() {
+                Ok(dias) => match set_shelf_life(connection, sesion, &obj, &tipo, dias) {
+                    Ok(()) => {
+                        print_shelf_life(&obj, &read_shelf_life(connection, &obj)?);
+                    }
+                    Err(e) => {
+                        println!("Ocurrió un error al guardar cuánto dura: {}", e);
+                    }
+                },
+                Err(_) => {
+                    println!("El número de días no es válido");
+                }
+            }
+        }
+        "3" => {
+            let objs = read_objects(connection, &sesion.hogar)?;
+            print_objects(objs.clone());
+            println!("\nInserta el ID, el nombre o el código del objeto:");
+            if let Some(obj) = choose_object(connection, &sesion.hogar, &read_input(), &objs)? {
+                print_shelf_life(&obj, &read_shelf_life(connection, &obj)?);
             }
         }
+        _ => {
+            println!("No ha seleccionado ninguna opción. Volviendo al menú");
+        }
+    }
+    return Ok(());
+}
+
+fn main() {
+    //!Lee la configuración, se conecta a la base de datos, inicia sesión y ejecuta el menú para comenzar a realizar operaciones sobre ella. Si se indica una orden en la línea de comandos, la ejecuta en su lugar. El servidor no inicia sesión, sino que autentica cada petición y elige su hogar. El diagnóstico se hace antes de todo lo demás, porque sirve precisamente cuando no se puede conectar.
+    let cli = Cli::parse();
+    if let Some(Comando::Doctor) = cli.comando {
+        let ok = diagnose(cli.config.clone(), cli.perfil.clone(), config_flags(&cli));
+        std::process::exit(if ok { 0 } else { 1 });
+    }
+    let config = match load(cli.config.clone(), cli.perfil.clone(), config_flags(&cli)) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Error en la configuración: {}", e);
+            std::process::exit(1);
+        }
+    };
+    println!("Conectando a la base de datos {}...", describe(&config));
+    match connect(&config) {
+        Ok(conexiones) => match cli.comando {
+            Some(Comando::Serve { direccion }) => {
+                serve(&conexiones, &direccion, cli.hogar.as_deref());
+            }
+            comando => {
+                let sesion = match get_conn(&conexiones) {
+                    Ok(mut connection) => {
+                        login_interactive(&mut connection, cli.usuario).and_then(|usuario| {
+                            let hogar = ask_household(&mut connection, &usuario, cli.hogar)?;
+                            match open_session(&mut connection, usuario, hogar) {
+                                Ok(sesion) => Some(sesion),
+                                Err(e) => {
+                                    eprintln!("{}", e);
+                                    None
+                                }
+                            }
+                        })
+                    }
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        None
+                    }
+                };
+                match sesion {
+                    Some(mut sesion) => match comando {
+                        Some(comando) => {
+                            if let Err(e) = run(&conexiones, &sesion, &config, comando) {
+                                eprintln!("Ocurrió un error: {}", e);
+                                std::process::exit(1);
+                            }
+                        }
+                        None => {
+                            let mut option = String::new();
+                            loop {
+                                let _ = clear();
+                                match get_conn(&conexiones) {
+                                    Ok(mut connection) => {
+                                        if let Err(e) =
+                                            menu(&mut connection, &mut sesion, &mut option, &config)
+                                        {
+                                            println!(
+                                                "Ocurrió un error con la base de datos: {}",
+                                                e
+                                            );
+                                        }
+                                    }
+                                    Err(e) => {
+                                        println!("{}\nPulsa Enter para volver a intentarlo", e);
+                                    }
+                                }
+                                let _ = stdin().lock().lines().next();
+                            }
+                        }
+                    },
+                    None => {
+                        eprintln!("No se pudo iniciar sesión");
+                        std::process::exit(1);
+                    }
+                }
+            }
+        },
         Err(e) => {
-            println!("Ocurrió un error al conectarse a la base de datos: {}", e)
+            eprintln!("Ocurrió un error al conectarse a la base de datos: {}", e);
+            std::process::exit(1);
         }
     }
 }
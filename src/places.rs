@@ -0,0 +1,714 @@
+///Módulo que gestiona las ubicaciones de cada lugar, como habitaciones, estanterías, baldas o congeladores, unas dentro de otras, y cuánto hay de cada objeto en cada una. Las tablas existencias_home y existencias_tara siguen guardando el total de cada lugar; lo que no está en ninguna ubicación queda sin ubicar.
+pub mod places {
+    use crate::auth::auth::{check_permission, Permiso};
+    use crate::db_manager::db_manager::{
+        contrary, get_quantity, get_string_name, get_table_name, parse_location, read_objects,
+        read_stock, record_movement, update_stock, Objeto, Procedencia, TipoMovimiento, EPSILON,
+    };
+    use crate::error::error::BodegaError;
+    use crate::expiry::expiry::{
+        add_lot, fit_lots, get_storage_name, move_lots, parse_storage, use_lots, Conservacion,
+    };
+    use crate::hogar::hogar::{Hogar, Sesion};
+    use crate::selector::selector::{choose, normalize};
+    use mysql::prelude::Queryable;
+    use mysql::{params, PooledConn, TxOpts};
+    use serde::Serialize;
+
+    ///Ubicación dentro de un lugar. Se guarda en la tabla ubicaciones.
+    #[derive(Clone, Serialize)]
+    pub struct Ubicacion {
+        pub id: i32,
+        pub nombre: String,
+        pub lugar: Procedencia,
+        ///Ubicación dentro de la que está, o None si está directamente en el lugar
+        pub padre: Option<i32>,
+        ///Nombre del lugar y de las ubicaciones que la contienen, como "Casa / Despensa / Balda de arriba"
+        pub ruta: String,
+        ///Cómo se conserva lo que se guarda en ella, de lo que depende cuándo caduca
+        pub tipo: Conservacion,
+    }
+
+    ///Sitio en el que pueden estar unas existencias: una ubicación o, sin ella, un lugar sin ubicación concreta.
+    #[derive(Clone, Serialize)]
+    pub struct Sitio {
+        pub lugar: Procedencia,
+        pub ubicacion: Option<Ubicacion>,
+    }
+
+    ///Cantidad de un objeto que hay en una ubicación. Se guarda en la tabla existencias_ubicaciones.
+    #[derive(Clone, Serialize)]
+    pub struct Colocada {
+        pub id_objeto: i32,
+        pub id_ubicacion: i32,
+        pub cantidad: f64,
+    }
+
+    ///Cantidad de un objeto que hay en un sitio.
+    #[derive(Clone, Serialize)]
+    pub struct Reparto {
+        pub sitio: Sitio,
+        pub cantidad: f64,
+    }
+
+    pub fn sitio_storage(sitio: &Sitio) -> Conservacion {
+        //!Devuelve cómo se conserva lo que está en un sitio: lo de su ubicación o, sin ella, la despensa.
+        return match &sitio.ubicacion {
+            Some(u) => u.tipo.clone(),
+            None => Conservacion::Despensa,
+        };
+    }
+
+    pub fn sitio_name(sitio: &Sitio) -> String {
+        //!Devuelve el nombre de un sitio: la ruta de su ubicación o el lugar seguido de "sin ubicar".
+        return match &sitio.ubicacion {
+            Some(u) => u.ruta.clone(),
+            None => format!("{} (sin ubicar)", get_string_name(&sitio.lugar)),
+        };
+    }
+
+    pub fn place_tree(places: &[Ubicacion]) -> Vec<(usize, Ubicacion)> {
+        //!Ordena las ubicaciones como un árbol, las de casa primero, cada una seguida de las que tiene dentro, y devuelve cada una con su profundidad: 0 para las que están directamente en el lugar.
+        fn add(
+            places: &[Ubicacion],
+            padre: Option<i32>,
+            lugar: &Procedencia,
+            depth: usize,
+            result: &mut Vec<(usize, Ubicacion)>,
+        ) {
+            let mut children: Vec<&Ubicacion> = places
+                .iter()
+                .filter(|u| u.padre == padre && u.lugar == *lugar)
+                .collect();
+            children.sort_by(|a, b| a.nombre.cmp(&b.nombre));
+            for u in children {
+                result.push((depth, u.clone()));
+                add(places, Some(u.id), lugar, depth + 1, result);
+            }
+        }
+        let mut result = Vec::new();
+        for lugar in Procedencia::all() {
+            add(places, None, &lugar, 0, &mut result);
+        }
+        return result;
+    }
+
+    pub fn read_places(
+        conn: &mut PooledConn,
+        hogar: &Hogar,
+    ) -> Result<Vec<Ubicacion>, BodegaError> {
+        //!Lee las ubicaciones del hogar dado, en el orden de place_tree() y con su ruta.
+        let rows: Vec<(i32, String, String, Option<i32>, String)> = conn.exec(
+            "SELECT id, nombre, lugar, padre, tipo FROM ubicaciones WHERE hogar = :hogar;",
+            params! {"hogar" => hogar.id},
+        )?;
+        let places: Vec<Ubicacion> = rows
+            .into_iter()
+            .filter_map(|(id, nombre, lugar, padre, tipo)| {
+                parse_location(&lugar).map(|lugar| Ubicacion {
+                    id,
+                    nombre,
+                    lugar,
+                    padre,
+                    ruta: String::new(),
+                    tipo: parse_storage(&tipo).unwrap_or(Conservacion::Despensa),
+                })
+            })
+            .collect();
+        let mut result: Vec<Ubicacion> = Vec::new();
+        for (_, mut u) in place_tree(&places) {
+            u.ruta = match result.iter().find(|p| Some(p.id) == u.padre) {
+                Some(p) => format!("{} / {}", p.ruta, u.nombre),
+                None => format!("{} / {}", get_string_name(&u.lugar), u.nombre),
+            };
+            result.push(u);
+        }
+        return Ok(result);
+    }
+
+    pub fn inside(places: &[Ubicacion], id: i32) -> Vec<i32> {
+        //!Devuelve el id de la ubicación dada y los de todas las que tiene dentro, directamente o no.
+        let mut result = vec![id];
+        let mut i = 0;
+        while i < result.len() {
+            for u in places {
+                if u.padre == Some(result[i]) && !result.contains(&u.id) {
+                    result.push(u.id);
+                }
+            }
+            i += 1;
+        }
+        return result;
+    }
+
+    pub fn read_placed(conn: &mut PooledConn, hogar: &Hogar) -> Result<Vec<Colocada>, BodegaError> {
+        //!Lee cuánto hay de cada objeto en cada ubicación del hogar dado.
+        return Ok(conn.exec_map(
+            "SELECT eu.id_objeto, eu.id_ubicacion, eu.cantidad FROM existencias_ubicaciones eu
+                JOIN ubicaciones u ON u.id = eu.id_ubicacion
+                WHERE u.hogar = :hogar AND eu.cantidad > 0;",
+            params! {"hogar" => hogar.id},
+            |(id_objeto, id_ubicacion, cantidad)| Colocada {
+                id_objeto,
+                id_ubicacion,
+                cantidad,
+            },
+        )?);
+    }
+
+    pub fn parse_sitio(text: &str, places: &[Ubicacion]) -> Option<Sitio> {
+        //!Resuelve lo que escribe el usuario como un sitio: "casa" o "tara" son el lugar sin ubicar, y cualquier otra cosa se busca entre las ubicaciones con choose().
+        if let Some(lugar) = parse_location(text) {
+            return Some(Sitio {
+                lugar,
+                ubicacion: None,
+            });
+        }
+        return choose(text, places).map(|u| Sitio {
+            lugar: u.lugar.clone(),
+            ubicacion: Some(u),
+        });
+    }
+
+    pub fn insert_place(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        name: &str,
+        dentro: &Sitio,
+        tipo: &Conservacion,
+    ) -> Result<Ubicacion, BodegaError> {
+        //!Crea una ubicación en el hogar activo, dentro de la ubicación del sitio dado o directamente en su lugar, con el tipo de conservación dado, si el usuario puede gestionar el catálogo. No puede haber dos ubicaciones con el mismo nombre en el mismo sitio.
+        check_permission(&sesion.usuario, &Permiso::GestionarCatalogo)?;
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(BodegaError::Invalido(String::from(
+                "El nombre no puede estar vacío",
+            )));
+        }
+        let places = read_places(conn, &sesion.hogar)?;
+        let padre = dentro.ubicacion.as_ref().map(|u| u.id);
+        if let Some(padre) = padre {
+            if !places.iter().any(|u| u.id == padre) {
+                return Err(BodegaError::NoEncontrado(format!(
+                    "No existe ninguna ubicación con id {} en {}",
+                    padre, sesion.hogar.nombre
+                )));
+            }
+        }
+        if places.iter().any(|u| {
+            u.padre == padre && u.lugar == dentro.lugar && normalize(&u.nombre) == normalize(name)
+        }) {
+            return Err(BodegaError::Invalido(format!(
+                "Ya existe una ubicación llamada {} en {}",
+                name,
+                sitio_name(dentro)
+            )));
+        }
+        conn.exec_drop(
+            "INSERT INTO ubicaciones (nombre, lugar, padre, hogar, tipo) VALUES (:nombre, :lugar, :padre, :hogar, :tipo);",
+            params! {
+                "nombre" => name,
+                "lugar" => get_string_name(&dentro.lugar),
+                "padre" => padre,
+                "hogar" => sesion.hogar.id,
+                "tipo" => get_storage_name(tipo),
+            },
+        )?;
+        let id = conn.last_insert_id() as i32;
+        return Ok(Ubicacion {
+            id,
+            nombre: String::from(name),
+            lugar: dentro.lugar.clone(),
+            padre,
+            ruta: match &dentro.ubicacion {
+                Some(u) => format!("{} / {}", u.ruta, name),
+                None => format!("{} / {}", get_string_name(&dentro.lugar), name),
+            },
+            tipo: tipo.clone(),
+        });
+    }
+
+    pub fn delete_place(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        id: i32,
+    ) -> Result<(), BodegaError> {
+        //!Elimina una ubicación del hogar activo, si el usuario puede gestionar el catálogo. Lo que había en ella queda sin ubicar en su lugar. No se puede eliminar una ubicación que tiene otras dentro.
+        check_permission(&sesion.usuario, &Permiso::GestionarCatalogo)?;
+        conn.exec_drop(
+            "DELETE FROM ubicaciones WHERE id = :id AND hogar = :hogar;",
+            params! {"id" => id, "hogar" => sesion.hogar.id},
+        )?;
+        if conn.affected_rows() == 0 {
+            return Err(BodegaError::NoEncontrado(format!(
+                "No existe ninguna ubicación con id {} en {}",
+                id, sesion.hogar.nombre
+            )));
+        }
+        return Ok(());
+    }
+
+    pub fn fit_places<Q: Queryable>(
+        conn: &mut Q,
+        id: i32,
+        location: &Procedencia,
+    ) -> Result<(), mysql::Error> {
+        //!Comprueba que lo que hay de un objeto en las ubicaciones de un lugar no pasa del total del lugar y, si pasa, lo retira de las ubicaciones empezando por la que tiene menos, que suele ser el envase empezado. La llama update_stock() cada vez que cambia un total.
+        let total: f64 = conn
+            .exec_first(
+                format!(
+                    "SELECT cantidad FROM {} WHERE id_objeto = :id;",
+                    get_table_name(location)
+                ),
+                params! {"id" => id},
+            )?
+            .unwrap_or(None::<f64>)
+            .unwrap_or(0.0);
+        let placed: Vec<(i32, f64)> = conn.exec(
+            "SELECT eu.id_ubicacion, eu.cantidad FROM existencias_ubicaciones eu
+                JOIN ubicaciones u ON u.id = eu.id_ubicacion
+                WHERE eu.id_objeto = :id AND u.lugar = :lugar
+                ORDER BY eu.cantidad, eu.id_ubicacion;",
+            params! {"id" => id, "lugar" => get_string_name(location)},
+        )?;
+        let mut excess = placed.iter().map(|(_, c)| c).sum::<f64>() - total.max(0.0);
+        for (ubicacion, cantidad) in placed {
+            if excess <= EPSILON {
+                break;
+            }
+            let taken = cantidad.min(excess);
+            conn.exec_drop(
+                "UPDATE existencias_ubicaciones SET cantidad = cantidad - :taken
+                    WHERE id_objeto = :id AND id_ubicacion = :ubicacion;",
+                params! {"taken" => taken, "id" => id, "ubicacion" => ubicacion},
+            )?;
+            excess -= taken;
+        }
+        return conn.exec_drop(
+            "DELETE FROM existencias_ubicaciones WHERE id_objeto = :id AND cantidad <= 0;",
+            params! {"id" => id},
+        );
+    }
+
+    fn update_placed<Q: Queryable>(
+        conn: &mut Q,
+        id: i32,
+        ubicacion: i32,
+        quant: f64,
+    ) -> Result<(), mysql::Error> {
+        //!Suma quant, positivo o negativo, a lo que hay de un objeto en una ubicación.
+        return conn.exec_drop(
+            "INSERT INTO existencias_ubicaciones (id_objeto, id_ubicacion, cantidad) VALUES (:id, :ubicacion, :quant)
+                ON DUPLICATE KEY UPDATE cantidad = cantidad + :quant;",
+            params! {"id" => id, "ubicacion" => ubicacion, "quant" => quant},
+        );
+    }
+
+    fn available<Q: Queryable>(
+        tx: &mut Q,
+        objeto: &Objeto,
+        sitio: &Sitio,
+    ) -> Result<f64, mysql::Error> {
+        //!Devuelve cuánto hay de un objeto en un sitio: en la ubicación, sin contar las que tiene dentro, o, sin ubicación, lo que hay en el lugar sin ubicar. Bloquea las filas que lee hasta el final de la transacción, para que otra petición no pueda gastar lo mismo a la vez.
+        let total: Option<Option<f64>> = tx.exec_first(
+            format!(
+                "SELECT cantidad FROM {} WHERE id_objeto = :id FOR UPDATE;",
+                get_table_name(&sitio.lugar)
+            ),
+            params! {"id" => objeto.id},
+        )?;
+        return Ok(match &sitio.ubicacion {
+            Some(u) => {
+                let placed: Option<Option<f64>> = tx.exec_first(
+                    "SELECT cantidad FROM existencias_ubicaciones
+                        WHERE id_objeto = :id AND id_ubicacion = :ubicacion FOR UPDATE;",
+                    params! {"id" => objeto.id, "ubicacion" => u.id},
+                )?;
+                placed.flatten().unwrap_or(0.0)
+            }
+            None => {
+                let placed: Option<Option<f64>> = tx.exec_first(
+                    "SELECT SUM(e.cantidad) FROM existencias_ubicaciones e
+                        JOIN ubicaciones u ON u.id = e.id_ubicacion
+                        WHERE e.id_objeto = :id AND u.lugar = :lugar FOR UPDATE;",
+                    params! {"id" => objeto.id, "lugar" => get_string_name(&sitio.lugar)},
+                )?;
+                total.flatten().unwrap_or(0.0) - placed.flatten().unwrap_or(0.0)
+            }
+        });
+    }
+
+    fn check_place(
+        places: &[Ubicacion],
+        sesion: &Sesion,
+        sitio: &Sitio,
+    ) -> Result<(), BodegaError> {
+        //!Comprueba que la ubicación del sitio, si tiene, es del hogar activo.
+        if let Some(u) = &sitio.ubicacion {
+            if !places.iter().any(|p| p.id == u.id) {
+                return Err(BodegaError::NoEncontrado(format!(
+                    "No existe ninguna ubicación con id {} en {}",
+                    u.id, sesion.hogar.nombre
+                )));
+            }
+        }
+        return Ok(());
+    }
+
+    pub fn place_stock(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        objeto: &Objeto,
+        ubicacion: &Ubicacion,
+        quant: f32,
+    ) -> Result<(), BodegaError> {
+        //!Añade existencias de un objeto directamente en una ubicación, o las retira de ella con una cantidad negativa. El total del lugar cambia igual, lo añadido se guarda en un lote con la caducidad que le da la conservación de la ubicación y el cambio se registra como un movimiento del usuario, todo en una única transacción. Retirar requiere el permiso de consumir; añadir, el de gestionar existencias.
+        match quant > 0.0 {
+            true => check_permission(&sesion.usuario, &Permiso::GestionarExistencias)?,
+            false => check_permission(&sesion.usuario, &Permiso::Consumir)?,
+        }
+        if !quant.is_finite() || quant == 0.0 {
+            return Err(BodegaError::Invalido(String::from(
+                "La cantidad debe ser un número distinto de 0",
+            )));
+        }
+        let sitio = Sitio {
+            lugar: ubicacion.lugar.clone(),
+            ubicacion: Some(ubicacion.clone()),
+        };
+        check_place(&read_places(conn, &sesion.hogar)?, sesion, &sitio)?;
+        if !read_objects(conn, &sesion.hogar)?
+            .iter()
+            .any(|o| o.id == objeto.id)
+        {
+            return Err(BodegaError::NoEncontrado(format!(
+                "No existe ningún objeto con id {} en {}",
+                objeto.id, sesion.hogar.nombre
+            )));
+        }
+        let mut tx = conn.start_transaction(TxOpts::default())?;
+        let current = available(&mut tx, objeto, &sitio)?;
+        if current + (quant as f64) < -EPSILON {
+            return Err(BodegaError::Invalido(format!(
+                "No hay suficientes existencias de {} en {}: hay x{} {}",
+                objeto.nombre, ubicacion.ruta, current, objeto.medida
+            )));
+        }
+        match quant > 0.0 {
+            true => add_lot(
+                &mut tx,
+                objeto.id,
+                quant as f64,
+                &ubicacion.lugar,
+                &ubicacion.tipo,
+            )?,
+            false => use_lots(
+                &mut tx,
+                objeto.id,
+                -quant as f64,
+                &ubicacion.lugar,
+                &ubicacion.tipo,
+            )?,
+        }
+        update_placed(&mut tx, objeto.id, ubicacion.id, quant as f64)?;
+        update_stock(&mut tx, objeto.id, false, quant, &ubicacion.lugar)?;
+        record_movement(
+            &mut tx,
+            objeto.id,
+            quant as f64,
+            &ubicacion.lugar,
+            &TipoMovimiento::Ajuste,
+            &sesion.usuario,
+        )?;
+        tx.commit()?;
+        return Ok(());
+    }
+
+    pub fn move_between(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        objeto: &Objeto,
+        quant: f32,
+        desde: &Sitio,
+        hasta: &Sitio,
+    ) -> Result<(), BodegaError> {
+        //!Mueve existencias de un objeto de un sitio a otro, en una única transacción. Si los sitios son de lugares distintos, es un translado: cambian los totales de los dos lugares y se registran la salida y la entrada como movimientos del usuario. Si la conservación del destino es otra, la caducidad de lo movido se vuelve a calcular con move_lots(). Requiere el permiso de gestionar existencias.
+        check_permission(&sesion.usuario, &Permiso::GestionarExistencias)?;
+        if quant.is_nan() || quant <= 0.0 {
+            return Err(BodegaError::Invalido(String::from(
+                "La cantidad a mover debe ser mayor que 0",
+            )));
+        }
+        let places = read_places(conn, &sesion.hogar)?;
+        check_place(&places, sesion, desde)?;
+        check_place(&places, sesion, hasta)?;
+        if desde.lugar == hasta.lugar
+            && desde.ubicacion.as_ref().map(|u| u.id) == hasta.ubicacion.as_ref().map(|u| u.id)
+        {
+            return Err(BodegaError::Invalido(String::from(
+                "El origen y el destino son el mismo sitio",
+            )));
+        }
+        if !read_objects(conn, &sesion.hogar)?
+            .iter()
+            .any(|o| o.id == objeto.id)
+        {
+            return Err(BodegaError::NoEncontrado(format!(
+                "No existe ningún objeto con id {} en {}",
+                objeto.id, sesion.hogar.nombre
+            )));
+        }
+        let mut tx = conn.start_transaction(TxOpts::default())?;
+        let current = available(&mut tx, objeto, desde)?;
+        if current - (quant as f64) < -EPSILON {
+            return Err(BodegaError::Invalido(format!(
+                "No hay suficientes existencias de {} en {}: hay x{} {}",
+                objeto.nombre,
+                sitio_name(desde),
+                current,
+                objeto.medida
+            )));
+        }
+        move_lots(
+            &mut tx,
+            objeto.id,
+            quant as f64,
+            (&desde.lugar, &sitio_storage(desde)),
+            (&hasta.lugar, &sitio_storage(hasta)),
+        )?;
+        if let Some(u) = &desde.ubicacion {
+            update_placed(&mut tx, objeto.id, u.id, -(quant as f64))?;
+        }
+        if let Some(u) = &hasta.ubicacion {
+            update_placed(&mut tx, objeto.id, u.id, quant as f64)?;
+        }
+        if desde.lugar != hasta.lugar {
+            update_stock(&mut tx, objeto.id, false, -quant, &desde.lugar)?;
+            update_stock(&mut tx, objeto.id, false, quant, &contrary(&desde.lugar))?;
+            record_movement(
+                &mut tx,
+                objeto.id,
+                -quant as f64,
+                &desde.lugar,
+                &TipoMovimiento::Translado,
+                &sesion.usuario,
+            )?;
+            record_movement(
+                &mut tx,
+                objeto.id,
+                quant as f64,
+                &hasta.lugar,
+                &TipoMovimiento::Translado,
+                &sesion.usuario,
+            )?;
+        } else {
+            fit_places(&mut tx, objeto.id, &desde.lugar)?;
+            fit_lots(&mut tx, objeto.id, &desde.lugar)?;
+        }
+        tx.commit()?;
+        return Ok(());
+    }
+
+    pub fn object_places(
+        conn: &mut PooledConn,
+        hogar: &Hogar,
+        objeto: &Objeto,
+    ) -> Result<Vec<Reparto>, BodegaError> {
+        //!Devuelve dónde está un objeto: para cada lugar con existencias, lo que hay en cada ubicación y, si queda algo, lo que está sin ubicar.
+        let places = read_places(conn, hogar)?;
+        let placed = read_placed(conn, hogar)?;
+        let stock = read_stock(conn, std::slice::from_ref(objeto))?;
+        let mut result = Vec::new();
+        for lugar in Procedencia::all() {
+            let mut in_places = 0.0;
+            for u in places.iter().filter(|u| u.lugar == lugar) {
+                if let Some(c) = placed
+                    .iter()
+                    .find(|c| c.id_objeto == objeto.id && c.id_ubicacion == u.id)
+                {
+                    in_places += c.cantidad;
+                    result.push(Reparto {
+                        sitio: Sitio {
+                            lugar: lugar.clone(),
+                            ubicacion: Some(u.clone()),
+                        },
+                        cantidad: c.cantidad,
+                    });
+                }
+            }
+            let rest = get_quantity(&stock, objeto.id, &lugar) - in_places;
+            if rest > EPSILON {
+                result.push(Reparto {
+                    sitio: Sitio {
+                        lugar,
+                        ubicacion: None,
+                    },
+                    cantidad: rest,
+                });
+            }
+        }
+        return Ok(result);
+    }
+
+    pub fn place_contents(
+        places: &[Ubicacion],
+        placed: &[Colocada],
+        objects: &[Objeto],
+        id: i32,
+    ) -> Vec<(Objeto, f64)> {
+        //!Devuelve lo que hay de cada objeto en una ubicación, sumando lo que hay en las que tiene dentro, ordenado por nombre.
+        let ids = inside(places, id);
+        let mut result: Vec<(Objeto, f64)> = objects
+            .iter()
+            .filter_map(|o| {
+                let quant: f64 = placed
+                    .iter()
+                    .filter(|c| c.id_objeto == o.id && ids.contains(&c.id_ubicacion))
+                    .map(|c| c.cantidad)
+                    .sum();
+                match quant > EPSILON {
+                    true => Some((o.clone(), quant)),
+                    false => None,
+                }
+            })
+            .collect();
+        result.sort_by(|a, b| a.0.nombre.cmp(&b.0.nombre));
+        return result;
+    }
+
+    pub fn print_places(
+        conn: &mut PooledConn,
+        hogar: &Hogar,
+        places: &[Ubicacion],
+    ) -> Result<(), BodegaError> {
+        //!Muestra el árbol de ubicaciones de cada lugar, con cuántos objetos hay en cada una contando las que tiene dentro, y después la cantidad total de cada objeto en el lugar.
+        let objects = read_objects(conn, hogar)?;
+        let stock = read_stock(conn, &objects)?;
+        let placed = read_placed(conn, hogar)?;
+        let tree = place_tree(places);
+        for lugar in Procedencia::all() {
+            let totals: Vec<(&Objeto, f64)> = objects
+                .iter()
+                .map(|o| (o, get_quantity(&stock, o.id, &lugar)))
+                .filter(|(_, quant)| *quant > EPSILON)
+                .collect();
+            println!(
+                "{} ({} objetos en total)",
+                get_string_name(&lugar),
+                totals.len()
+            );
+            for (depth, u) in tree.iter().filter(|(_, u)| u.lugar == lugar) {
+                println!(
+                    "{}({}) {} [{}]: {} objetos",
+                    "    ".repeat(depth + 1),
+                    u.id,
+                    u.nombre,
+                    get_storage_name(&u.tipo),
+                    place_contents(places, &placed, &objects, u.id).len()
+                );
+            }
+            if !totals.is_empty() {
+                println!("    En total:");
+            }
+            for (o, quant) in totals {
+                println!("        {}: x{} {}", o.nombre, quant, o.medida);
+            }
+        }
+        return Ok(());
+    }
+
+    pub fn print_object_places(objeto: &Objeto, repartos: &[Reparto]) {
+        //!Muestra dónde está un objeto, con el total de cada lugar y lo que hay en cada sitio.
+        if repartos.is_empty() {
+            println!("{}: Sin existencias", objeto.nombre);
+            return;
+        }
+        for lugar in Procedencia::all() {
+            let rows: Vec<&Reparto> = repartos.iter().filter(|r| r.sitio.lugar == lugar).collect();
+            if rows.is_empty() {
+                continue;
+            }
+            println!(
+                "{} en {}: x{} {}",
+                objeto.nombre,
+                get_string_name(&lugar),
+                rows.iter().map(|r| r.cantidad).sum::<f64>(),
+                objeto.medida
+            );
+            for r in rows {
+                println!(
+                    "    {}: x{} {}",
+                    sitio_name(&r.sitio),
+                    r.cantidad,
+                    objeto.medida
+                );
+            }
+        }
+    }
+
+    pub fn print_place_contents(ubicacion: &Ubicacion, contents: &[(Objeto, f64)]) {
+        //!Muestra lo que hay en una ubicación y en las que tiene dentro.
+        if contents.is_empty() {
+            println!("No hay nada en {}", ubicacion.ruta);
+            return;
+        }
+        println!("En {}:", ubicacion.ruta);
+        for (o, quant) in contents {
+            println!("    {}: x{} {}", o.nombre, quant, o.medida);
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn ubicacion(id: i32, nombre: &str, lugar: Procedencia, padre: Option<i32>) -> Ubicacion {
+            return Ubicacion {
+                id,
+                nombre: String::from(nombre),
+                lugar,
+                padre,
+                ruta: String::from(nombre),
+                tipo: Conservacion::Despensa,
+            };
+        }
+
+        fn places() -> Vec<Ubicacion> {
+            return vec![
+                ubicacion(1, "Trastero", Procedencia::Tara, None),
+                ubicacion(2, "Despensa", Procedencia::Casa, None),
+                ubicacion(3, "Balda de arriba", Procedencia::Casa, Some(2)),
+                ubicacion(4, "Caja", Procedencia::Casa, Some(3)),
+                ubicacion(5, "Cocina", Procedencia::Casa, None),
+            ];
+        }
+
+        #[test]
+        fn inside_includes_nested_places() {
+            let mut ids = inside(&places(), 2);
+            ids.sort();
+            assert_eq!(ids, vec![2, 3, 4]);
+            assert_eq!(inside(&places(), 5), vec![5]);
+        }
+
+        #[test]
+        fn inside_stops_at_a_cycle() {
+            let places = vec![
+                ubicacion(1, "A", Procedencia::Casa, Some(2)),
+                ubicacion(2, "B", Procedencia::Casa, Some(1)),
+            ];
+            let mut ids = inside(&places, 1);
+            ids.sort();
+            assert_eq!(ids, vec![1, 2]);
+        }
+
+        #[test]
+        fn tree_lists_home_first_and_nests_by_name() {
+            let tree: Vec<(usize, i32)> = place_tree(&places())
+                .into_iter()
+                .map(|(depth, u)| (depth, u.id))
+                .collect();
+            assert_eq!(tree, vec![(0, 5), (0, 2), (1, 3), (2, 4), (0, 1)]);
+        }
+    }
+}
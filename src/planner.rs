@@ -0,0 +1,216 @@
+///Módulo que planifica una estancia en un lugar: a partir de las fechas, el número de personas y lo que consume cada persona al día de cada objeto, calcula cuánto hará falta, cuánto hay ya en el destino, cuánto se puede llevar del otro lugar y cuánto hay que comprar.
+pub mod planner {
+    use crate::auth::auth::{check_permission, Permiso};
+    use crate::db_manager::db_manager::{
+        contrary, get_quantity, get_string_name, read_objects, read_stock, Objeto, Procedencia,
+        EPSILON,
+    };
+    use crate::error::error::BodegaError;
+    use crate::hogar::hogar::{Hogar, Sesion};
+    use mysql::prelude::Queryable;
+    use mysql::{params, PooledConn};
+    use serde::Serialize;
+
+    ///Cantidad de un objeto que consume una persona al día. Se guarda en la tabla raciones.
+    #[derive(Clone, Serialize)]
+    pub struct Racion {
+        pub objeto: Objeto,
+        pub por_persona_dia: f64,
+    }
+
+    ///Lo que hace falta de un objeto para una estancia y de dónde sale.
+    #[derive(Clone, Serialize)]
+    pub struct Necesidad {
+        pub objeto: Objeto,
+        ///Cantidad que se consumirá durante la estancia
+        pub necesario: f64,
+        ///Existencias que ya hay en el destino
+        pub en_destino: f64,
+        ///Cantidad que hay que llevar desde el otro lugar
+        pub llevar: f64,
+        ///Cantidad que falta aun llevando todo lo que hay en el otro lugar
+        pub comprar: f64,
+    }
+
+    ///Plan de una estancia.
+    #[derive(Serialize)]
+    pub struct Plan {
+        pub destino: Procedencia,
+        pub dias: i64,
+        pub personas: u32,
+        pub necesidades: Vec<Necesidad>,
+    }
+
+    pub fn read_rations(conn: &mut PooledConn, hogar: &Hogar) -> Result<Vec<Racion>, BodegaError> {
+        //!Lee lo que consume una persona al día de cada objeto del hogar que lo tiene indicado.
+        let objects = read_objects(conn, hogar)?;
+        let rows: Vec<(i32, f64)> =
+            conn.query("SELECT id_objeto, por_persona_dia FROM raciones;")?;
+        return Ok(rows
+            .into_iter()
+            .filter_map(|(id, por_persona_dia)| {
+                objects.iter().find(|o| o.id == id).map(|o| Racion {
+                    objeto: o.clone(),
+                    por_persona_dia,
+                })
+            })
+            .collect());
+    }
+
+    pub fn set_ration(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        objeto: &Objeto,
+        quant: f32,
+    ) -> Result<(), BodegaError> {
+        //!Fija lo que consume una persona al día de un objeto del hogar activo; con 0, deja de tenerse en cuenta en los planes. Requiere poder gestionar el catálogo.
+        check_permission(&sesion.usuario, &Permiso::GestionarCatalogo)?;
+        if !quant.is_finite() || quant < 0.0 {
+            return Err(BodegaError::Invalido(String::from(
+                "La cantidad por persona y día debe ser un número no negativo",
+            )));
+        }
+        if !read_objects(conn, &sesion.hogar)?
+            .iter()
+            .any(|o| o.id == objeto.id)
+        {
+            return Err(BodegaError::NoEncontrado(format!(
+                "No existe ningún objeto con id {} en {}",
+                objeto.id, sesion.hogar.nombre
+            )));
+        }
+        if (quant as f64) <= EPSILON {
+            conn.exec_drop(
+                "DELETE FROM raciones WHERE id_objeto = :id;",
+                params! {"id" => objeto.id},
+            )?;
+        } else {
+            conn.exec_drop(
+                "INSERT INTO raciones (id_objeto, por_persona_dia) VALUES (:id, :quant)
+                    ON DUPLICATE KEY UPDATE por_persona_dia = :quant;",
+                params! {"id" => objeto.id, "quant" => quant},
+            )?;
+        }
+        return Ok(());
+    }
+
+    pub fn stay_days(conn: &mut PooledConn, desde: &str, hasta: &str) -> Result<i64, BodegaError> {
+        //!Devuelve cuántos días dura una estancia entre dos fechas escritas como AAAA-MM-DD, contando el primero y el último.
+        let days: Option<Option<i64>> = conn.exec_first(
+            "SELECT DATEDIFF(STR_TO_DATE(:hasta, '%Y-%m-%d'), STR_TO_DATE(:desde, '%Y-%m-%d')) + 1;",
+            params! {"desde" => desde.trim(), "hasta" => hasta.trim()},
+        )?;
+        return match days.flatten() {
+            Some(days) if days >= 1 => Ok(days),
+            Some(_) => Err(BodegaError::Invalido(format!(
+                "La estancia termina ({}) antes de empezar ({})",
+                hasta.trim(),
+                desde.trim()
+            ))),
+            None => Err(BodegaError::Invalido(String::from(
+                "Alguna de las fechas no es válida. Escríbelas como AAAA-MM-DD",
+            ))),
+        };
+    }
+
+    pub fn plan_stay(
+        conn: &mut PooledConn,
+        hogar: &Hogar,
+        destino: &Procedencia,
+        dias: i64,
+        personas: u32,
+    ) -> Result<Plan, BodegaError> {
+        //!Calcula, para cada objeto con ración indicada, lo que hará falta en el destino durante los días dados para el número de personas dado. Lo que no haya en el destino se lleva del otro lugar mientras haya, y el resto se compra.
+        if personas == 0 {
+            return Err(BodegaError::Invalido(String::from(
+                "La estancia debe ser de al menos una persona",
+            )));
+        }
+        let rations = read_rations(conn, hogar)?;
+        let objects: Vec<Objeto> = rations.iter().map(|r| r.objeto.clone()).collect();
+        let stock = read_stock(conn, &objects)?;
+        let origin = contrary(destino);
+        let mut necesidades = Vec::new();
+        for r in rations {
+            let necesario = r.por_persona_dia * personas as f64 * dias as f64;
+            let en_destino = get_quantity(&stock, r.objeto.id, destino).max(0.0);
+            let falta = (necesario - en_destino).max(0.0);
+            let llevar = falta.min(get_quantity(&stock, r.objeto.id, &origin).max(0.0));
+            necesidades.push(Necesidad {
+                objeto: r.objeto,
+                necesario,
+                en_destino,
+                llevar,
+                comprar: falta - llevar,
+            });
+        }
+        necesidades.sort_by(|a, b| {
+            (&a.objeto.categoria.nombre, &a.objeto.nombre)
+                .cmp(&(&b.objeto.categoria.nombre, &b.objeto.nombre))
+        });
+        return Ok(Plan {
+            destino: destino.clone(),
+            dias,
+            personas,
+            necesidades,
+        });
+    }
+
+    pub fn print_plan(plan: &Plan) {
+        //!Muestra un plan de estancia como una lista de lo que hay que llevar y otra de lo que hay que comprar.
+        println!(
+            "Estancia de {} días para {} personas en {}",
+            plan.dias,
+            plan.personas,
+            get_string_name(&plan.destino)
+        );
+        if plan.necesidades.is_empty() {
+            println!("No hay ningún objeto con ración por persona y día, así que no se puede calcular nada");
+            return;
+        }
+        let bring: Vec<&Necesidad> = plan
+            .necesidades
+            .iter()
+            .filter(|n| n.llevar > EPSILON)
+            .collect();
+        let buy: Vec<&Necesidad> = plan
+            .necesidades
+            .iter()
+            .filter(|n| n.comprar > EPSILON)
+            .collect();
+        println!(
+            "\nLlevar desde {}:",
+            get_string_name(&contrary(&plan.destino))
+        );
+        if bring.is_empty() {
+            println!("  Nada");
+        }
+        for n in bring {
+            println!(
+                "  {}: x{:.2} {}",
+                n.objeto.nombre, n.llevar, n.objeto.medida
+            );
+        }
+        println!("\nComprar:");
+        if buy.is_empty() {
+            println!("  Nada");
+        }
+        for n in buy {
+            println!(
+                "  {}: x{:.2} {}",
+                n.objeto.nombre, n.comprar, n.objeto.medida
+            );
+        }
+        let covered = plan
+            .necesidades
+            .iter()
+            .filter(|n| n.necesario - n.en_destino <= EPSILON)
+            .count();
+        println!(
+            "\n{} de {} objetos ya tienen suficientes existencias en {}",
+            covered,
+            plan.necesidades.len(),
+            get_string_name(&plan.destino)
+        );
+    }
+}
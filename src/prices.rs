@@ -0,0 +1,213 @@
+///Módulo que consulta los precios de compra registrados en la tabla precios: el historial de cada objeto, el valor de las existencias de cada lugar y categoría según el último precio de cada objeto y lo gastado cada mes.
+pub mod prices {
+    use crate::db_manager::db_manager::{
+        category_path, get_quantity, get_string_name, parse_location, read_categories,
+        read_objects, read_stock, Objeto, Procedencia, EPSILON,
+    };
+    use crate::error::error::BodegaError;
+    use crate::hogar::hogar::Hogar;
+    use mysql::prelude::Queryable;
+    use mysql::{params, PooledConn};
+    use serde::Serialize;
+
+    ///Número de meses hacia atrás que se muestran por defecto en el gasto mensual
+    pub const DEFAULT_MONTHS: u32 = 12;
+
+    ///Compra registrada de un objeto.
+    #[derive(Clone, Serialize)]
+    pub struct Compra {
+        pub fecha: String,
+        pub lugar: Procedencia,
+        pub cantidad: f64,
+        ///Lo que se pagó en total
+        pub precio: f64,
+        ///Precio por unidad de medida
+        pub unitario: f64,
+        pub usuario: String,
+    }
+
+    ///Valor de las existencias de una categoría en un lugar.
+    #[derive(Clone, Serialize)]
+    pub struct Valor {
+        pub lugar: Procedencia,
+        ///Nombre de la categoría precedido de los de las categorías de las que cuelga
+        pub categoria: String,
+        pub valor: f64,
+        ///Objetos con existencias que no tienen ningún precio registrado, y que no se cuentan
+        pub sin_precio: Vec<String>,
+    }
+
+    ///Lo que se gastó en un mes.
+    #[derive(Clone, Serialize)]
+    pub struct Gasto {
+        ///Mes, como AAAA-MM
+        pub mes: String,
+        pub total: f64,
+        pub compras: i64,
+    }
+
+    pub fn read_purchases(
+        conn: &mut PooledConn,
+        objeto: &Objeto,
+    ) -> Result<Vec<Compra>, BodegaError> {
+        //!Lee las compras registradas de un objeto, de la más reciente a la más antigua.
+        let rows: Vec<(String, String, f64, f64, Option<String>)> = conn.exec(
+            "SELECT DATE_FORMAT(p.fecha, '%Y-%m-%d %H:%i'), p.lugar, p.cantidad, p.precio, u.nombre
+                FROM precios p LEFT JOIN usuarios u ON u.id = p.id_usuario
+                WHERE p.id_objeto = :id ORDER BY p.fecha DESC, p.id DESC;",
+            params! {"id" => objeto.id},
+        )?;
+        return Ok(rows
+            .into_iter()
+            .filter_map(|(fecha, lugar, cantidad, precio, usuario)| {
+                parse_location(&lugar).map(|lugar| Compra {
+                    fecha,
+                    lugar,
+                    cantidad,
+                    precio,
+                    unitario: precio / cantidad,
+                    usuario: usuario.unwrap_or_default(),
+                })
+            })
+            .collect());
+    }
+
+    pub fn valuation(conn: &mut PooledConn, hogar: &Hogar) -> Result<Vec<Valor>, BodegaError> {
+        //!Calcula el valor de las existencias del hogar en cada lugar y categoría, multiplicando las de cada objeto por el precio por unidad de su última compra. Los objetos con existencias y sin ningún precio se devuelven aparte en cada fila.
+        let objects = read_objects(conn, hogar)?;
+        let categories = read_categories(conn, hogar)?;
+        let stock = read_stock(conn, &objects)?;
+        let mut result: Vec<Valor> = Vec::new();
+        for lugar in Procedencia::all() {
+            for o in &objects {
+                let quant = get_quantity(&stock, o.id, &lugar);
+                if quant <= EPSILON {
+                    continue;
+                }
+                let categoria = category_path(&categories, o.categoria.id);
+                let index = match result
+                    .iter()
+                    .position(|v| v.lugar == lugar && v.categoria == categoria)
+                {
+                    Some(index) => index,
+                    None => {
+                        result.push(Valor {
+                            lugar: lugar.clone(),
+                            categoria,
+                            valor: 0.0,
+                            sin_precio: Vec::new(),
+                        });
+                        result.len() - 1
+                    }
+                };
+                match o.precio {
+                    Some(precio) => result[index].valor += quant * precio,
+                    None => result[index].sin_precio.push(o.nombre.clone()),
+                }
+            }
+        }
+        result.sort_by(|a, b| {
+            (get_string_name(&a.lugar), &a.categoria)
+                .cmp(&(get_string_name(&b.lugar), &b.categoria))
+        });
+        return Ok(result);
+    }
+
+    pub fn monthly_spend(
+        conn: &mut PooledConn,
+        hogar: &Hogar,
+        months: u32,
+    ) -> Result<Vec<Gasto>, BodegaError> {
+        //!Suma lo gastado en las compras de los objetos del hogar en cada uno de los últimos months meses, contando el actual. Los meses sin compras no aparecen.
+        if months == 0 {
+            return Err(BodegaError::Invalido(String::from(
+                "El número de meses debe ser mayor que 0",
+            )));
+        }
+        let rows: Vec<(String, f64, i64)> = conn.exec(
+            "SELECT DATE_FORMAT(p.fecha, '%Y-%m') AS mes, SUM(p.precio), COUNT(*)
+                FROM precios p
+                JOIN objetos o ON o.id = p.id_objeto
+                JOIN categorias c ON c.id = o.categoria
+                WHERE c.hogar = :hogar
+                    AND p.fecha >= DATE_FORMAT(CURDATE(), '%Y-%m-01') - INTERVAL :meses MONTH
+                GROUP BY mes ORDER BY mes;",
+            params! {"hogar" => hogar.id, "meses" => months - 1},
+        )?;
+        return Ok(rows
+            .into_iter()
+            .map(|(mes, total, compras)| Gasto {
+                mes,
+                total,
+                compras,
+            })
+            .collect());
+    }
+
+    pub fn print_purchases(objeto: &Objeto, compras: &[Compra]) {
+        //!Muestra el historial de compras de un objeto.
+        if compras.is_empty() {
+            println!("No hay ninguna compra registrada de {}", objeto.nombre);
+            return;
+        }
+        println!("Compras de {}:", objeto.nombre);
+        for c in compras {
+            println!(
+                "{}  x{} {} en {} por {:.2} € ({:.2} €/{}){}",
+                c.fecha,
+                c.cantidad,
+                objeto.medida,
+                get_string_name(&c.lugar),
+                c.precio,
+                c.unitario,
+                objeto.medida,
+                match c.usuario.is_empty() {
+                    true => String::new(),
+                    false => format!(", {}", c.usuario),
+                }
+            );
+        }
+    }
+
+    pub fn print_valuation(valores: &[Valor]) {
+        //!Muestra el valor de las existencias de cada lugar por categoría, con el total de cada lugar y el de todo el hogar.
+        if valores.is_empty() {
+            println!("No hay existencias");
+            return;
+        }
+        let mut total = 0.0;
+        for lugar in Procedencia::all() {
+            let rows: Vec<&Valor> = valores.iter().filter(|v| v.lugar == lugar).collect();
+            if rows.is_empty() {
+                continue;
+            }
+            println!("{}:", get_string_name(&lugar));
+            let mut subtotal = 0.0;
+            for v in rows {
+                println!("  {:<30} {:>10.2} €", v.categoria, v.valor);
+                if !v.sin_precio.is_empty() {
+                    println!("    Sin precio: {}", v.sin_precio.join(", "));
+                }
+                subtotal += v.valor;
+            }
+            println!("  {:<30} {:>10.2} €\n", "Total", subtotal);
+            total += subtotal;
+        }
+        println!("Valor total de las existencias: {:.2} €", total);
+    }
+
+    pub fn print_spend(gastos: &[Gasto]) {
+        //!Muestra lo gastado cada mes y el total.
+        if gastos.is_empty() {
+            println!("No hay compras registradas en ese periodo");
+            return;
+        }
+        for g in gastos {
+            println!("{}  {:>10.2} €  ({} compras)", g.mes, g.total, g.compras);
+        }
+        println!(
+            "Total: {:.2} €",
+            gastos.iter().map(|g| g.total).sum::<f64>()
+        );
+    }
+}
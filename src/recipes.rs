@@ -0,0 +1,343 @@
+///Módulo que gestiona las recetas: listas de objetos y cantidades que se gastan juntas al cocinar. Cocinar una receta comprueba que hay de todo en el lugar elegido y retira todos los ingredientes en una única transacción.
+pub mod recipes {
+    use crate::auth::auth::{check_permission, Permiso};
+    use crate::db_manager::db_manager::{
+        get_quantity, get_string_name, get_table_name, read_objects, read_stock, record_movement,
+        update_stock, Existencia, Objeto, Procedencia, TipoMovimiento, EPSILON,
+    };
+    use crate::error::error::BodegaError;
+    use crate::hogar::hogar::{Hogar, Sesion};
+    use mysql::prelude::Queryable;
+    use mysql::{params, PooledConn, TxOpts};
+    use serde::Serialize;
+
+    ///Cantidad de un objeto que lleva una receta.
+    #[derive(Clone, Serialize)]
+    pub struct Ingrediente {
+        pub objeto: Objeto,
+        pub cantidad: f64,
+    }
+
+    ///Estructura basada en la tabla recetas de la base de datos bodega-db, con sus ingredientes de la tabla ingredientes.
+    #[derive(Clone, Serialize)]
+    pub struct Receta {
+        pub id: i32,
+        pub nombre: String,
+        ///Número de raciones para las que están pensadas las cantidades de los ingredientes
+        pub raciones: u32,
+        pub ingredientes: Vec<Ingrediente>,
+    }
+
+    ///Cuántas raciones de una receta se pueden cocinar en un lugar, y qué ingredientes faltan para una.
+    #[derive(Serialize)]
+    pub struct Disponibilidad {
+        pub receta: Receta,
+        pub raciones_posibles: u32,
+        pub faltan: Vec<Ingrediente>,
+    }
+
+    pub fn read_recipes(conn: &mut PooledConn, hogar: &Hogar) -> Result<Vec<Receta>, BodegaError> {
+        //!Lee las recetas del hogar dado con sus ingredientes, ordenadas por nombre.
+        let objects = read_objects(conn, hogar)?;
+        let mut recipes: Vec<Receta> = conn.exec_map(
+            "SELECT id, nombre, raciones FROM recetas WHERE hogar = :hogar ORDER BY nombre;",
+            params! {"hogar" => hogar.id},
+            |(id, nombre, raciones)| Receta {
+                id,
+                nombre,
+                raciones,
+                ingredientes: Vec::new(),
+            },
+        )?;
+        let rows: Vec<(i32, i32, f64)> = conn.exec(
+            "SELECT i.id_receta, i.id_objeto, i.cantidad FROM ingredientes i
+                    JOIN recetas r ON r.id = i.id_receta WHERE r.hogar = :hogar;",
+            params! {"hogar" => hogar.id},
+        )?;
+        for (id_receta, id_objeto, cantidad) in rows {
+            let objeto = objects.iter().find(|o| o.id == id_objeto);
+            let receta = recipes.iter_mut().find(|r| r.id == id_receta);
+            if let (Some(objeto), Some(receta)) = (objeto, receta) {
+                receta.ingredientes.push(Ingrediente {
+                    objeto: objeto.clone(),
+                    cantidad,
+                });
+            }
+        }
+        return Ok(recipes);
+    }
+
+    pub fn insert_recipe(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        name: String,
+        raciones: u32,
+    ) -> Result<Receta, BodegaError> {
+        //!Crea una receta sin ingredientes en el hogar activo, si el usuario puede gestionar el catálogo. Comprueba que el nombre no está vacío ni repetido en el hogar.
+        check_permission(&sesion.usuario, &Permiso::GestionarCatalogo)?;
+        let name = String::from(name.trim());
+        if name.is_empty() {
+            return Err(BodegaError::Invalido(String::from(
+                "El nombre no puede estar vacío",
+            )));
+        }
+        if raciones == 0 {
+            return Err(BodegaError::Invalido(String::from(
+                "La receta debe ser de al menos una ración",
+            )));
+        }
+        if read_recipes(conn, &sesion.hogar)?
+            .iter()
+            .any(|r| r.nombre == name)
+        {
+            return Err(BodegaError::Invalido(format!(
+                "Ya existe una receta llamada {}",
+                name
+            )));
+        }
+        conn.exec_drop(
+            "INSERT INTO recetas (nombre, raciones, hogar) VALUES (:nombre, :raciones, :hogar);",
+            params! {"nombre" => &name, "raciones" => raciones, "hogar" => sesion.hogar.id},
+        )?;
+        return Ok(Receta {
+            id: conn.last_insert_id() as i32,
+            nombre: name,
+            raciones,
+            ingredientes: Vec::new(),
+        });
+    }
+
+    pub fn delete_recipe(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        id: i32,
+    ) -> Result<(), BodegaError> {
+        //!Elimina una receta del hogar activo y sus ingredientes, si el usuario puede gestionar el catálogo.
+        check_permission(&sesion.usuario, &Permiso::GestionarCatalogo)?;
+        conn.exec_drop(
+            "DELETE FROM recetas WHERE id = :id AND hogar = :hogar;",
+            params! {"id" => id, "hogar" => sesion.hogar.id},
+        )?;
+        if conn.affected_rows() == 0 {
+            return Err(BodegaError::NoEncontrado(format!(
+                "No existe ninguna receta con id {} en {}",
+                id, sesion.hogar.nombre
+            )));
+        }
+        return Ok(());
+    }
+
+    pub fn set_ingredient(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        receta: &Receta,
+        objeto: &Objeto,
+        quant: f32,
+    ) -> Result<(), BodegaError> {
+        //!Fija la cantidad de un objeto que lleva una receta del hogar activo; con 0, lo quita de la receta. Requiere poder gestionar el catálogo.
+        check_permission(&sesion.usuario, &Permiso::GestionarCatalogo)?;
+        if !quant.is_finite() || quant < 0.0 {
+            return Err(BodegaError::Invalido(String::from(
+                "La cantidad de un ingrediente debe ser un número no negativo",
+            )));
+        }
+        if !read_recipes(conn, &sesion.hogar)?
+            .iter()
+            .any(|r| r.id == receta.id)
+            || !read_objects(conn, &sesion.hogar)?
+                .iter()
+                .any(|o| o.id == objeto.id)
+        {
+            return Err(BodegaError::NoEncontrado(format!(
+                "La receta o el objeto no son de {}",
+                sesion.hogar.nombre
+            )));
+        }
+        if (quant as f64) <= EPSILON {
+            conn.exec_drop(
+                "DELETE FROM ingredientes WHERE id_receta = :receta AND id_objeto = :objeto;",
+                params! {"receta" => receta.id, "objeto" => objeto.id},
+            )?;
+        } else {
+            conn.exec_drop(
+                "INSERT INTO ingredientes (id_receta, id_objeto, cantidad) VALUES (:receta, :objeto, :quant)
+                    ON DUPLICATE KEY UPDATE cantidad = :quant;",
+                params! {"receta" => receta.id, "objeto" => objeto.id, "quant" => quant},
+            )?;
+        }
+        return Ok(());
+    }
+
+    fn scaled(receta: &Receta, raciones: u32) -> Vec<Ingrediente> {
+        //!Devuelve los ingredientes de la receta con las cantidades necesarias para el número de raciones dado.
+        return receta
+            .ingredientes
+            .iter()
+            .map(|i| Ingrediente {
+                objeto: i.objeto.clone(),
+                cantidad: i.cantidad * raciones as f64 / receta.raciones as f64,
+            })
+            .collect();
+    }
+
+    fn missing(
+        receta: &Receta,
+        raciones: u32,
+        stock: &[Existencia],
+        lugar: &Procedencia,
+    ) -> Vec<Ingrediente> {
+        //!Devuelve lo que falta de cada ingrediente para cocinar las raciones dadas con las existencias de lugar.
+        return scaled(receta, raciones)
+            .into_iter()
+            .filter_map(|i| {
+                let falta = i.cantidad - get_quantity(stock, i.objeto.id, lugar);
+                match falta > EPSILON {
+                    true => Some(Ingrediente {
+                        objeto: i.objeto,
+                        cantidad: falta,
+                    }),
+                    false => None,
+                }
+            })
+            .collect();
+    }
+
+    pub fn availability(
+        conn: &mut PooledConn,
+        hogar: &Hogar,
+        lugar: &Procedencia,
+    ) -> Result<Vec<Disponibilidad>, BodegaError> {
+        //!Calcula, para cada receta del hogar con ingredientes, cuántas raciones se pueden cocinar con las existencias de lugar y qué falta para cocinar una. Las que se pueden cocinar van primero.
+        let recipes = read_recipes(conn, hogar)?;
+        let objects = read_objects(conn, hogar)?;
+        let stock = read_stock(conn, &objects)?;
+        let mut result: Vec<Disponibilidad> = recipes
+            .into_iter()
+            .filter(|r| !r.ingredientes.is_empty())
+            .map(|receta| {
+                let raciones_posibles = scaled(&receta, 1)
+                    .iter()
+                    .map(|i| {
+                        let available = get_quantity(&stock, i.objeto.id, lugar).max(0.0);
+                        ((available + EPSILON) / i.cantidad).floor() as u32
+                    })
+                    .min()
+                    .unwrap_or(0);
+                Disponibilidad {
+                    faltan: missing(&receta, 1, &stock, lugar),
+                    receta,
+                    raciones_posibles,
+                }
+            })
+            .collect();
+        result.sort_by_key(|d| std::cmp::Reverse(d.raciones_posibles));
+        return Ok(result);
+    }
+
+    pub fn cook(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        receta: &Receta,
+        raciones: u32,
+        lugar: &Procedencia,
+    ) -> Result<(), BodegaError> {
+        //!Cocina las raciones dadas de una receta: comprueba que hay suficiente de cada ingrediente en lugar y los retira todos, registrándolos como movimientos de receta, en una única transacción. Si falta algo no se retira nada. Requiere el permiso de consumir.
+        check_permission(&sesion.usuario, &Permiso::Consumir)?;
+        if raciones == 0 {
+            return Err(BodegaError::Invalido(String::from(
+                "Hay que cocinar al menos una ración",
+            )));
+        }
+        if receta.ingredientes.is_empty() {
+            return Err(BodegaError::Invalido(format!(
+                "La receta {} no tiene ingredientes",
+                receta.nombre
+            )));
+        }
+        let mut tx = conn.start_transaction(TxOpts::default())?;
+        let needed = scaled(receta, raciones);
+        let mut faltan = Vec::new();
+        for i in &needed {
+            let current: Option<Option<f64>> = tx.exec_first(
+                format!(
+                    "SELECT cantidad FROM {} WHERE id_objeto = :id FOR UPDATE;",
+                    get_table_name(lugar)
+                ),
+                params! {"id" => i.objeto.id},
+            )?;
+            let current = current.flatten().unwrap_or(0.0);
+            if i.cantidad - current > EPSILON {
+                faltan.push(format!(
+                    "{} (hay x{} {}, hacen falta x{:.2})",
+                    i.objeto.nombre, current, i.objeto.medida, i.cantidad
+                ));
+            }
+        }
+        if !faltan.is_empty() {
+            return Err(BodegaError::Invalido(format!(
+                "No hay suficiente en {} para {} raciones de {}: {}",
+                get_string_name(lugar),
+                raciones,
+                receta.nombre,
+                faltan.join(", ")
+            )));
+        }
+        for i in &needed {
+            update_stock(&mut tx, i.objeto.id, false, -(i.cantidad as f32), lugar)?;
+            record_movement(
+                &mut tx,
+                i.objeto.id,
+                -i.cantidad,
+                lugar,
+                &TipoMovimiento::Receta,
+                &sesion.usuario,
+            )?;
+        }
+        tx.commit()?;
+        return Ok(());
+    }
+
+    pub fn print_recipe(receta: &Receta) {
+        //!Muestra una receta con sus ingredientes.
+        println!(
+            "({}) {}, para {} raciones:",
+            receta.id, receta.nombre, receta.raciones
+        );
+        if receta.ingredientes.is_empty() {
+            println!("  Sin ingredientes");
+        }
+        for i in &receta.ingredientes {
+            println!("  {}: x{} {}", i.objeto.nombre, i.cantidad, i.objeto.medida);
+        }
+    }
+
+    pub fn print_availability(list: &[Disponibilidad], lugar: &Procedencia) {
+        //!Muestra qué recetas se pueden cocinar en lugar y qué falta para las demás.
+        if list.is_empty() {
+            println!("No hay recetas con ingredientes");
+            return;
+        }
+        println!("Con lo que hay en {}:", get_string_name(lugar));
+        for d in list {
+            if d.raciones_posibles > 0 {
+                println!(
+                    "  {}: hasta {} raciones",
+                    d.receta.nombre, d.raciones_posibles
+                );
+            } else {
+                println!(
+                    "  {}: falta {}",
+                    d.receta.nombre,
+                    d.faltan
+                        .iter()
+                        .map(|i| format!(
+                            "x{:.2} {} de {}",
+                            i.cantidad, i.objeto.medida, i.objeto.nombre
+                        ))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,310 @@
+///Módulo que resuelve qué registro quiere seleccionar el usuario a partir de lo que escribe: un id, un nombre exacto, un prefijo o un nombre aproximado.
+pub mod selector {
+    use crate::auth::auth::{get_role_name, Usuario};
+    use crate::db_manager::db_manager::{Categoria, Objeto};
+    use crate::hogar::hogar::Hogar;
+    use crate::places::places::Ubicacion;
+    use crate::recipes::recipes::Receta;
+    use crate::shops::shops::Tienda;
+    use std::io::stdin;
+
+    ///Distancia de edición máxima que se tolera en una coincidencia aproximada por cada 4 caracteres escritos
+    const FUZZY_RATIO: usize = 4;
+
+    ///Registro que puede ser seleccionado por su id o por su nombre.
+    pub trait Seleccionable: Clone {
+        fn id(&self) -> i32;
+        fn nombre(&self) -> &str;
+        fn describir(&self) -> String;
+    }
+
+    impl Seleccionable for Objeto {
+        fn id(&self) -> i32 {
+            return self.id;
+        }
+        fn nombre(&self) -> &str {
+            return &self.nombre;
+        }
+        fn describir(&self) -> String {
+            return format!(
+                "{} (ID:{}, CT:{}, MD:{})",
+                self.nombre, self.id, self.categoria.nombre, self.medida
+            );
+        }
+    }
+
+    impl Seleccionable for Categoria {
+        fn id(&self) -> i32 {
+            return self.id;
+        }
+        fn nombre(&self) -> &str {
+            return &self.nombre;
+        }
+        fn describir(&self) -> String {
+            return format!("({}) {}: {}", self.id, self.nombre, self.descripcion);
+        }
+    }
+
+    impl Seleccionable for Usuario {
+        fn id(&self) -> i32 {
+            return self.id;
+        }
+        fn nombre(&self) -> &str {
+            return &self.nombre;
+        }
+        fn describir(&self) -> String {
+            return format!(
+                "{} (ID:{}, {})",
+                self.nombre,
+                self.id,
+                get_role_name(&self.rol)
+            );
+        }
+    }
+
+    impl Seleccionable for Receta {
+        fn id(&self) -> i32 {
+            return self.id;
+        }
+        fn nombre(&self) -> &str {
+            return &self.nombre;
+        }
+        fn describir(&self) -> String {
+            return format!(
+                "{} (ID:{}, {} ingredientes)",
+                self.nombre,
+                self.id,
+                self.ingredientes.len()
+            );
+        }
+    }
+
+    impl Seleccionable for Tienda {
+        fn id(&self) -> i32 {
+            return self.id;
+        }
+        fn nombre(&self) -> &str {
+            return &self.nombre;
+        }
+        fn describir(&self) -> String {
+            return match self.notas.is_empty() {
+                true => format!("{} (ID:{})", self.nombre, self.id),
+                false => format!("{} (ID:{}): {}", self.nombre, self.id, self.notas),
+            };
+        }
+    }
+
+    impl Seleccionable for Ubicacion {
+        fn id(&self) -> i32 {
+            return self.id;
+        }
+        fn nombre(&self) -> &str {
+            return &self.nombre;
+        }
+        fn describir(&self) -> String {
+            return format!("{} (ID:{})", self.ruta, self.id);
+        }
+    }
+
+    impl Seleccionable for Hogar {
+        fn id(&self) -> i32 {
+            return self.id;
+        }
+        fn nombre(&self) -> &str {
+            return &self.nombre;
+        }
+        fn describir(&self) -> String {
+            return format!("{} (ID:{})", self.nombre, self.id);
+        }
+    }
+
+    ///Resultado de buscar un texto dentro de una lista de registros.
+    pub enum Resolucion<T> {
+        Unico(T),
+        Varios(Vec<T>),
+        Ninguno,
+    }
+
+    pub fn read_input() -> String {
+        //!Lee una línea de la entrada estándar y la devuelve sin espacios ni saltos de línea en los extremos. Si no se puede leer, devuelve una cadena vacía.
+        let mut input = String::new();
+        if let Err(e) = stdin().read_line(&mut input) {
+            println!("No se pudo leer la entrada: {}", e);
+        }
+        return String::from(input.trim());
+    }
+
+    pub fn normalize(text: &str) -> String {
+        //!Pasa un texto a minúsculas, le quita las tildes y deja un único espacio entre palabras, para poder comparar nombres sin que importe cómo se escribieron.
+        let text: String = text
+            .to_lowercase()
+            .chars()
+            .map(|c| match c {
+                'á' | 'à' | 'ä' | 'â' => 'a',
+                'é' | 'è' | 'ë' | 'ê' => 'e',
+                'í' | 'ì' | 'ï' | 'î' => 'i',
+                'ó' | 'ò' | 'ö' | 'ô' => 'o',
+                'ú' | 'ù' | 'ü' | 'û' => 'u',
+                _ => c,
+            })
+            .collect();
+        return text.split_whitespace().collect::<Vec<&str>>().join(" ");
+    }
+
+    fn matches_or_next<T: Seleccionable>(
+        list: &[T],
+        filter: impl Fn(&str) -> bool,
+    ) -> Option<Resolucion<T>> {
+        //!Filtra la lista con el criterio dado sobre el nombre normalizado. Devuelve None si nada coincide, para que se pruebe con el siguiente criterio.
+        let found: Vec<T> = list
+            .iter()
+            .filter(|r| filter(&normalize(r.nombre())))
+            .cloned()
+            .collect();
+        return match found.len() {
+            0 => None,
+            1 => Some(Resolucion::Unico(found[0].clone())),
+            _ => Some(Resolucion::Varios(found)),
+        };
+    }
+
+    pub fn resolve<T: Seleccionable>(input: &str, list: &[T]) -> Resolucion<T> {
+        //!Busca en la lista el registro al que se refiere el texto dado. Se prueba, por este orden, con el id, con el nombre exacto, con el principio del nombre, con cualquier parte del nombre, y por último con una coincidencia aproximada que admite pequeñas erratas. Se detiene en el primer criterio que encuentre algo.
+        let text = normalize(input);
+        if text.is_empty() {
+            return Resolucion::Ninguno;
+        }
+        if let Ok(id) = text.parse::<i32>() {
+            if let Some(r) = list.iter().find(|r| r.id() == id) {
+                return Resolucion::Unico(r.clone());
+            }
+        }
+        if let Some(res) = matches_or_next(list, |name| name == text) {
+            return res;
+        }
+        if let Some(res) = matches_or_next(list, |name| name.starts_with(&text)) {
+            return res;
+        }
+        if let Some(res) = matches_or_next(list, |name| name.contains(&text)) {
+            return res;
+        }
+        let max_distance = 1.max(text.chars().count() / FUZZY_RATIO);
+        let mut scored: Vec<(usize, T)> = list
+            .iter()
+            .map(|r| {
+                let name = normalize(r.nombre());
+                let prefix: String = name.chars().take(text.chars().count()).collect();
+                let distance =
+                    strsim::levenshtein(&name, &text).min(strsim::levenshtein(&prefix, &text));
+                (distance, r.clone())
+            })
+            .filter(|(distance, _)| *distance <= max_distance)
+            .collect();
+        scored.sort_by_key(|(distance, _)| *distance);
+        return match scored.len() {
+            0 => Resolucion::Ninguno,
+            1 => Resolucion::Unico(scored.remove(0).1),
+            _ => Resolucion::Varios(scored.into_iter().map(|(_, r)| r).collect()),
+        };
+    }
+
+    pub fn choose<T: Seleccionable>(input: &str, list: &[T]) -> Option<T> {
+        //!Resuelve el texto dado con resolve(). Si coinciden varios registros, muestra una lista numerada y pide al usuario que elija uno. Devuelve None si no hay coincidencias o si la elección no es válida, explicando el motivo.
+        match resolve(input, list) {
+            Resolucion::Unico(r) => {
+                return Some(r);
+            }
+            Resolucion::Varios(candidates) => {
+                println!("Hay varias coincidencias con \"{}\":", input);
+                for (i, c) in candidates.iter().enumerate() {
+                    println!("{}. {}", i + 1, c.describir());
+                }
+                println!("\nElige el número de la lista (cualquier otra cosa para cancelar):");
+                match read_input().parse::<usize>() {
+                    Ok(n) if n >= 1 && n <= candidates.len() => {
+                        return Some(candidates[n - 1].clone());
+                    }
+                    _ => {
+                        println!("No se eligió ninguna de las coincidencias.");
+                        return None;
+                    }
+                }
+            }
+            Resolucion::Ninguno => {
+                println!("No hay nada con el id o el nombre \"{}\".", input);
+                return None;
+            }
+        }
+    }
+
+    pub fn ask<T: Seleccionable>(prompt: &str, list: &[T]) -> Option<T> {
+        //!Muestra el mensaje dado, lee lo que escriba el usuario y lo resuelve con choose().
+        println!("{}", prompt);
+        return choose(&read_input(), list);
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn hogares(names: &[&str]) -> Vec<Hogar> {
+            return names
+                .iter()
+                .enumerate()
+                .map(|(i, nombre)| Hogar {
+                    id: i as i32 + 1,
+                    nombre: String::from(*nombre),
+                })
+                .collect();
+        }
+
+        fn ids(res: Resolucion<Hogar>) -> Vec<i32> {
+            return match res {
+                Resolucion::Unico(h) => vec![h.id],
+                Resolucion::Varios(list) => list.iter().map(|h| h.id).collect(),
+                Resolucion::Ninguno => Vec::new(),
+            };
+        }
+
+        #[test]
+        fn normalize_ignores_case_accents_and_spaces() {
+            assert_eq!(normalize("  Azúcar   MORENO "), "azucar moreno");
+            assert_eq!(normalize("Pingüino"), "pinguino");
+            assert_eq!(normalize(""), "");
+        }
+
+        #[test]
+        fn resolve_by_id_before_name() {
+            let list = hogares(&["Casa", "1"]);
+            assert_eq!(ids(resolve("1", &list)), vec![1]);
+            assert_eq!(ids(resolve("2", &list)), vec![2]);
+        }
+
+        #[test]
+        fn resolve_exact_name_before_prefix() {
+            let list = hogares(&["Arroz", "Arroz integral"]);
+            assert_eq!(ids(resolve("arroz", &list)), vec![1]);
+            assert_eq!(ids(resolve("arr", &list)), vec![1, 2]);
+        }
+
+        #[test]
+        fn resolve_prefix_then_substring() {
+            let list = hogares(&["Leche entera", "Yogur de leche"]);
+            assert_eq!(ids(resolve("leche", &list)), vec![1]);
+            assert_eq!(ids(resolve("de lec", &list)), vec![2]);
+        }
+
+        #[test]
+        fn resolve_tolerates_small_typos() {
+            let list = hogares(&["Garbanzos", "Lentejas"]);
+            assert_eq!(ids(resolve("garbansos", &list)), vec![1]);
+            assert_eq!(ids(resolve("macarrones", &list)), Vec::<i32>::new());
+        }
+
+        #[test]
+        fn resolve_empty_input_is_nothing() {
+            let list = hogares(&["Casa"]);
+            assert_eq!(ids(resolve("   ", &list)), Vec::<i32>::new());
+        }
+    }
+}
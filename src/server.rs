@@ -0,0 +1,764 @@
+///Módulo con el servidor HTTP que expone la bodega en la red local, como una API JSON bajo /api y como interfaz web en el resto de rutas.
+pub mod server {
+    use crate::auth::auth::{basic_auth, Usuario};
+    use crate::barcode::barcode::find_by_code;
+    use crate::connection_manager::connection_manager::{get_conn, Conexiones};
+    use crate::consumption::consumption::{
+        days_until, read_consumption, running_out, DEFAULT_WINDOW,
+    };
+    use crate::db_manager::db_manager::*;
+    use crate::error::error::BodegaError;
+    use crate::expiry::expiry::{
+        parse_storage, read_shelf_life, set_shelf_life, use_first, Conservacion,
+    };
+    use crate::hogar::hogar::{find_household, open_session, read_user_households, Hogar, Sesion};
+    use crate::places::places::{
+        delete_place, insert_place, move_between, object_places, place_stock, read_places,
+        sitio_storage, Sitio, Ubicacion,
+    };
+    use crate::prices::prices::{monthly_spend, read_purchases, valuation, DEFAULT_MONTHS};
+    use crate::recipes::recipes::{availability, cook, read_recipes};
+    use crate::shops::shops::{read_shops, shopping_list};
+    use crate::tags::tags::read_tags;
+    use crate::web::web;
+    use mysql::PooledConn;
+    use serde::{Deserialize, Serialize};
+    use std::io::{Cursor, Read};
+    use tiny_http::{Header, Method, Request, Response, Server};
+
+    ///Respuesta HTTP con el cuerpo en memoria
+    pub type Respuesta = Response<Cursor<Vec<u8>>>;
+
+    ///Tamaño máximo en bytes del cuerpo de una petición, para que una petición enorme no agote la memoria
+    pub const MAX_BODY: usize = 64 * 1024;
+
+    ///Cuerpo de la petición para crear una categoría
+    #[derive(Deserialize)]
+    struct NuevaCategoria {
+        nombre: String,
+        #[serde(default)]
+        descripcion: String,
+        ///Id de la categoría de la que cuelga, si no va en la raíz
+        #[serde(default)]
+        padre: Option<i32>,
+    }
+
+    ///Cuerpo de la petición para crear un objeto. La categoría se indica por su id.
+    #[derive(Deserialize)]
+    struct NuevoObjeto {
+        categoria: i32,
+        nombre: String,
+        medida: String,
+    }
+
+    ///Cuerpo de la petición para añadir, retirar o fijar existencias, igual que la opción 2 del menú
+    #[derive(Deserialize)]
+    struct Ajuste {
+        lugar: String,
+        cantidad: f32,
+        #[serde(default)]
+        fijar: bool,
+        ///Lo que se pagó en total, si es una compra
+        #[serde(default)]
+        precio: Option<f64>,
+        ///Despensa, nevera o congelador, para la caducidad de lo añadido. Por defecto, la habitual del objeto
+        #[serde(default)]
+        tipo: Option<String>,
+    }
+
+    ///Cuerpo de la petición para transladar existencias, igual que la opción 3 del menú
+    #[derive(Deserialize)]
+    struct Transferencia {
+        objeto: i32,
+        cantidad: f32,
+        desde: String,
+    }
+
+    ///Cuerpo de la petición para crear una ubicación. Está en casa, en tara o dentro de la ubicación con el id dado.
+    #[derive(Deserialize)]
+    struct NuevaUbicacion {
+        nombre: String,
+        en: String,
+        ///Despensa, nevera o congelador. Por defecto, como donde está
+        #[serde(default)]
+        tipo: Option<String>,
+    }
+
+    ///Cuerpo de la petición para añadir o retirar existencias de una ubicación
+    #[derive(Deserialize)]
+    struct Guardado {
+        objeto: i32,
+        cantidad: f32,
+    }
+
+    ///Cuerpo de la petición para mover existencias entre sitios. Cada sitio es casa o tara, para lo que está sin ubicar, o el id de una ubicación.
+    #[derive(Deserialize)]
+    struct Movimiento {
+        objeto: i32,
+        cantidad: f32,
+        desde: String,
+        hasta: String,
+    }
+
+    ///Cuerpo de la petición para cocinar una receta
+    #[derive(Deserialize)]
+    struct Cocinado {
+        raciones: u32,
+        lugar: String,
+    }
+
+    ///Cuerpo de la petición para fijar cuántos días dura un objeto en un tipo de conservación. Con 0 días se quita
+    #[derive(Deserialize)]
+    struct VidaUtilNueva {
+        tipo: String,
+        dias: u32,
+    }
+
+    ///Existencias de un objeto en cada lugar
+    #[derive(Serialize)]
+    pub struct ExistenciasObjeto {
+        pub objeto: Objeto,
+        pub casa: f64,
+        pub tara: f64,
+    }
+
+    ///Cuerpo de la petición para cambiar la categoría de la que cuelga una categoría. Sin padre, la categoría pasa a la raíz.
+    #[derive(Deserialize)]
+    struct CambioPadre {
+        #[serde(default)]
+        padre: Option<i32>,
+    }
+
+    ///Resumen de existencias de una categoría, contando también los objetos de sus subcategorías
+    #[derive(Serialize)]
+    pub struct ResumenCategoria {
+        pub categoria: Categoria,
+        ///Profundidad de la categoría en el árbol: 0 para las de la raíz
+        pub nivel: usize,
+        pub objetos: usize,
+        pub con_existencias_casa: usize,
+        pub con_existencias_tara: usize,
+    }
+
+    ///Informe general de la bodega
+    #[derive(Serialize)]
+    struct Informe {
+        categorias: Vec<ResumenCategoria>,
+        agotados: Vec<Objeto>,
+    }
+
+    ///Mensaje de error que se devuelve en el cuerpo
+    #[derive(Serialize)]
+    struct MensajeError {
+        error: String,
+    }
+
+    pub fn serve(conexiones: &Conexiones, address: &str, default_household: Option<&str>) {
+        //!Atiende peticiones HTTP en la dirección dada hasta que se detenga el programa. Las peticiones se atienden de una en una, cada una con una conexión tomada del conjunto, así que el servidor sigue funcionando si mysql se reinicia. Cada petición debe identificarse con un usuario mediante autenticación Basic, y su hogar se elige con request_session().
+        let server = match Server::http(address) {
+            Ok(server) => server,
+            Err(e) => {
+                println!("No se pudo abrir el servidor en {}: {}", address, e);
+                return;
+            }
+        };
+        println!("Servidor escuchando en http://{}", address);
+        for mut request in server.incoming_requests() {
+            let authorization = request
+                .headers()
+                .iter()
+                .find(|h| h.field.equiv("Authorization"))
+                .map(|h| h.value.to_string());
+            let url = String::from(request.url());
+            let response = match get_conn(conexiones).and_then(|mut connection| {
+                let usuario = basic_auth(&mut connection, authorization.as_deref())?;
+                let sesion = request_session(&mut connection, usuario, &url, default_household)?;
+                return Ok((connection, sesion));
+            }) {
+                Ok((mut connection, sesion)) => {
+                    if url.starts_with("/api/") {
+                        match handle(&mut connection, &sesion, &mut request) {
+                            Ok(response) => response,
+                            Err(e) => error_response(&e),
+                        }
+                    } else {
+                        web::handle(&mut connection, &sesion, &mut request)
+                    }
+                }
+                Err(e) => error_response(&e),
+            };
+            println!(
+                "{} {} -> {}",
+                request.method(),
+                request.url(),
+                response.status_code().0
+            );
+            if let Err(e) = request.respond(response) {
+                println!("No se pudo enviar la respuesta: {}", e);
+            }
+        }
+    }
+
+    pub fn query_param(url: &str, name: &str) -> Option<String> {
+        //!Obtiene un parámetro de la consulta de una dirección.
+        let query = url.split_once('?').map(|(_, q)| q).unwrap_or("");
+        return form_urlencoded::parse(query.as_bytes())
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned());
+    }
+
+    fn request_session(
+        connection: &mut PooledConn,
+        usuario: Usuario,
+        url: &str,
+        default_household: Option<&str>,
+    ) -> Result<Sesion, BodegaError> {
+        //!Elige el hogar de una petición: el del parámetro hogar si lo lleva, el hogar por defecto del servidor si el usuario es miembro de él, o el único hogar del usuario.
+        let hogar = match query_param(url, "hogar") {
+            Some(name) => find_household(connection, &usuario, Some(&name))?,
+            None => match default_household
+                .and_then(|name| find_household(connection, &usuario, Some(name)).ok())
+            {
+                Some(hogar) => hogar,
+                None => find_household(connection, &usuario, None)?,
+            },
+        };
+        return open_session(connection, usuario, hogar);
+    }
+
+    pub fn json_response<T: Serialize>(status: u16, body: &T) -> Respuesta {
+        //!Construye una respuesta con el código dado y el cuerpo serializado en JSON.
+        let data = serde_json::to_vec(body).unwrap_or_default();
+        return Response::from_data(data)
+            .with_status_code(status)
+            .with_header(
+                Header::from_bytes("Content-Type", "application/json; charset=utf-8").unwrap(),
+            );
+    }
+
+    pub fn error_status(e: &BodegaError) -> u16 {
+        //!Código HTTP que corresponde a cada tipo de error.
+        match e {
+            BodegaError::Invalido(_) => 400,
+            BodegaError::NoEncontrado(_) => 404,
+            BodegaError::Conflicto(_) => 409,
+            BodegaError::NoAutenticado(_) => 401,
+            BodegaError::SinPermiso(_) => 403,
+            BodegaError::Configuracion(_) => 500,
+            BodegaError::SinConexion(_) => 503,
+            BodegaError::DemasiadoGrande(_) => 413,
+            BodegaError::Terminal(_) => 500,
+            BodegaError::BaseDeDatos(_) => 500,
+        }
+    }
+
+    fn error_response(e: &BodegaError) -> Respuesta {
+        //!Construye la respuesta JSON de un error. Si falta la autenticación, pide al navegador que muestre el inicio de sesión.
+        let response = json_response(
+            error_status(e),
+            &MensajeError {
+                error: e.to_string(),
+            },
+        );
+        if let BodegaError::NoAutenticado(_) = e {
+            return response.with_header(
+                Header::from_bytes(
+                    "WWW-Authenticate",
+                    "Basic realm=\"bodega\", charset=\"UTF-8\"",
+                )
+                .unwrap(),
+            );
+        }
+        return response;
+    }
+
+    fn read_body<T: for<'de> Deserialize<'de>>(request: &mut Request) -> Result<T, BodegaError> {
+        //!Lee el cuerpo JSON de la petición.
+        let body = read_raw_body(request)?;
+        return serde_json::from_slice(&body)
+            .map_err(|e| BodegaError::Invalido(format!("El cuerpo no es válido: {}", e)));
+    }
+
+    pub fn read_raw_body(request: &mut Request) -> Result<Vec<u8>, BodegaError> {
+        //!Lee el cuerpo de la petición sin pasar de MAX_BODY bytes. Si la petición declara un tamaño mayor, ni siquiera se empieza a leer.
+        let too_large = BodegaError::DemasiadoGrande(format!(
+            "El cuerpo de la petición no puede pasar de {} bytes",
+            MAX_BODY
+        ));
+        if let Some(length) = request.body_length() {
+            if length > MAX_BODY {
+                return Err(too_large);
+            }
+        }
+        let mut body = Vec::new();
+        if let Err(e) = request
+            .as_reader()
+            .take(MAX_BODY as u64 + 1)
+            .read_to_end(&mut body)
+        {
+            return Err(BodegaError::Invalido(format!(
+                "No se pudo leer el cuerpo de la petición: {}",
+                e
+            )));
+        }
+        if body.len() > MAX_BODY {
+            return Err(too_large);
+        }
+        return Ok(body);
+    }
+
+    fn parse_conservation(text: &str) -> Result<Conservacion, BodegaError> {
+        //!Obtiene el tipo de conservación a partir del nombre dado en el cuerpo.
+        return parse_storage(text).ok_or(BodegaError::Invalido(format!(
+            "\"{}\" no es un tipo de conservación válido. Usa despensa, nevera o congelador",
+            text
+        )));
+    }
+
+    fn parse_id(text: &str) -> Result<i32, BodegaError> {
+        //!Obtiene un id de un segmento de la ruta.
+        return text
+            .parse::<i32>()
+            .map_err(|_| BodegaError::Invalido(format!("\"{}\" no es un id válido", text)));
+    }
+
+    fn parse_place(text: &str) -> Result<Procedencia, BodegaError> {
+        //!Obtiene la procedencia a partir del nombre dado en el cuerpo.
+        return parse_location(text).ok_or(BodegaError::Invalido(format!(
+            "\"{}\" no es un lugar válido. Usa casa o tara",
+            text
+        )));
+    }
+
+    fn find_place(
+        connection: &mut PooledConn,
+        sesion: &Sesion,
+        id: i32,
+    ) -> Result<Ubicacion, BodegaError> {
+        //!Busca una ubicación del hogar activo por su id.
+        return read_places(connection, &sesion.hogar)?
+            .into_iter()
+            .find(|u| u.id == id)
+            .ok_or(BodegaError::NoEncontrado(format!(
+                "No existe ninguna ubicación con id {} en {}",
+                id, sesion.hogar.nombre
+            )));
+    }
+
+    fn parse_spot(
+        connection: &mut PooledConn,
+        sesion: &Sesion,
+        text: &str,
+    ) -> Result<Sitio, BodegaError> {
+        //!Obtiene el sitio dado en el cuerpo: casa o tara, sin ubicación, o el id de una ubicación.
+        if let Some(lugar) = parse_location(text) {
+            return Ok(Sitio {
+                lugar,
+                ubicacion: None,
+            });
+        }
+        let u = find_place(connection, sesion, parse_id(text)?)?;
+        return Ok(Sitio {
+            lugar: u.lugar.clone(),
+            ubicacion: Some(u),
+        });
+    }
+
+    fn check_name(name: &str) -> Result<String, BodegaError> {
+        //!Comprueba que un nombre no está vacío.
+        if name.trim().is_empty() {
+            return Err(BodegaError::Invalido(String::from(
+                "El nombre no puede estar vacío",
+            )));
+        }
+        return Ok(String::from(name.trim()));
+    }
+
+    fn find_object(
+        connection: &mut PooledConn,
+        sesion: &Sesion,
+        id: i32,
+    ) -> Result<Objeto, BodegaError> {
+        //!Busca un objeto del hogar activo por su id, o devuelve un error de registro inexistente.
+        return get_object_by_id(id, read_objects(connection, &sesion.hogar)?).ok_or(
+            BodegaError::NoEncontrado(format!("No existe ningún objeto con id {}", id)),
+        );
+    }
+
+    fn find_category(
+        connection: &mut PooledConn,
+        sesion: &Sesion,
+        id: i32,
+    ) -> Result<Categoria, BodegaError> {
+        //!Busca una categoría del hogar activo por su id, o devuelve un error de registro inexistente.
+        return get_category_by_id(id, read_categories(connection, &sesion.hogar)?).ok_or(
+            BodegaError::NoEncontrado(format!("No existe ninguna categoría con id {}", id)),
+        );
+    }
+
+    pub fn stock_rows(
+        connection: &mut PooledConn,
+        objects: Vec<Objeto>,
+    ) -> Result<Vec<ExistenciasObjeto>, BodegaError> {
+        //!Obtiene las existencias en cada lugar de los objetos dados.
+        let stock = read_stock(connection, &objects)?;
+        return Ok(objects
+            .into_iter()
+            .map(|o| ExistenciasObjeto {
+                casa: get_quantity(&stock, o.id, &Procedencia::Casa),
+                tara: get_quantity(&stock, o.id, &Procedencia::Tara),
+                objeto: o,
+            })
+            .collect());
+    }
+
+    pub fn category_summary(
+        connection: &mut PooledConn,
+        hogar: &Hogar,
+    ) -> Result<Vec<ResumenCategoria>, BodegaError> {
+        //!Resume, por categoría del hogar dado y en el orden del árbol de categorías, cuántos objetos hay en ella y en sus subcategorías y cuántos de ellos tienen existencias en cada lugar.
+        let objects = read_objects(connection, hogar)?;
+        let rows = stock_rows(connection, objects)?;
+        let categories = read_categories(connection, hogar)?;
+        return Ok(category_tree(&categories)
+            .into_iter()
+            .map(|(nivel, c)| {
+                let ids = subtree(&categories, c.id);
+                let of_category: Vec<&ExistenciasObjeto> = rows
+                    .iter()
+                    .filter(|r| ids.contains(&r.objeto.categoria.id))
+                    .collect();
+                ResumenCategoria {
+                    nivel,
+                    objetos: of_category.len(),
+                    con_existencias_casa: of_category.iter().filter(|r| r.casa > EPSILON).count(),
+                    con_existencias_tara: of_category.iter().filter(|r| r.tara > EPSILON).count(),
+                    categoria: c,
+                }
+            })
+            .collect());
+    }
+
+    fn report(connection: &mut PooledConn, sesion: &Sesion) -> Result<Informe, BodegaError> {
+        //!Resume, por categoría del hogar activo y contando sus subcategorías, cuántos objetos hay y cuántos tienen existencias en cada lugar, y lista los objetos sin existencias en ningún lugar.
+        let categorias = category_summary(connection, &sesion.hogar)?;
+        let objects = read_objects(connection, &sesion.hogar)?;
+        let agotados = stock_rows(connection, objects)?
+            .into_iter()
+            .filter(|r| r.casa <= EPSILON && r.tara <= EPSILON)
+            .map(|r| r.objeto)
+            .collect();
+        return Ok(Informe {
+            categorias,
+            agotados,
+        });
+    }
+
+    fn handle(
+        connection: &mut PooledConn,
+        sesion: &Sesion,
+        request: &mut Request,
+    ) -> Result<Respuesta, BodegaError> {
+        //!Atiende una petición de la API según su método y su ruta, en nombre del usuario autenticado y en el hogar de la petición.
+        let url = String::from(request.url());
+        let path = url.split('?').next().unwrap_or("");
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        let method = request.method().clone();
+        match (method, segments.as_slice()) {
+            (Method::Get, ["api", "categorias"]) => {
+                return Ok(json_response(
+                    200,
+                    &read_categories(connection, &sesion.hogar)?,
+                ));
+            }
+            (Method::Post, ["api", "categorias"]) => {
+                let body: NuevaCategoria = read_body(request)?;
+                let nombre = check_name(&body.nombre)?;
+                let id = insert_category(connection, sesion, nombre, body.descripcion, body.padre)?;
+                let created = find_category(connection, sesion, id)?;
+                return Ok(json_response(201, &created));
+            }
+            (Method::Post, ["api", "categorias", id, "padre"]) => {
+                let cat = find_category(connection, sesion, parse_id(id)?)?;
+                let body: CambioPadre = read_body(request)?;
+                set_parent(connection, sesion, cat.id, body.padre)?;
+                let moved = find_category(connection, sesion, cat.id)?;
+                return Ok(json_response(200, &moved));
+            }
+            (Method::Delete, ["api", "categorias", id]) => {
+                let cat = find_category(connection, sesion, parse_id(id)?)?;
+                delete_category(connection, sesion, cat.id)?;
+                return Ok(json_response(200, &cat));
+            }
+            (Method::Get, ["api", "objetos"]) => {
+                let mut objects = read_objects(connection, &sesion.hogar)?;
+                if let Some(tag) = query_param(&url, "etiqueta") {
+                    objects = with_tag(objects, &tag);
+                }
+                return Ok(json_response(200, &objects));
+            }
+            (Method::Get, ["api", "objetos", id]) => {
+                return Ok(json_response(
+                    200,
+                    &find_object(connection, sesion, parse_id(id)?)?,
+                ));
+            }
+            (Method::Get, ["api", "etiquetas"]) => {
+                return Ok(json_response(200, &read_tags(connection, &sesion.hogar)?));
+            }
+            (Method::Get, ["api", "codigos", codigo]) => {
+                let obj = find_by_code(connection, &sesion.hogar, codigo)?.ok_or(
+                    BodegaError::NoEncontrado(format!("Ningún objeto tiene el código {}", codigo)),
+                )?;
+                return Ok(json_response(200, &obj));
+            }
+            (Method::Post, ["api", "objetos"]) => {
+                let body: NuevoObjeto = read_body(request)?;
+                let nombre = check_name(&body.nombre)?;
+                let cat =
+                    get_category_by_id(body.categoria, read_categories(connection, &sesion.hogar)?)
+                        .ok_or(BodegaError::Invalido(format!(
+                            "No existe ninguna categoría con id {}",
+                            body.categoria
+                        )))?;
+                let id = insert_object(
+                    connection,
+                    sesion,
+                    cat,
+                    nombre,
+                    String::from(body.medida.trim()),
+                )?;
+                let created = find_object(connection, sesion, id)?;
+                return Ok(json_response(201, &created));
+            }
+            (Method::Delete, ["api", "objetos", id]) => {
+                let obj = find_object(connection, sesion, parse_id(id)?)?;
+                delete_object(connection, sesion, obj.id)?;
+                return Ok(json_response(200, &obj));
+            }
+            (Method::Get, ["api", "existencias"]) => {
+                let mut objects = read_objects(connection, &sesion.hogar)?;
+                if let Some(tag) = query_param(&url, "etiqueta") {
+                    objects = with_tag(objects, &tag);
+                }
+                return Ok(json_response(200, &stock_rows(connection, objects)?));
+            }
+            (Method::Get, ["api", "existencias", id]) => {
+                let obj = find_object(connection, sesion, parse_id(id)?)?;
+                return Ok(json_response(
+                    200,
+                    &stock_rows(connection, vec![obj])?.pop(),
+                ));
+            }
+            (Method::Post, ["api", "existencias", id]) => {
+                let id = parse_id(id)?;
+                let body: Ajuste = read_body(request)?;
+                let location = parse_place(&body.lugar)?;
+                let tipo = match &body.tipo {
+                    Some(tipo) => Some(parse_conservation(tipo)?),
+                    None => None,
+                };
+                match (body.precio, body.fijar) {
+                    (Some(_), true) => {
+                        return Err(BodegaError::Invalido(String::from(
+                            "Solo se puede registrar el precio al añadir existencias, no al fijarlas",
+                        )));
+                    }
+                    (Some(precio), false) => purchase_stock(
+                        connection,
+                        sesion,
+                        id,
+                        body.cantidad,
+                        precio,
+                        &location,
+                        tipo.as_ref(),
+                    )?,
+                    (None, _) => adjust_stock(
+                        connection,
+                        sesion,
+                        id,
+                        body.fijar,
+                        body.cantidad,
+                        &location,
+                        tipo.as_ref(),
+                    )?,
+                }
+                let obj = find_object(connection, sesion, id)?;
+                return Ok(json_response(
+                    200,
+                    &stock_rows(connection, vec![obj])?.pop(),
+                ));
+            }
+            (Method::Post, ["api", "transferencias"]) => {
+                let body: Transferencia = read_body(request)?;
+                let from = parse_place(&body.desde)?;
+                move_stock(connection, sesion, body.objeto, body.cantidad, &from)?;
+                let obj = find_object(connection, sesion, body.objeto)?;
+                return Ok(json_response(
+                    200,
+                    &stock_rows(connection, vec![obj])?.pop(),
+                ));
+            }
+            (Method::Get, ["api", "existencias", id, "ubicaciones"]) => {
+                let obj = find_object(connection, sesion, parse_id(id)?)?;
+                return Ok(json_response(
+                    200,
+                    &object_places(connection, &sesion.hogar, &obj)?,
+                ));
+            }
+            (Method::Get, ["api", "ubicaciones"]) => {
+                return Ok(json_response(200, &read_places(connection, &sesion.hogar)?));
+            }
+            (Method::Post, ["api", "ubicaciones"]) => {
+                let body: NuevaUbicacion = read_body(request)?;
+                let dentro = parse_spot(connection, sesion, &body.en)?;
+                let tipo = match body.tipo {
+                    Some(tipo) => parse_conservation(&tipo)?,
+                    None => sitio_storage(&dentro),
+                };
+                let created = insert_place(connection, sesion, &body.nombre, &dentro, &tipo)?;
+                return Ok(json_response(201, &created));
+            }
+            (Method::Delete, ["api", "ubicaciones", id]) => {
+                let u = find_place(connection, sesion, parse_id(id)?)?;
+                delete_place(connection, sesion, u.id)?;
+                return Ok(json_response(200, &u));
+            }
+            (Method::Post, ["api", "ubicaciones", id, "existencias"]) => {
+                let u = find_place(connection, sesion, parse_id(id)?)?;
+                let body: Guardado = read_body(request)?;
+                let obj = find_object(connection, sesion, body.objeto)?;
+                place_stock(connection, sesion, &obj, &u, body.cantidad)?;
+                return Ok(json_response(
+                    200,
+                    &object_places(connection, &sesion.hogar, &obj)?,
+                ));
+            }
+            (Method::Post, ["api", "ubicaciones", "movimientos"]) => {
+                let body: Movimiento = read_body(request)?;
+                let obj = find_object(connection, sesion, body.objeto)?;
+                let desde = parse_spot(connection, sesion, &body.desde)?;
+                let hasta = parse_spot(connection, sesion, &body.hasta)?;
+                move_between(connection, sesion, &obj, body.cantidad, &desde, &hasta)?;
+                return Ok(json_response(
+                    200,
+                    &object_places(connection, &sesion.hogar, &obj)?,
+                ));
+            }
+            (Method::Get, ["api", "usuario"]) => {
+                return Ok(json_response(200, &sesion.usuario));
+            }
+            (Method::Get, ["api", "hogares"]) => {
+                return Ok(json_response(
+                    200,
+                    &read_user_households(connection, &sesion.usuario)?,
+                ));
+            }
+            (Method::Get, ["api", "informes", "resumen"]) => {
+                return Ok(json_response(200, &report(connection, sesion)?));
+            }
+            (Method::Get, ["api", "recetas"]) => {
+                return Ok(json_response(
+                    200,
+                    &read_recipes(connection, &sesion.hogar)?,
+                ));
+            }
+            (Method::Get, ["api", "recetas", "disponibles", lugar]) => {
+                let location = parse_place(lugar)?;
+                return Ok(json_response(
+                    200,
+                    &availability(connection, &sesion.hogar, &location)?,
+                ));
+            }
+            (Method::Post, ["api", "recetas", id, "cocinar"]) => {
+                let id = parse_id(id)?;
+                let body: Cocinado = read_body(request)?;
+                let location = parse_place(&body.lugar)?;
+                let receta = read_recipes(connection, &sesion.hogar)?
+                    .into_iter()
+                    .find(|r| r.id == id)
+                    .ok_or(BodegaError::NoEncontrado(format!(
+                        "No existe ninguna receta con id {}",
+                        id
+                    )))?;
+                cook(connection, sesion, &receta, body.raciones, &location)?;
+                let ids: Vec<i32> = receta.ingredientes.iter().map(|i| i.objeto.id).collect();
+                let objects = read_objects(connection, &sesion.hogar)?
+                    .into_iter()
+                    .filter(|o| ids.contains(&o.id))
+                    .collect();
+                return Ok(json_response(200, &stock_rows(connection, objects)?));
+            }
+            (Method::Get, ["api", "objetos", id, "conservacion"]) => {
+                let obj = find_object(connection, sesion, parse_id(id)?)?;
+                return Ok(json_response(200, &read_shelf_life(connection, &obj)?));
+            }
+            (Method::Post, ["api", "objetos", id, "conservacion"]) => {
+                let obj = find_object(connection, sesion, parse_id(id)?)?;
+                let body: VidaUtilNueva = read_body(request)?;
+                let tipo = parse_conservation(&body.tipo)?;
+                set_shelf_life(connection, sesion, &obj, &tipo, body.dias)?;
+                return Ok(json_response(200, &read_shelf_life(connection, &obj)?));
+            }
+            (Method::Get, ["api", "objetos", id, "precios"]) => {
+                let obj = find_object(connection, sesion, parse_id(id)?)?;
+                return Ok(json_response(200, &read_purchases(connection, &obj)?));
+            }
+            (Method::Get, ["api", "tiendas"]) => {
+                return Ok(json_response(200, &read_shops(connection, &sesion.hogar)?));
+            }
+            (Method::Get, ["api", "informes", "compra"]) => {
+                return Ok(json_response(
+                    200,
+                    &shopping_list(connection, &sesion.hogar)?,
+                ));
+            }
+            (Method::Get, ["api", "informes", "valor"]) => {
+                return Ok(json_response(200, &valuation(connection, &sesion.hogar)?));
+            }
+            (Method::Get, ["api", "informes", "gasto"]) => {
+                let months = match query_param(&url, "meses") {
+                    Some(meses) => meses.trim().parse::<u32>().map_err(|_| {
+                        BodegaError::Invalido(format!("\"{}\" no es un número de meses", meses))
+                    })?,
+                    None => DEFAULT_MONTHS,
+                };
+                return Ok(json_response(
+                    200,
+                    &monthly_spend(connection, &sesion.hogar, months)?,
+                ));
+            }
+            (Method::Get, ["api", "informes", "consumo"]) => {
+                let window = match query_param(&url, "dias") {
+                    Some(dias) => dias.trim().parse::<u32>().map_err(|_| {
+                        BodegaError::Invalido(format!("\"{}\" no es un número de días", dias))
+                    })?,
+                    None => DEFAULT_WINDOW,
+                };
+                let mut consumos = read_consumption(connection, &sesion.hogar, window)?;
+                if let Some(date) = query_param(&url, "hasta") {
+                    consumos = running_out(&consumos, days_until(connection, &date)?);
+                }
+                return Ok(json_response(200, &consumos));
+            }
+            (Method::Get, ["api", "informes", "caducidades"]) => {
+                let dias = match query_param(&url, "dias") {
+                    Some(dias) => Some(dias.trim().parse::<i64>().map_err(|_| {
+                        BodegaError::Invalido(format!("\"{}\" no es un número de días", dias))
+                    })?),
+                    None => None,
+                };
+                return Ok(json_response(
+                    200,
+                    &use_first(connection, &sesion.hogar, dias)?,
+                ));
+            }
+            _ => {
+                return Err(BodegaError::NoEncontrado(format!(
+                    "No existe la ruta {}",
+                    path
+                )));
+            }
+        }
+    }
+}
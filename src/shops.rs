@@ -0,0 +1,412 @@
+///Módulo que gestiona las tiendas de cada hogar y en cuáles se compra cada objeto, con una de ellas como la preferida, y que hace la lista de la compra de lo que se ha agotado agrupada por tienda.
+pub mod shops {
+    use crate::auth::auth::{check_permission, Permiso};
+    use crate::db_manager::db_manager::{
+        get_quantity, read_objects, read_stock, Objeto, Procedencia, EPSILON,
+    };
+    use crate::error::error::BodegaError;
+    use crate::hogar::hogar::{Hogar, Sesion};
+    use crate::selector::selector::normalize;
+    use mysql::prelude::Queryable;
+    use mysql::{params, PooledConn, TxOpts};
+    use serde::Serialize;
+    use std::path::Path;
+
+    ///Tienda en la que se compran objetos. Se guarda en la tabla tiendas.
+    #[derive(Clone, Serialize)]
+    pub struct Tienda {
+        pub id: i32,
+        pub nombre: String,
+        pub notas: String,
+    }
+
+    ///Tienda en la que se compra un objeto.
+    #[derive(Clone, Serialize)]
+    pub struct Proveedor {
+        pub tienda: Tienda,
+        pub preferida: bool,
+    }
+
+    ///Objetos que hay que comprar en una tienda. La tienda es None para los que no tienen ninguna.
+    #[derive(Clone, Serialize)]
+    pub struct PorTienda {
+        pub tienda: Option<Tienda>,
+        pub objetos: Vec<Objeto>,
+    }
+
+    pub fn read_shops(conn: &mut PooledConn, hogar: &Hogar) -> Result<Vec<Tienda>, BodegaError> {
+        //!Lee las tiendas del hogar dado, ordenadas por nombre.
+        return Ok(conn.exec_map(
+            "SELECT id, nombre, notas FROM tiendas WHERE hogar = :hogar ORDER BY nombre;",
+            params! {"hogar" => hogar.id},
+            |(id, nombre, notas)| Tienda { id, nombre, notas },
+        )?);
+    }
+
+    fn check_shop_name(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        name: &str,
+        id: Option<i32>,
+    ) -> Result<String, BodegaError> {
+        //!Comprueba que el nombre de una tienda no está vacío ni lo tiene ya otra tienda del hogar activo, y lo devuelve sin espacios en los extremos.
+        let name = String::from(name.trim());
+        if name.is_empty() {
+            return Err(BodegaError::Invalido(String::from(
+                "El nombre no puede estar vacío",
+            )));
+        }
+        if read_shops(conn, &sesion.hogar)?
+            .iter()
+            .any(|t| Some(t.id) != id && normalize(&t.nombre) == normalize(&name))
+        {
+            return Err(BodegaError::Invalido(format!(
+                "Ya existe una tienda llamada {}",
+                name
+            )));
+        }
+        return Ok(name);
+    }
+
+    pub fn insert_shop(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        name: &str,
+        notes: &str,
+    ) -> Result<Tienda, BodegaError> {
+        //!Crea una tienda en el hogar activo, si el usuario puede gestionar el catálogo.
+        check_permission(&sesion.usuario, &Permiso::GestionarCatalogo)?;
+        let name = check_shop_name(conn, sesion, name, None)?;
+        conn.exec_drop(
+            "INSERT INTO tiendas (nombre, notas, hogar) VALUES (:nombre, :notas, :hogar);",
+            params! {"nombre" => &name, "notas" => notes.trim(), "hogar" => sesion.hogar.id},
+        )?;
+        return Ok(Tienda {
+            id: conn.last_insert_id() as i32,
+            nombre: name,
+            notas: String::from(notes.trim()),
+        });
+    }
+
+    pub fn update_shop(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        tienda: &Tienda,
+        name: &str,
+        notes: &str,
+    ) -> Result<Tienda, BodegaError> {
+        //!Cambia el nombre y las notas de una tienda del hogar activo, si el usuario puede gestionar el catálogo.
+        check_permission(&sesion.usuario, &Permiso::GestionarCatalogo)?;
+        let name = check_shop_name(conn, sesion, name, Some(tienda.id))?;
+        if !read_shops(conn, &sesion.hogar)?
+            .iter()
+            .any(|t| t.id == tienda.id)
+        {
+            return Err(BodegaError::NoEncontrado(format!(
+                "No existe ninguna tienda con id {} en {}",
+                tienda.id, sesion.hogar.nombre
+            )));
+        }
+        conn.exec_drop(
+            "UPDATE tiendas SET nombre = :nombre, notas = :notas WHERE id = :id;",
+            params! {"nombre" => &name, "notas" => notes.trim(), "id" => tienda.id},
+        )?;
+        return Ok(Tienda {
+            id: tienda.id,
+            nombre: name,
+            notas: String::from(notes.trim()),
+        });
+    }
+
+    pub fn delete_shop(conn: &mut PooledConn, sesion: &Sesion, id: i32) -> Result<(), BodegaError> {
+        //!Elimina una tienda del hogar activo, y con ella lo que se compraba allí, si el usuario puede gestionar el catálogo.
+        check_permission(&sesion.usuario, &Permiso::GestionarCatalogo)?;
+        conn.exec_drop(
+            "DELETE FROM tiendas WHERE id = :id AND hogar = :hogar;",
+            params! {"id" => id, "hogar" => sesion.hogar.id},
+        )?;
+        if conn.affected_rows() == 0 {
+            return Err(BodegaError::NoEncontrado(format!(
+                "No existe ninguna tienda con id {} en {}",
+                id, sesion.hogar.nombre
+            )));
+        }
+        return Ok(());
+    }
+
+    pub fn read_suppliers(
+        conn: &mut PooledConn,
+        hogar: &Hogar,
+    ) -> Result<Vec<(i32, Proveedor)>, BodegaError> {
+        //!Lee en qué tiendas del hogar se compra cada objeto, como pares de id del objeto y tienda, con las preferidas primero.
+        let shops = read_shops(conn, hogar)?;
+        let rows: Vec<(i32, i32, bool)> = conn.exec(
+            "SELECT p.id_objeto, p.id_tienda, p.preferida FROM proveedores p
+                JOIN tiendas t ON t.id = p.id_tienda
+                WHERE t.hogar = :hogar ORDER BY p.preferida DESC, t.nombre;",
+            params! {"hogar" => hogar.id},
+        )?;
+        return Ok(rows
+            .into_iter()
+            .filter_map(|(objeto, tienda, preferida)| {
+                shops.iter().find(|t| t.id == tienda).map(|t| {
+                    (
+                        objeto,
+                        Proveedor {
+                            tienda: t.clone(),
+                            preferida,
+                        },
+                    )
+                })
+            })
+            .collect());
+    }
+
+    pub fn object_suppliers(
+        conn: &mut PooledConn,
+        hogar: &Hogar,
+        objeto: &Objeto,
+    ) -> Result<Vec<Proveedor>, BodegaError> {
+        //!Devuelve las tiendas en las que se compra un objeto, con la preferida primero.
+        return Ok(read_suppliers(conn, hogar)?
+            .into_iter()
+            .filter(|(id, _)| *id == objeto.id)
+            .map(|(_, p)| p)
+            .collect());
+    }
+
+    fn check_link(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        objeto: &Objeto,
+        tienda: &Tienda,
+    ) -> Result<(), BodegaError> {
+        //!Comprueba que el objeto y la tienda son del hogar activo.
+        if !read_objects(conn, &sesion.hogar)?
+            .iter()
+            .any(|o| o.id == objeto.id)
+        {
+            return Err(BodegaError::NoEncontrado(format!(
+                "No existe ningún objeto con id {} en {}",
+                objeto.id, sesion.hogar.nombre
+            )));
+        }
+        if !read_shops(conn, &sesion.hogar)?
+            .iter()
+            .any(|t| t.id == tienda.id)
+        {
+            return Err(BodegaError::NoEncontrado(format!(
+                "No existe ninguna tienda con id {} en {}",
+                tienda.id, sesion.hogar.nombre
+            )));
+        }
+        return Ok(());
+    }
+
+    pub fn link_supplier(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        objeto: &Objeto,
+        tienda: &Tienda,
+        preferida: bool,
+    ) -> Result<(), BodegaError> {
+        //!Indica que un objeto se compra en una tienda, si el usuario puede gestionar el catálogo. Si es la preferida, deja de serlo la que lo fuera antes, en la misma transacción.
+        check_permission(&sesion.usuario, &Permiso::GestionarCatalogo)?;
+        check_link(conn, sesion, objeto, tienda)?;
+        let mut tx = conn.start_transaction(TxOpts::default())?;
+        if preferida {
+            tx.exec_drop(
+                "UPDATE proveedores SET preferida = FALSE WHERE id_objeto = :id;",
+                params! {"id" => objeto.id},
+            )?;
+        }
+        tx.exec_drop(
+            "INSERT INTO proveedores (id_objeto, id_tienda, preferida) VALUES (:id, :tienda, :preferida)
+                ON DUPLICATE KEY UPDATE preferida = :preferida;",
+            params! {"id" => objeto.id, "tienda" => tienda.id, "preferida" => preferida},
+        )?;
+        tx.commit()?;
+        return Ok(());
+    }
+
+    pub fn unlink_supplier(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        objeto: &Objeto,
+        tienda: &Tienda,
+    ) -> Result<(), BodegaError> {
+        //!Indica que un objeto ya no se compra en una tienda, si el usuario puede gestionar el catálogo.
+        check_permission(&sesion.usuario, &Permiso::GestionarCatalogo)?;
+        check_link(conn, sesion, objeto, tienda)?;
+        conn.exec_drop(
+            "DELETE FROM proveedores WHERE id_objeto = :id AND id_tienda = :tienda;",
+            params! {"id" => objeto.id, "tienda" => tienda.id},
+        )?;
+        if conn.affected_rows() == 0 {
+            return Err(BodegaError::NoEncontrado(format!(
+                "{} no se compra en {}",
+                objeto.nombre, tienda.nombre
+            )));
+        }
+        return Ok(());
+    }
+
+    pub fn preferred_shop(suppliers: &[(i32, Proveedor)], id: i32) -> Option<Tienda> {
+        //!Elige la tienda en la que comprar el objeto con la id dada entre las de suppliers: la preferida o, si no tiene, la primera por orden alfabético. No depende del orden de suppliers.
+        let shops: Vec<&Proveedor> = suppliers
+            .iter()
+            .filter(|(o, _)| *o == id)
+            .map(|(_, p)| p)
+            .collect();
+        if let Some(p) = shops.iter().find(|p| p.preferida) {
+            return Some(p.tienda.clone());
+        }
+        return shops
+            .into_iter()
+            .min_by_key(|p| normalize(&p.tienda.nombre))
+            .map(|p| p.tienda.clone());
+    }
+
+    pub fn shopping_list(
+        conn: &mut PooledConn,
+        hogar: &Hogar,
+    ) -> Result<Vec<PorTienda>, BodegaError> {
+        //!Hace la lista de la compra: los objetos del hogar que no tienen existencias en ningún lugar, agrupados por la tienda en la que se compran. Cada objeto va en su tienda preferida o, si no tiene, en la primera por orden alfabético. Los que no se compran en ninguna van al final.
+        let mut objects = read_objects(conn, hogar)?;
+        objects.sort_by(|a, b| {
+            (&a.categoria.nombre, &a.nombre).cmp(&(&b.categoria.nombre, &b.nombre))
+        });
+        let stock = read_stock(conn, &objects)?;
+        let suppliers = read_suppliers(conn, hogar)?;
+        let mut groups: Vec<PorTienda> = Vec::new();
+        for o in objects {
+            let empty = Procedencia::all()
+                .iter()
+                .all(|l| get_quantity(&stock, o.id, l) <= EPSILON);
+            if !empty {
+                continue;
+            }
+            let tienda = preferred_shop(&suppliers, o.id);
+            let index = match groups
+                .iter()
+                .position(|g| g.tienda.as_ref().map(|t| t.id) == tienda.as_ref().map(|t| t.id))
+            {
+                Some(index) => index,
+                None => {
+                    groups.push(PorTienda {
+                        tienda,
+                        objetos: Vec::new(),
+                    });
+                    groups.len() - 1
+                }
+            };
+            groups[index].objetos.push(o);
+        }
+        groups.sort_by_key(|g| {
+            (
+                g.tienda.is_none(),
+                g.tienda.as_ref().map(|t| t.nombre.clone()),
+            )
+        });
+        return Ok(groups);
+    }
+
+    pub fn print_shopping_list(groups: &[PorTienda]) {
+        //!Muestra la lista de la compra agrupada por tienda.
+        if groups.is_empty() {
+            println!("No se ha agotado nada");
+            return;
+        }
+        for g in groups {
+            match &g.tienda {
+                Some(t) if t.notas.is_empty() => println!("{}:", t.nombre),
+                Some(t) => println!("{} ({}):", t.nombre, t.notas),
+                None => println!("Sin tienda:"),
+            }
+            for o in &g.objetos {
+                match o.precio {
+                    Some(precio) => println!(
+                        "  {} ({}), último precio {:.2} €/{}",
+                        o.nombre, o.categoria.nombre, precio, o.medida
+                    ),
+                    None => println!("  {} ({})", o.nombre, o.categoria.nombre),
+                }
+            }
+            println!();
+        }
+    }
+
+    pub fn export_shopping_list(path: &Path, groups: &[PorTienda]) -> Result<(), BodegaError> {
+        //!Guarda la lista de la compra en un fichero CSV, con una fila por objeto y la tienda en la primera columna, para abrirla en una hoja de cálculo o imprimirla.
+        let error = |e: &dyn std::fmt::Display| {
+            BodegaError::Invalido(format!("No se pudo escribir {}: {}", path.display(), e))
+        };
+        let mut writer = csv::Writer::from_path(path).map_err(|e| error(&e))?;
+        writer
+            .write_record([
+                "tienda",
+                "objeto",
+                "categoria",
+                "medida",
+                "envase",
+                "ultimo_precio",
+            ])
+            .map_err(|e| error(&e))?;
+        for g in groups {
+            let tienda = g
+                .tienda
+                .as_ref()
+                .map(|t| t.nombre.clone())
+                .unwrap_or_default();
+            for o in &g.objetos {
+                writer
+                    .write_record([
+                        tienda.clone(),
+                        o.nombre.clone(),
+                        o.categoria.nombre.clone(),
+                        o.medida.clone(),
+                        o.envase.map(|e| e.to_string()).unwrap_or_default(),
+                        o.precio.map(|p| format!("{:.2}", p)).unwrap_or_default(),
+                    ])
+                    .map_err(|e| error(&e))?;
+            }
+        }
+        return writer.flush().map_err(|e| error(&e));
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn proveedor(id: i32, nombre: &str, preferida: bool) -> Proveedor {
+            return Proveedor {
+                tienda: Tienda {
+                    id,
+                    nombre: String::from(nombre),
+                    notas: String::new(),
+                },
+                preferida,
+            };
+        }
+
+        #[test]
+        fn preferred_shop_wins_whatever_the_order() {
+            let suppliers = vec![
+                (1, proveedor(1, "Abacería", false)),
+                (1, proveedor(2, "Mercado", true)),
+            ];
+            assert_eq!(preferred_shop(&suppliers, 1).map(|t| t.id), Some(2));
+        }
+
+        #[test]
+        fn alphabetical_shop_without_preferred() {
+            let suppliers = vec![
+                (1, proveedor(1, "Mercado", false)),
+                (1, proveedor(2, "Ábaco", false)),
+                (2, proveedor(3, "Aaa", true)),
+            ];
+            assert_eq!(preferred_shop(&suppliers, 1).map(|t| t.id), Some(2));
+            assert_eq!(preferred_shop(&suppliers, 3).map(|t| t.id), None);
+        }
+    }
+}
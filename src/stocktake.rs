@@ -0,0 +1,187 @@
+///Módulo que hace inventario de un lugar: recorre todos los objetos del hogar pidiendo la cantidad contada, muestra las diferencias con la base de datos y aplica todas las correcciones en una única transacción, registradas en el historial como movimientos de inventario.
+pub mod stocktake {
+    use crate::auth::auth::{check_permission, Permiso};
+    use crate::db_manager::db_manager::{
+        get_quantity, get_string_name, get_table_name, parse_quantity, read_objects, read_stock,
+        record_movement, update_stock, Objeto, Procedencia, TipoMovimiento, EPSILON,
+    };
+    use crate::error::error::BodegaError;
+    use crate::hogar::hogar::Sesion;
+    use crate::selector::selector::read_input;
+    use mysql::prelude::Queryable;
+    use mysql::{params, PooledConn, TxOpts};
+
+    ///Lo que se escribe para terminar el recuento antes de llegar al último objeto
+    const FINISH: &str = "fin";
+
+    ///Cantidad contada de un objeto, junto con la que había en la base de datos al empezar.
+    #[derive(Clone)]
+    pub struct Recuento {
+        pub objeto: Objeto,
+        pub esperado: f64,
+        pub contado: f64,
+    }
+
+    pub fn differences(recuentos: &[Recuento]) -> Vec<Recuento> {
+        //!Devuelve los recuentos cuya cantidad contada no coincide con la de la base de datos.
+        return recuentos
+            .iter()
+            .filter(|r| (r.contado - r.esperado).abs() > EPSILON)
+            .cloned()
+            .collect();
+    }
+
+    pub fn apply_stocktake(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        lugar: &Procedencia,
+        recuentos: &[Recuento],
+    ) -> Result<usize, BodegaError> {
+        //!Fija las existencias de cada objeto contado en lugar a la cantidad contada y registra la diferencia como un movimiento de inventario, todo en una única transacción. La diferencia se calcula con la cantidad que hay al aplicar, por si alguien ha cambiado las existencias mientras se contaba. Devuelve cuántas existencias se han corregido. Requiere el permiso de gestionar existencias.
+        check_permission(&sesion.usuario, &Permiso::GestionarExistencias)?;
+        let objects = read_objects(conn, &sesion.hogar)?;
+        let mut tx = conn.start_transaction(TxOpts::default())?;
+        let mut corrected = 0;
+        for r in recuentos {
+            if !objects.iter().any(|o| o.id == r.objeto.id) {
+                return Err(BodegaError::NoEncontrado(format!(
+                    "{} ya no existe en {}",
+                    r.objeto.nombre, sesion.hogar.nombre
+                )));
+            }
+            if !r.contado.is_finite() || r.contado < 0.0 {
+                return Err(BodegaError::Invalido(format!(
+                    "La cantidad contada de {} no es válida: {}",
+                    r.objeto.nombre, r.contado
+                )));
+            }
+            let current: Option<Option<f64>> = tx.exec_first(
+                format!(
+                    "SELECT cantidad FROM {} WHERE id_objeto=:id FOR UPDATE;",
+                    get_table_name(lugar)
+                ),
+                params! {"id" => r.objeto.id},
+            )?;
+            let current = current.flatten().unwrap_or(0.0);
+            if (r.contado - current).abs() <= EPSILON {
+                continue;
+            }
+            update_stock(&mut tx, r.objeto.id, true, r.contado as f32, lugar)?;
+            record_movement(
+                &mut tx,
+                r.objeto.id,
+                r.contado - current,
+                lugar,
+                &TipoMovimiento::Inventario,
+                &sesion.usuario,
+            )?;
+            corrected += 1;
+        }
+        tx.commit()?;
+        return Ok(corrected);
+    }
+
+    fn ask_count(objeto: &Objeto, esperado: f64) -> Option<Option<f64>> {
+        //!Pide la cantidad contada de un objeto hasta que se escriba una válida. Devuelve None si se quiere terminar, Some(None) si el objeto se salta y Some(Some(cantidad)) con la cantidad contada.
+        loop {
+            let input = read_input();
+            if input.eq_ignore_ascii_case(FINISH) {
+                return None;
+            }
+            if input.is_empty() {
+                return Some(None);
+            }
+            if input == "=" {
+                return Some(Some(esperado));
+            }
+            match parse_quantity(&input) {
+                Ok(quant) if quant >= 0.0 => {
+                    return Some(Some(quant as f64));
+                }
+                Ok(_) => {
+                    println!("La cantidad contada no puede ser negativa. Vuelve a escribirla:");
+                }
+                Err(e) => {
+                    println!("{}. Vuelve a escribirla (en {}):", e, objeto.medida);
+                }
+            }
+        }
+    }
+
+    pub fn count_interactive(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        lugar: &Procedencia,
+    ) -> Result<(), BodegaError> {
+        //!Hace inventario de un lugar por la terminal: recorre los objetos del hogar por categoría pidiendo la cantidad contada de cada uno, muestra las diferencias y, si se confirma, las aplica con apply_stocktake().
+        check_permission(&sesion.usuario, &Permiso::GestionarExistencias)?;
+        let mut objects = read_objects(conn, &sesion.hogar)?;
+        objects.sort_by(|a, b| {
+            (&a.categoria.nombre, &a.nombre).cmp(&(&b.categoria.nombre, &b.nombre))
+        });
+        let stock = read_stock(conn, &objects)?;
+        println!(
+            "Inventario de {} en {}. Escribe la cantidad contada de cada objeto; \"=\" si coincide, nada para saltarlo o \"{}\" para terminar.\n",
+            get_string_name(lugar),
+            sesion.hogar.nombre,
+            FINISH
+        );
+        let mut recuentos = Vec::new();
+        for (i, objeto) in objects.iter().enumerate() {
+            let esperado = get_quantity(&stock, objeto.id, lugar);
+            println!(
+                "({}/{}) {} › {} [{}], según la base de datos x{}:",
+                i + 1,
+                objects.len(),
+                objeto.categoria.nombre,
+                objeto.nombre,
+                objeto.medida,
+                esperado
+            );
+            match ask_count(objeto, esperado) {
+                Some(Some(contado)) => recuentos.push(Recuento {
+                    objeto: objeto.clone(),
+                    esperado,
+                    contado,
+                }),
+                Some(None) => {}
+                None => break,
+            }
+        }
+        let diffs = differences(&recuentos);
+        println!(
+            "\nSe contaron {} objetos; {} coinciden con la base de datos.",
+            recuentos.len(),
+            recuentos.len() - diffs.len()
+        );
+        if diffs.is_empty() {
+            println!("No hay nada que corregir");
+            return Ok(());
+        }
+        println!(
+            "\n{:<30} {:>10} {:>10} {:>10}",
+            "Objeto", "Esperado", "Contado", "Diferencia"
+        );
+        for d in &diffs {
+            println!(
+                "{:<30} {:>10} {:>10} {:>+10} {}",
+                d.objeto.nombre,
+                d.esperado,
+                d.contado,
+                d.contado - d.esperado,
+                d.objeto.medida
+            );
+        }
+        println!("\n¿Aplicar estas {} correcciones? (s/n)", diffs.len());
+        if !read_input().to_lowercase().starts_with('s') {
+            println!("No se aplicó ninguna corrección");
+            return Ok(());
+        }
+        let corrected = apply_stocktake(conn, sesion, lugar, &diffs)?;
+        println!(
+            "Se corrigieron {} existencias y quedaron registradas como inventario",
+            corrected
+        );
+        return Ok(());
+    }
+}
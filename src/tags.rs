@@ -0,0 +1,143 @@
+///Módulo que gestiona las etiquetas libres de los objetos de cada hogar, como "sin gluten", "congelado" o "bebé". Un objeto puede tener varias, y cada etiqueta se guarda una sola vez por hogar en la tabla etiquetas.
+pub mod tags {
+    use crate::auth::auth::{check_permission, Permiso};
+    use crate::db_manager::db_manager::{read_objects, Objeto};
+    use crate::error::error::BodegaError;
+    use crate::hogar::hogar::{Hogar, Sesion};
+    use crate::selector::selector::normalize;
+    use mysql::prelude::Queryable;
+    use mysql::{params, PooledConn, TxOpts};
+    use serde::Serialize;
+
+    ///Etiqueta de los objetos de un hogar, con cuántos objetos la tienen.
+    #[derive(Clone, Serialize)]
+    pub struct Etiqueta {
+        pub id: i32,
+        pub nombre: String,
+        pub objetos: i64,
+    }
+
+    pub fn read_tags(conn: &mut PooledConn, hogar: &Hogar) -> Result<Vec<Etiqueta>, BodegaError> {
+        //!Lee las etiquetas del hogar dado, ordenadas por nombre.
+        return Ok(conn.exec_map(
+            "SELECT e.id, e.nombre, COUNT(oe.id_objeto) FROM etiquetas e
+                LEFT JOIN objetos_etiquetas oe ON oe.id_etiqueta = e.id
+                WHERE e.hogar = :hogar GROUP BY e.id, e.nombre ORDER BY e.nombre;",
+            params! {"hogar" => hogar.id},
+            |(id, nombre, objetos)| Etiqueta {
+                id,
+                nombre,
+                objetos,
+            },
+        )?);
+    }
+
+    pub fn find_tag(tags: &[Etiqueta], name: &str) -> Option<Etiqueta> {
+        //!Busca una etiqueta por su nombre, sin distinguir mayúsculas ni tildes.
+        let name = normalize(name);
+        return tags.iter().find(|e| normalize(&e.nombre) == name).cloned();
+    }
+
+    fn check_object(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        objeto: &Objeto,
+    ) -> Result<(), BodegaError> {
+        //!Comprueba que el objeto es del hogar activo.
+        if !read_objects(conn, &sesion.hogar)?
+            .iter()
+            .any(|o| o.id == objeto.id)
+        {
+            return Err(BodegaError::NoEncontrado(format!(
+                "No existe ningún objeto con id {} en {}",
+                objeto.id, sesion.hogar.nombre
+            )));
+        }
+        return Ok(());
+    }
+
+    pub fn tag_object(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        objeto: &Objeto,
+        name: &str,
+    ) -> Result<(), BodegaError> {
+        //!Pone una etiqueta a un objeto del hogar activo, si el usuario puede gestionar el catálogo. Si la etiqueta no existe en el hogar se crea, en la misma transacción.
+        check_permission(&sesion.usuario, &Permiso::GestionarCatalogo)?;
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(BodegaError::Invalido(String::from(
+                "La etiqueta no puede estar vacía",
+            )));
+        }
+        check_object(conn, sesion, objeto)?;
+        let existing = find_tag(&read_tags(conn, &sesion.hogar)?, name);
+        let mut tx = conn.start_transaction(TxOpts::default())?;
+        let id = match existing {
+            Some(e) => e.id,
+            None => {
+                tx.exec_drop(
+                    "INSERT INTO etiquetas (nombre, hogar) VALUES (:nombre, :hogar);",
+                    params! {"nombre" => name, "hogar" => sesion.hogar.id},
+                )?;
+                tx.last_insert_id().unwrap_or_default() as i32
+            }
+        };
+        tx.exec_drop(
+            "INSERT IGNORE INTO objetos_etiquetas (id_objeto, id_etiqueta) VALUES (:id, :etiqueta);",
+            params! {"id" => objeto.id, "etiqueta" => id},
+        )?;
+        tx.commit()?;
+        return Ok(());
+    }
+
+    pub fn untag_object(
+        conn: &mut PooledConn,
+        sesion: &Sesion,
+        objeto: &Objeto,
+        name: &str,
+    ) -> Result<(), BodegaError> {
+        //!Quita una etiqueta de un objeto del hogar activo, si el usuario puede gestionar el catálogo. Si ya no la tiene ningún objeto, la etiqueta se elimina.
+        check_permission(&sesion.usuario, &Permiso::GestionarCatalogo)?;
+        check_object(conn, sesion, objeto)?;
+        let tag = match find_tag(&read_tags(conn, &sesion.hogar)?, name) {
+            Some(tag) => tag,
+            None => {
+                return Err(BodegaError::NoEncontrado(format!(
+                    "No existe ninguna etiqueta {} en {}",
+                    name.trim(),
+                    sesion.hogar.nombre
+                )));
+            }
+        };
+        let mut tx = conn.start_transaction(TxOpts::default())?;
+        tx.exec_drop(
+            "DELETE FROM objetos_etiquetas WHERE id_objeto = :id AND id_etiqueta = :etiqueta;",
+            params! {"id" => objeto.id, "etiqueta" => tag.id},
+        )?;
+        if tx.affected_rows() == 0 {
+            return Err(BodegaError::NoEncontrado(format!(
+                "{} no tiene la etiqueta {}",
+                objeto.nombre, tag.nombre
+            )));
+        }
+        tx.exec_drop(
+            "DELETE FROM etiquetas WHERE id = :etiqueta
+                AND NOT EXISTS (SELECT 1 FROM objetos_etiquetas WHERE id_etiqueta = :etiqueta);",
+            params! {"etiqueta" => tag.id},
+        )?;
+        tx.commit()?;
+        return Ok(());
+    }
+
+    pub fn print_tags(tags: &[Etiqueta]) {
+        //!Muestra las etiquetas del hogar con cuántos objetos tienen cada una.
+        if tags.is_empty() {
+            println!("No hay ninguna etiqueta");
+            return;
+        }
+        for e in tags {
+            println!("{} ({} objetos)", e.nombre, e.objetos);
+        }
+    }
+}
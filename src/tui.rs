@@ -0,0 +1,687 @@
+///Módulo con la interfaz a pantalla completa: una tabla de existencias (objetos × lugares) que se recorre con el teclado, con búsqueda en vivo y diálogos para gestionar categorías y objetos.
+pub mod tui {
+    use crate::connection_manager::connection_manager::{get_conn, Conexiones};
+    use crate::db_manager::db_manager::*;
+    use crate::error::error::BodegaError;
+    use crate::hogar::hogar::Sesion;
+    use crate::selector::selector::{resolve, Resolucion};
+    use mysql::PooledConn;
+    use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+    use ratatui::layout::{Constraint, Flex, Layout, Rect};
+    use ratatui::style::{Modifier, Style, Stylize};
+    use ratatui::text::Line;
+    use ratatui::widgets::{
+        Block, Cell, Clear, List, ListItem, ListState, Paragraph, Row, Table, TableState,
+    };
+    use ratatui::{DefaultTerminal, Frame};
+
+    ///Lugares que aparecen como columnas de la tabla, en orden
+    const LOCATIONS: [Procedencia; 2] = [Procedencia::Casa, Procedencia::Tara];
+    ///Columnas de la tabla anteriores a las de los lugares
+    const FIXED_COLUMNS: usize = 3;
+    ///Atajos que se muestran en la barra inferior de la vista principal
+    const HELP: &str = "↑↓←→ mover  + añadir  - retirar  = fijar  t transladar  / buscar  n nuevo objeto  d borrar objeto  c categorías  q salir";
+
+    ///Operación de existencias que se aplica sobre la celda seleccionada.
+    #[derive(Clone, Copy)]
+    enum Accion {
+        Sumar,
+        Retirar,
+        Fijar,
+        Transladar,
+    }
+
+    ///Operación que espera confirmación antes de realizarse.
+    enum Confirmacion {
+        BorrarCategoria(Categoria),
+        BorrarObjeto(Objeto),
+    }
+
+    ///Ventana que se muestra sobre la tabla y recibe las pulsaciones mientras está abierta.
+    enum Dialogo {
+        Cantidad {
+            accion: Accion,
+            entrada: String,
+        },
+        Categorias {
+            lista: ListState,
+        },
+        NuevaCategoria {
+            nombre: String,
+            descripcion: String,
+            campo: usize,
+        },
+        NuevoObjeto {
+            nombre: String,
+            medida: String,
+            categoria: usize,
+            campo: usize,
+        },
+        Confirmar {
+            mensaje: String,
+            accion: Confirmacion,
+        },
+    }
+
+    ///Estado de la interfaz.
+    struct App<'a> {
+        conexiones: &'a Conexiones,
+        sesion: &'a Sesion,
+        objetos: Vec<Objeto>,
+        categorias: Vec<Categoria>,
+        existencias: Vec<Existencia>,
+        busqueda: String,
+        buscando: bool,
+        tabla: TableState,
+        columna: usize,
+        dialogo: Option<Dialogo>,
+        estado: String,
+        salir: bool,
+    }
+
+    pub fn run(conexiones: &Conexiones, sesion: &Sesion) -> std::io::Result<()> {
+        //!Abre la interfaz a pantalla completa sobre el conjunto de conexiones dado, en nombre del usuario que ha iniciado sesión y en su hogar activo, y restaura la terminal al salir.
+        let mut terminal = ratatui::init();
+        let mut app = App {
+            conexiones,
+            sesion,
+            objetos: Vec::new(),
+            categorias: Vec::new(),
+            existencias: Vec::new(),
+            busqueda: String::new(),
+            buscando: false,
+            tabla: TableState::default().with_selected(Some(0)),
+            columna: 0,
+            dialogo: None,
+            estado: String::from(HELP),
+            salir: false,
+        };
+        app.refresh();
+        let result = app.main_loop(&mut terminal);
+        ratatui::restore();
+        return result;
+    }
+
+    fn text_field(text: &mut String, code: KeyCode) {
+        //!Edita un campo de texto con la tecla pulsada.
+        match code {
+            KeyCode::Char(c) => text.push(c),
+            KeyCode::Backspace => {
+                text.pop();
+            }
+            _ => {}
+        }
+    }
+
+    fn centered(area: Rect, width: u16, height: u16) -> Rect {
+        //!Devuelve un rectángulo del tamaño dado centrado dentro de area.
+        let [area] = Layout::horizontal([Constraint::Length(width)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::vertical([Constraint::Length(height)])
+            .flex(Flex::Center)
+            .areas(area);
+        return area;
+    }
+
+    fn field_line(label: &str, value: &str, selected: bool) -> String {
+        //!Formatea una línea de un formulario, marcando el campo que se está editando.
+        return format!(
+            "{} {}: {}{}",
+            if selected { ">" } else { " " },
+            label,
+            value,
+            if selected { "_" } else { "" }
+        );
+    }
+
+    impl App<'_> {
+        fn with_connection<T>(
+            &self,
+            operation: impl FnOnce(&mut PooledConn) -> Result<T, BodegaError>,
+        ) -> Result<T, BodegaError> {
+            //!Toma una conexión del conjunto y hace con ella la operación dada.
+            let mut connection = get_conn(self.conexiones)?;
+            return operation(&mut connection);
+        }
+
+        fn refresh(&mut self) {
+            //!Vuelve a leer categorías, objetos y existencias del hogar activo. Si falla, se conservan los datos anteriores y se muestra el error.
+            let hogar = &self.sesion.hogar;
+            let read = self.with_connection(|connection| {
+                let categorias = read_categories(connection, hogar)?;
+                let objetos = read_objects(connection, hogar)?;
+                let existencias = read_stock(connection, &objetos)?;
+                return Ok((categorias, objetos, existencias));
+            });
+            match read {
+                Ok((categorias, objetos, existencias)) => {
+                    self.categorias = categorias;
+                    self.objetos = objetos;
+                    self.existencias = existencias;
+                }
+                Err(e) => {
+                    self.estado = format!("No se pudieron leer los datos: {}", e);
+                }
+            }
+            let len = self.visible().len();
+            if self.tabla.selected().unwrap_or(0) >= len {
+                self.tabla.select(Some(len.saturating_sub(1)));
+            }
+        }
+
+        fn visible(&self) -> Vec<Objeto> {
+            //!Objetos que se muestran en la tabla: todos, o los que coinciden con la búsqueda usando la misma resolución que el menú.
+            if self.busqueda.trim().is_empty() {
+                return self.objetos.clone();
+            }
+            return match resolve(&self.busqueda, &self.objetos) {
+                Resolucion::Unico(o) => vec![o],
+                Resolucion::Varios(list) => list,
+                Resolucion::Ninguno => Vec::new(),
+            };
+        }
+
+        fn selected(&self) -> Option<Objeto> {
+            //!Objeto de la fila seleccionada.
+            return self
+                .tabla
+                .selected()
+                .and_then(|i| self.visible().get(i).cloned());
+        }
+
+        fn main_loop(&mut self, terminal: &mut DefaultTerminal) -> std::io::Result<()> {
+            //!Dibuja la interfaz y atiende el teclado hasta que se pide salir.
+            while !self.salir {
+                terminal.draw(|frame| self.draw(frame))?;
+                if let Event::Key(key) = event::read()? {
+                    if key.kind == KeyEventKind::Press {
+                        self.handle_key(key.code);
+                    }
+                }
+            }
+            return Ok(());
+        }
+
+        fn handle_key(&mut self, code: KeyCode) {
+            //!Envía la tecla pulsada al diálogo abierto, a la búsqueda o a la vista principal.
+            if let Some(dialogo) = self.dialogo.take() {
+                self.dialogo = self.handle_dialog(dialogo, code);
+                return;
+            }
+            if self.buscando {
+                match code {
+                    KeyCode::Enter | KeyCode::Esc => {
+                        self.buscando = false;
+                        if code == KeyCode::Esc {
+                            self.busqueda.clear();
+                        }
+                    }
+                    _ => text_field(&mut self.busqueda, code),
+                }
+                self.tabla.select(Some(0));
+                return;
+            }
+            let len = self.visible().len();
+            match code {
+                KeyCode::Char('q') | KeyCode::Esc => self.salir = true,
+                KeyCode::Up | KeyCode::Char('k') => self.tabla.select_previous(),
+                KeyCode::Down | KeyCode::Char('j')
+                    if self.tabla.selected().unwrap_or(0) + 1 < len =>
+                {
+                    self.tabla.select_next()
+                }
+                KeyCode::Left | KeyCode::Char('h') => self.columna = self.columna.saturating_sub(1),
+                KeyCode::Right | KeyCode::Char('l') => {
+                    self.columna = (self.columna + 1).min(LOCATIONS.len() - 1)
+                }
+                KeyCode::Char('/') => self.buscando = true,
+                KeyCode::Char('+') | KeyCode::Char('a') => self.ask_quantity(Accion::Sumar),
+                KeyCode::Char('-') | KeyCode::Char('r') => self.ask_quantity(Accion::Retirar),
+                KeyCode::Char('=') | KeyCode::Char('s') => self.ask_quantity(Accion::Fijar),
+                KeyCode::Char('t') => self.ask_quantity(Accion::Transladar),
+                KeyCode::Char('c') => {
+                    self.dialogo = Some(Dialogo::Categorias {
+                        lista: ListState::default().with_selected(Some(0)),
+                    })
+                }
+                KeyCode::Char('n') => {
+                    if self.categorias.is_empty() {
+                        self.estado =
+                            String::from("Crea primero una categoría (tecla c) para el objeto");
+                    } else {
+                        self.dialogo = Some(Dialogo::NuevoObjeto {
+                            nombre: String::new(),
+                            medida: String::new(),
+                            categoria: 0,
+                            campo: 0,
+                        });
+                    }
+                }
+                KeyCode::Char('d') => {
+                    if let Some(obj) = self.selected() {
+                        self.dialogo = Some(Dialogo::Confirmar {
+                            mensaje: format!("¿Eliminar el objeto {}?", obj.nombre),
+                            accion: Confirmacion::BorrarObjeto(obj),
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        fn ask_quantity(&mut self, accion: Accion) {
+            //!Abre el diálogo de cantidad para la celda seleccionada, si hay algún objeto seleccionado.
+            if self.selected().is_some() {
+                self.dialogo = Some(Dialogo::Cantidad {
+                    accion,
+                    entrada: String::new(),
+                });
+            }
+        }
+
+        fn apply(&mut self, accion: Accion, entrada: &str) {
+            //!Aplica la operación sobre el objeto y el lugar seleccionados. Si no se escribió ninguna cantidad, se usa 1.
+            let obj = match self.selected() {
+                Some(obj) => obj,
+                None => return,
+            };
+            let location = &LOCATIONS[self.columna];
+            let quant = if entrada.trim().is_empty() {
+                Ok(1.0)
+            } else {
+                parse_quantity(&entrada.replace(',', "."))
+            };
+            match quant {
+                Ok(quant) => {
+                    let result = match accion {
+                        Accion::Sumar => self.with_connection(|connection| {
+                            adjust_stock(
+                                connection,
+                                self.sesion,
+                                obj.id,
+                                false,
+                                quant,
+                                location,
+                                None,
+                            )
+                        }),
+                        Accion::Retirar => self.with_connection(|connection| {
+                            adjust_stock(
+                                connection,
+                                self.sesion,
+                                obj.id,
+                                false,
+                                -quant,
+                                location,
+                                None,
+                            )
+                        }),
+                        Accion::Fijar => self.with_connection(|connection| {
+                            adjust_stock(
+                                connection,
+                                self.sesion,
+                                obj.id,
+                                true,
+                                quant,
+                                location,
+                                None,
+                            )
+                        }),
+                        Accion::Transladar => self.with_connection(|connection| {
+                            move_stock(connection, self.sesion, obj.id, quant, location)
+                        }),
+                    };
+                    self.estado = match result {
+                        Ok(()) => format!("{} actualizado", obj.nombre),
+                        Err(e) => format!("Ocurrió un error al actualizar la base de datos: {}", e),
+                    };
+                    self.refresh();
+                }
+                Err(e) => {
+                    self.estado = e.to_string();
+                }
+            }
+        }
+
+        fn confirm(&mut self, accion: Confirmacion) {
+            //!Realiza una operación que ya ha sido confirmada.
+            self.estado = match accion {
+                Confirmacion::BorrarCategoria(cat) => {
+                    match self.with_connection(|connection| {
+                        delete_category(connection, self.sesion, cat.id)
+                    }) {
+                        Ok(()) => format!(
+                            "La categoría {} fue eliminada satisfactoriamente",
+                            cat.nombre
+                        ),
+                        Err(e) => format!("Ocurrió un error al eliminar la categoría: {}", e),
+                    }
+                }
+                Confirmacion::BorrarObjeto(obj) => {
+                    match self.with_connection(|connection| {
+                        delete_object(connection, self.sesion, obj.id)
+                    }) {
+                        Ok(()) => format!("Objeto {} eliminado satisfactoriamente", obj.nombre),
+                        Err(e) => format!("Ocurrió un error al eliminar el objeto: {}", e),
+                    }
+                }
+            };
+            self.refresh();
+        }
+
+        fn handle_dialog(&mut self, dialogo: Dialogo, code: KeyCode) -> Option<Dialogo> {
+            //!Atiende una tecla dentro de un diálogo. Devuelve el diálogo que debe quedar abierto, o None si se cierra.
+            if code == KeyCode::Esc {
+                return None;
+            }
+            match dialogo {
+                Dialogo::Cantidad {
+                    accion,
+                    mut entrada,
+                } => {
+                    if code == KeyCode::Enter {
+                        self.apply(accion, &entrada);
+                        return None;
+                    }
+                    text_field(&mut entrada, code);
+                    return Some(Dialogo::Cantidad { accion, entrada });
+                }
+                Dialogo::Categorias { mut lista } => {
+                    match code {
+                        KeyCode::Up | KeyCode::Char('k') => lista.select_previous(),
+                        KeyCode::Down | KeyCode::Char('j')
+                            if lista.selected().unwrap_or(0) + 1 < self.categorias.len() =>
+                        {
+                            lista.select_next()
+                        }
+                        KeyCode::Char('n') => {
+                            return Some(Dialogo::NuevaCategoria {
+                                nombre: String::new(),
+                                descripcion: String::new(),
+                                campo: 0,
+                            });
+                        }
+                        KeyCode::Char('d') => {
+                            if let Some(cat) = lista
+                                .selected()
+                                .and_then(|i| self.categorias.get(i).cloned())
+                            {
+                                return Some(Dialogo::Confirmar {
+                                    mensaje: format!("¿Eliminar la categoría {}?", cat.nombre),
+                                    accion: Confirmacion::BorrarCategoria(cat),
+                                });
+                            }
+                        }
+                        _ => {}
+                    }
+                    return Some(Dialogo::Categorias { lista });
+                }
+                Dialogo::NuevaCategoria {
+                    mut nombre,
+                    mut descripcion,
+                    campo,
+                } => {
+                    match code {
+                        KeyCode::Tab | KeyCode::Down | KeyCode::Up => {
+                            return Some(Dialogo::NuevaCategoria {
+                                nombre,
+                                descripcion,
+                                campo: 1 - campo,
+                            });
+                        }
+                        KeyCode::Enter => {
+                            self.estado = match self.with_connection(|connection| {
+                                insert_category(connection, self.sesion, nombre, descripcion, None)
+                            }) {
+                                Ok(_) => String::from("La categoría se creó satisfactoriamente"),
+                                Err(e) => {
+                                    format!("Ocurrió un error al crear la categoría: {}", e)
+                                }
+                            };
+                            self.refresh();
+                            return Some(Dialogo::Categorias {
+                                lista: ListState::default().with_selected(Some(0)),
+                            });
+                        }
+                        _ => {
+                            if campo == 0 {
+                                text_field(&mut nombre, code);
+                            } else {
+                                text_field(&mut descripcion, code);
+                            }
+                        }
+                    }
+                    return Some(Dialogo::NuevaCategoria {
+                        nombre,
+                        descripcion,
+                        campo,
+                    });
+                }
+                Dialogo::NuevoObjeto {
+                    mut nombre,
+                    mut medida,
+                    mut categoria,
+                    mut campo,
+                } => {
+                    match code {
+                        KeyCode::Tab | KeyCode::Down => campo = (campo + 1) % 3,
+                        KeyCode::Up => campo = (campo + 2) % 3,
+                        KeyCode::Left if campo == 2 => categoria = categoria.saturating_sub(1),
+                        KeyCode::Right if campo == 2 => {
+                            categoria = (categoria + 1).min(self.categorias.len() - 1)
+                        }
+                        KeyCode::Enter => {
+                            if let Some(cat) = self.categorias.get(categoria).cloned() {
+                                self.estado = match self.with_connection(|connection| {
+                                    insert_object(connection, self.sesion, cat, nombre, medida)
+                                }) {
+                                    Ok(_) => String::from("El objeto se creó satisfactoriamente"),
+                                    Err(e) => {
+                                        format!("Ocurrió un error al crear el objeto: {}", e)
+                                    }
+                                };
+                                self.refresh();
+                            }
+                            return None;
+                        }
+                        _ => {
+                            if campo == 0 {
+                                text_field(&mut nombre, code);
+                            } else if campo == 1 {
+                                text_field(&mut medida, code);
+                            }
+                        }
+                    }
+                    return Some(Dialogo::NuevoObjeto {
+                        nombre,
+                        medida,
+                        categoria,
+                        campo,
+                    });
+                }
+                Dialogo::Confirmar { mensaje, accion } => match code {
+                    KeyCode::Char('s') | KeyCode::Char('S') | KeyCode::Enter => {
+                        self.confirm(accion);
+                        return None;
+                    }
+                    KeyCode::Char('n') | KeyCode::Char('N') => return None,
+                    _ => return Some(Dialogo::Confirmar { mensaje, accion }),
+                },
+            }
+        }
+
+        fn draw(&mut self, frame: &mut Frame) {
+            //!Dibuja la tabla de existencias, la barra de búsqueda, la barra de estado y el diálogo abierto, si lo hay.
+            let [search_area, table_area, status_area] = Layout::vertical([
+                Constraint::Length(3),
+                Constraint::Min(3),
+                Constraint::Length(3),
+            ])
+            .areas(frame.area());
+
+            let search = if self.buscando {
+                format!("{}_", self.busqueda)
+            } else {
+                self.busqueda.clone()
+            };
+            let search_block = Block::bordered().title(" Buscar (/) ");
+            let search_block = if self.buscando {
+                search_block.bold()
+            } else {
+                search_block
+            };
+            frame.render_widget(Paragraph::new(search).block(search_block), search_area);
+
+            let mut header = vec![
+                Cell::from("Objeto"),
+                Cell::from("Categoría"),
+                Cell::from("Medida"),
+            ];
+            header.extend(LOCATIONS.iter().map(|l| Cell::from(get_string_name(l))));
+            let rows: Vec<Row> = self
+                .visible()
+                .iter()
+                .map(|o| {
+                    let mut cells = vec![
+                        Cell::from(o.nombre.clone()),
+                        Cell::from(o.categoria.nombre.clone()),
+                        Cell::from(o.medida.clone()),
+                    ];
+                    cells.extend(LOCATIONS.iter().map(|l| {
+                        Cell::from(format!("{}", get_quantity(&self.existencias, o.id, l)))
+                    }));
+                    Row::new(cells)
+                })
+                .collect();
+            let mut widths = vec![
+                Constraint::Fill(3),
+                Constraint::Fill(2),
+                Constraint::Fill(1),
+            ];
+            widths.extend(LOCATIONS.iter().map(|_| Constraint::Length(10)));
+            let table = Table::new(rows, widths)
+                .header(Row::new(header).bold())
+                .block(
+                    Block::bordered().title(" EXISTENCIAS ").title(
+                        Line::from(format!(
+                            " {} @ {} ",
+                            self.sesion.usuario.nombre, self.sesion.hogar.nombre
+                        ))
+                        .right_aligned(),
+                    ),
+                )
+                .row_highlight_style(Style::new().add_modifier(Modifier::BOLD))
+                .cell_highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+                .highlight_symbol("> ");
+            self.tabla.select_column(Some(FIXED_COLUMNS + self.columna));
+            frame.render_stateful_widget(table, table_area, &mut self.tabla);
+
+            frame.render_widget(
+                Paragraph::new(self.estado.clone()).block(Block::bordered()),
+                status_area,
+            );
+
+            let selected = self.selected();
+            if let Some(dialogo) = self.dialogo.as_mut() {
+                draw_dialog(frame, dialogo, &self.categorias, &selected, self.columna);
+            }
+        }
+    }
+
+    fn draw_dialog(
+        frame: &mut Frame,
+        dialogo: &mut Dialogo,
+        categorias: &[Categoria],
+        selected: &Option<Objeto>,
+        columna: usize,
+    ) {
+        //!Dibuja el diálogo abierto centrado sobre la tabla.
+        let area = centered(frame.area(), 60, 12);
+        frame.render_widget(Clear, area);
+        match dialogo {
+            Dialogo::Cantidad { accion, entrada } => {
+                let location = &LOCATIONS[columna];
+                let title = match accion {
+                    Accion::Sumar => format!(" Añadir en {} ", get_string_name(location)),
+                    Accion::Retirar => format!(" Retirar de {} ", get_string_name(location)),
+                    Accion::Fijar => format!(" Fijar en {} ", get_string_name(location)),
+                    Accion::Transladar => format!(
+                        " Transladar {} -> {} ",
+                        get_string_name(location),
+                        get_string_name(&contrary(location))
+                    ),
+                };
+                let (nombre, medida) = match selected {
+                    Some(o) => (o.nombre.clone(), o.medida.clone()),
+                    None => (String::new(), String::new()),
+                };
+                let text = format!(
+                    "{}\n\nCantidad ({}): {}_\n\nEnter para aceptar (vacío = 1), Esc para cancelar",
+                    nombre, medida, entrada
+                );
+                frame.render_widget(
+                    Paragraph::new(text).block(Block::bordered().title(title)),
+                    area,
+                );
+            }
+            Dialogo::Categorias { lista } => {
+                let items: Vec<ListItem> = categorias
+                    .iter()
+                    .map(|c| ListItem::new(format!("({}) {}: {}", c.id, c.nombre, c.descripcion)))
+                    .collect();
+                let list = List::new(items)
+                    .block(
+                        Block::bordered()
+                            .title(" Categorías ")
+                            .title_bottom(" n nueva  d eliminar  Esc cerrar "),
+                    )
+                    .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+                frame.render_stateful_widget(list, area, lista);
+            }
+            Dialogo::NuevaCategoria {
+                nombre,
+                descripcion,
+                campo,
+            } => {
+                let text = format!(
+                    "{}\n{}\n\nTab cambia de campo, Enter guarda, Esc cancela",
+                    field_line("Nombre", nombre, *campo == 0),
+                    field_line("Descripción", descripcion, *campo == 1)
+                );
+                frame.render_widget(
+                    Paragraph::new(text).block(Block::bordered().title(" Nueva categoría ")),
+                    area,
+                );
+            }
+            Dialogo::NuevoObjeto {
+                nombre,
+                medida,
+                categoria,
+                campo,
+            } => {
+                let cat = categorias
+                    .get(*categoria)
+                    .map(|c| format!("< {} >", c.nombre))
+                    .unwrap_or_default();
+                let text = format!(
+                    "{}\n{}\n{}\n\nTab cambia de campo, ←→ cambia la categoría, Enter guarda, Esc cancela",
+                    field_line("Nombre", nombre, *campo == 0),
+                    field_line("Medida", medida, *campo == 1),
+                    field_line("Categoría", &cat, *campo == 2)
+                );
+                frame.render_widget(
+                    Paragraph::new(text).block(Block::bordered().title(" Nuevo objeto ")),
+                    area,
+                );
+            }
+            Dialogo::Confirmar { mensaje, .. } => {
+                let text = format!("{}\n\nS para aceptar, N o Esc para cancelar", mensaje);
+                frame.render_widget(
+                    Paragraph::new(text).block(Block::bordered().title(" Confirmar ")),
+                    area,
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,336 @@
+///Módulo con la interfaz web para móviles: páginas generadas en el servidor, sin recursos externos, para consultar las existencias de cada lugar y añadir, retirar o transladar con un toque.
+pub mod web {
+    use crate::db_manager::db_manager::*;
+    use crate::error::error::BodegaError;
+    use crate::hogar::hogar::{read_user_households, Sesion};
+    use crate::selector::selector::{resolve, Resolucion};
+    use crate::server::server::{error_status, read_raw_body, stock_rows, Respuesta};
+    use crate::TITLE;
+    use mysql::PooledConn;
+    use std::collections::HashMap;
+    use tiny_http::{Header, Method, Request, Response};
+
+    ///Hoja de estilos de las páginas, incluida en cada una para no depender de nada externo
+    const STYLE: &str = "
+        body { font-family: sans-serif; margin: 0; padding: 0.5em; background: #faf7f2; color: #222; }
+        h1 { font-size: 1.3em; text-align: center; margin: 0.3em 0; }
+        nav { display: flex; gap: 0.5em; margin-bottom: 0.5em; }
+        nav a { flex: 1; text-align: center; padding: 0.6em; border-radius: 0.4em; background: #e4ddd0; color: #222; text-decoration: none; }
+        nav a.sel { background: #7a4b2a; color: #fff; }
+        form.buscar { display: flex; gap: 0.3em; margin-bottom: 0.5em; }
+        form.buscar input { flex: 1; }
+        input, button { font-size: 1.1em; padding: 0.4em; }
+        .mensaje { padding: 0.5em; border-radius: 0.4em; background: #dfeedd; }
+        .error { background: #f4d6d2; }
+        .objeto { background: #fff; border-radius: 0.4em; padding: 0.5em; margin-bottom: 0.5em; }
+        .objeto.vacio { opacity: 0.6; }
+        .fila { display: flex; justify-content: space-between; align-items: baseline; }
+        .cantidad { font-size: 1.3em; font-weight: bold; }
+        small { color: #666; }
+        .acciones { display: flex; gap: 0.3em; margin-top: 0.4em; }
+        .acciones input { width: 4.5em; }
+        .acciones button { min-width: 2.6em; }
+    ";
+
+    pub fn handle(
+        connection: &mut PooledConn,
+        sesion: &Sesion,
+        request: &mut Request,
+    ) -> Respuesta {
+        //!Atiende una petición de la interfaz web en nombre del usuario autenticado y en el hogar de la petición. Los errores se muestran dentro de la propia página.
+        let url = String::from(request.url());
+        let (path, query) = url.split_once('?').unwrap_or((url.as_str(), ""));
+        let params = parse_form(query.as_bytes());
+        match (request.method().clone(), path) {
+            (Method::Get, "/") => {
+                return match stock_page(connection, sesion, &params) {
+                    Ok(html) => html_response(200, &html),
+                    Err(e) => html_response(
+                        error_status(&e),
+                        &layout(&format!(
+                            "<p class=\"mensaje error\">{}</p><p><a href=\"/\">Volver</a></p>",
+                            escape(&e.to_string())
+                        )),
+                    ),
+                };
+            }
+            (Method::Post, "/movimiento") => {
+                if !same_origin(request) {
+                    return html_response(
+                        403,
+                        &layout("<p class=\"mensaje error\">El formulario no se envió desde esta página.</p><p><a href=\"/\">Volver</a></p>"),
+                    );
+                }
+                let body = match read_raw_body(request) {
+                    Ok(body) => body,
+                    Err(e) => {
+                        return html_response(
+                            error_status(&e),
+                            &layout(&format!(
+                                "<p class=\"mensaje error\">No se pudo leer el formulario: {}</p><p><a href=\"/\">Volver</a></p>",
+                                escape(&e.to_string())
+                            )),
+                        );
+                    }
+                };
+                let form = parse_form(&body);
+                let mut back = form_urlencoded::Serializer::new(String::new());
+                back.append_pair("hogar", &sesion.hogar.id.to_string());
+                back.append_pair("lugar", field(&form, "lugar"));
+                back.append_pair("q", field(&form, "q"));
+                match apply(connection, sesion, &form) {
+                    Ok(mensaje) => back.append_pair("mensaje", &mensaje),
+                    Err(e) => back.append_pair("error", &e.to_string()),
+                };
+                return redirect(&format!("/?{}", back.finish()));
+            }
+            _ => {
+                return html_response(
+                    404,
+                    &layout("<p>La página no existe.</p><p><a href=\"/\">Volver</a></p>"),
+                );
+            }
+        }
+    }
+
+    fn header<'a>(request: &'a Request, name: &'static str) -> Option<&'a str> {
+        //!Obtiene el valor de una cabecera de la petición.
+        return request
+            .headers()
+            .iter()
+            .find(|h| h.field.equiv(name))
+            .map(|h| h.value.as_str());
+    }
+
+    fn same_origin(request: &Request) -> bool {
+        //!Comprueba que un formulario se ha enviado desde las propias páginas del servidor, comparando el equipo de la cabecera Origin (o, si no está, de Referer) con el de Host. El navegador reenvía solo las credenciales a cualquier página que le haga enviar un formulario aquí, así que sin esto otra web podría mover existencias en nombre del usuario.
+        let host = match header(request, "Host") {
+            Some(host) => host,
+            None => return false,
+        };
+        let source = match header(request, "Origin").or(header(request, "Referer")) {
+            Some(source) => source,
+            None => return false,
+        };
+        let source = source
+            .strip_prefix("http://")
+            .or(source.strip_prefix("https://"))
+            .unwrap_or("");
+        return source.split('/').next() == Some(host);
+    }
+
+    fn parse_form(data: &[u8]) -> HashMap<String, String> {
+        //!Lee los campos de un formulario o de una consulta codificados como application/x-www-form-urlencoded.
+        return form_urlencoded::parse(data).into_owned().collect();
+    }
+
+    fn field<'a>(form: &'a HashMap<String, String>, name: &str) -> &'a str {
+        //!Obtiene un campo del formulario, o una cadena vacía si no está.
+        return form.get(name).map(String::as_str).unwrap_or("");
+    }
+
+    fn escape(text: &str) -> String {
+        //!Escapa un texto para incluirlo en el HTML.
+        return text
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+            .replace('\'', "&#39;");
+    }
+
+    fn html_response(status: u16, html: &str) -> Respuesta {
+        //!Construye una respuesta HTML con el código dado.
+        return Response::from_data(html.as_bytes().to_vec())
+            .with_status_code(status)
+            .with_header(Header::from_bytes("Content-Type", "text/html; charset=utf-8").unwrap());
+    }
+
+    fn redirect(location: &str) -> Respuesta {
+        //!Construye una redirección a la dirección dada, para que recargar la página no repita el formulario.
+        return Response::from_data(Vec::new())
+            .with_status_code(303)
+            .with_header(Header::from_bytes("Location", location).unwrap());
+    }
+
+    fn layout(content: &str) -> String {
+        //!Envuelve el contenido en una página completa con el título y los estilos.
+        return format!(
+            "<!DOCTYPE html><html lang=\"es\"><head><meta charset=\"utf-8\">\
+            <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\
+            <title>{title}</title><style>{style}</style></head>\
+            <body><h1>{title}</h1>{content}</body></html>",
+            title = escape(TITLE),
+            style = STYLE,
+            content = content
+        );
+    }
+
+    fn apply(
+        connection: &mut PooledConn,
+        sesion: &Sesion,
+        form: &HashMap<String, String>,
+    ) -> Result<String, BodegaError> {
+        //!Aplica el movimiento pedido en el formulario con las mismas validaciones que las opciones 2 y 3 del menú. Devuelve el mensaje a mostrar.
+        let id = field(form, "objeto")
+            .parse::<i32>()
+            .map_err(|_| BodegaError::Invalido(String::from("No se indicó ningún objeto")))?;
+        let location = parse_location(field(form, "lugar")).ok_or(BodegaError::Invalido(
+            String::from("No se indicó un lugar válido"),
+        ))?;
+        let quant = parse_quantity(field(form, "cantidad"))?;
+        match field(form, "accion") {
+            "sumar" => adjust_stock(connection, sesion, id, false, quant, &location, None)?,
+            "retirar" => adjust_stock(connection, sesion, id, false, -quant, &location, None)?,
+            "transladar" => move_stock(connection, sesion, id, quant, &location)?,
+            other => {
+                return Err(BodegaError::Invalido(format!(
+                    "La acción \"{}\" no existe",
+                    other
+                )));
+            }
+        }
+        let obj = get_object_by_id(id, read_objects(connection, &sesion.hogar)?).ok_or(
+            BodegaError::NoEncontrado(format!("No existe ningún objeto con id {}", id)),
+        )?;
+        return Ok(match field(form, "accion") {
+            "sumar" => format!(
+                "+{} {} de {} en {}",
+                quant,
+                obj.medida,
+                obj.nombre,
+                get_string_name(&location)
+            ),
+            "retirar" => format!(
+                "-{} {} de {} en {}",
+                quant,
+                obj.medida,
+                obj.nombre,
+                get_string_name(&location)
+            ),
+            _ => format!(
+                "{} {} de {}: {} -> {}",
+                quant,
+                obj.medida,
+                obj.nombre,
+                get_string_name(&location),
+                get_string_name(&contrary(&location))
+            ),
+        });
+    }
+
+    fn stock_page(
+        connection: &mut PooledConn,
+        sesion: &Sesion,
+        params: &HashMap<String, String>,
+    ) -> Result<String, BodegaError> {
+        //!Genera la página con las existencias de un lugar del hogar activo, con un buscador y los botones de cada objeto. Si el usuario es miembro de varios hogares, también permite cambiar de hogar.
+        let location = parse_location(field(params, "lugar")).unwrap_or(Procedencia::Casa);
+        let other = contrary(&location);
+        let lugar = get_string_name(&location).to_lowercase();
+        let search = field(params, "q");
+        let hogar = sesion.hogar.id;
+        let objects = read_objects(connection, &sesion.hogar)?;
+        let mut objects = if search.trim().is_empty() {
+            objects
+        } else {
+            match resolve(search, &objects) {
+                Resolucion::Unico(o) => vec![o],
+                Resolucion::Varios(list) => list,
+                Resolucion::Ninguno => Vec::new(),
+            }
+        };
+        objects.sort_by(|a, b| {
+            (&a.categoria.nombre, &a.nombre).cmp(&(&b.categoria.nombre, &b.nombre))
+        });
+
+        let mut html = String::new();
+        let households = read_user_households(connection, &sesion.usuario)?;
+        if households.len() > 1 {
+            html.push_str("<nav>");
+            for h in households {
+                html.push_str(&format!(
+                    "<a href=\"/?hogar={}&lugar={}\"{}>{}</a>",
+                    h.id,
+                    lugar,
+                    if h.id == hogar { " class=\"sel\"" } else { "" },
+                    escape(&h.nombre)
+                ));
+            }
+            html.push_str("</nav>");
+        }
+        html.push_str("<nav>");
+        for l in Procedencia::all() {
+            html.push_str(&format!(
+                "<a href=\"/?hogar={}&lugar={}\"{}>{}</a>",
+                hogar,
+                get_string_name(&l).to_lowercase(),
+                if l == location { " class=\"sel\"" } else { "" },
+                get_string_name(&l)
+            ));
+        }
+        html.push_str("</nav>");
+        html.push_str(&format!(
+            "<p><small>Sesión: {} · {}</small></p>",
+            escape(&sesion.usuario.nombre),
+            escape(&sesion.hogar.nombre)
+        ));
+        html.push_str(&format!(
+            "<form class=\"buscar\" method=\"get\" action=\"/\">\
+            <input type=\"hidden\" name=\"hogar\" value=\"{}\">\
+            <input type=\"hidden\" name=\"lugar\" value=\"{}\">\
+            <input type=\"search\" name=\"q\" value=\"{}\" placeholder=\"Buscar objeto\">\
+            <button>Buscar</button></form>",
+            hogar,
+            lugar,
+            escape(search)
+        ));
+        if !field(params, "mensaje").is_empty() {
+            html.push_str(&format!(
+                "<p class=\"mensaje\">{}</p>",
+                escape(field(params, "mensaje"))
+            ));
+        }
+        if !field(params, "error").is_empty() {
+            html.push_str(&format!(
+                "<p class=\"mensaje error\">{}</p>",
+                escape(field(params, "error"))
+            ));
+        }
+        if objects.is_empty() {
+            html.push_str("<p>No hay objetos que mostrar.</p>");
+        }
+        for row in stock_rows(connection, objects)? {
+            let (here, there) = match location {
+                Procedencia::Casa => (row.casa, row.tara),
+                Procedencia::Tara => (row.tara, row.casa),
+            };
+            html.push_str(&format!(
+                "<div class=\"objeto{vacio}\"><div class=\"fila\">\
+                <span>{nombre}<br><small>{categoria} · x{alli} {medida} en {otro}</small></span>\
+                <span class=\"cantidad\">x{aqui} {medida}</span></div>\
+                <form class=\"acciones\" method=\"post\" action=\"/movimiento?hogar={hogar}\">\
+                <input type=\"hidden\" name=\"objeto\" value=\"{id}\">\
+                <input type=\"hidden\" name=\"lugar\" value=\"{lugar}\">\
+                <input type=\"hidden\" name=\"q\" value=\"{q}\">\
+                <input type=\"number\" name=\"cantidad\" value=\"1\" min=\"0\" step=\"any\" inputmode=\"decimal\">\
+                <button name=\"accion\" value=\"retirar\">−</button>\
+                <button name=\"accion\" value=\"sumar\">+</button>\
+                <button name=\"accion\" value=\"transladar\">→ {otro}</button>\
+                </form></div>",
+                vacio = if here <= EPSILON { " vacio" } else { "" },
+                nombre = escape(&row.objeto.nombre),
+                categoria = escape(&row.objeto.categoria.nombre),
+                medida = escape(&row.objeto.medida),
+                aqui = here,
+                alli = there,
+                otro = get_string_name(&other),
+                id = row.objeto.id,
+                hogar = hogar,
+                lugar = lugar,
+                q = escape(search)
+            ));
+        }
+        return Ok(layout(&html));
+    }
+}